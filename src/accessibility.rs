@@ -0,0 +1,243 @@
+//! Mermaid `accTitle` and `accDescr` statements
+//!
+//! ```text
+//! sequenceDiagram
+//!     accTitle: Login flow
+//!     accDescr {
+//!         The browser posts credentials
+//!         and the server answers with a session.
+//!     }
+//!     Browser->>Server: POST /login
+//! ```
+//!
+//! Later statements replace earlier ones. The rendered SVG carries the title
+//! and description as `<title>` and `<desc>` elements referenced by
+//! `aria-labelledby` and `aria-describedby`.
+
+/// Accessible title and description of a diagram
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Accessibility {
+    /// Short title (`accTitle: ...`)
+    pub title: Option<String>,
+    /// Longer description (`accDescr: ...` or `accDescr { ... }`)
+    pub description: Option<String>,
+    warnings: Vec<String>,
+}
+
+impl Accessibility {
+    /// Returns problems found while extracting the statements
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Handles `accTitle: <title>`, given the text after the keyword
+    pub(crate) fn add_title(&mut self, rest: &str) -> bool {
+        match rest.trim_start().strip_prefix(':') {
+            Some(title) => {
+                self.title = non_empty(title);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Handles `accDescr: <text>` and `accDescr { ... }`, given the text
+    /// after the keyword
+    ///
+    /// Returns the block if it continues on the next lines. `line` is the
+    /// whole statement line, `number` its line number and `following` the
+    /// lines after it, which tell whether the block is ever closed.
+    pub(crate) fn add_description(
+        &mut self,
+        rest: &str,
+        line: &str,
+        number: usize,
+        following: &[&str],
+    ) -> Option<DescriptionBlock> {
+        let rest = rest.trim_start();
+        if let Some(description) = rest.strip_prefix(':') {
+            self.description = non_empty(description);
+            return None;
+        }
+        let body = rest.strip_prefix('{')?;
+        if let Some(end) = closing_brace(body) {
+            self.description = non_empty(&body[..end]);
+            return None;
+        }
+
+        let terminated = following.iter().any(|line| closing_brace(line).is_some());
+        if !terminated {
+            self.warnings.push(format!(
+                "accDescr block on line {} is missing its closing '}}'",
+                number
+            ));
+        }
+        Some(DescriptionBlock {
+            lines: vec![body.trim().to_string()],
+            indentation: indentation(line),
+            terminated,
+            closed: false,
+        })
+    }
+}
+
+/// The lines of a multi-line `accDescr { ... }` block
+///
+/// Lines are trimmed and joined with newlines. The block ends at a `}`
+/// that closes no `{` of its line; without one it ends at the first line
+/// indented no deeper than `accDescr {`.
+#[derive(Debug, Clone)]
+pub(crate) struct DescriptionBlock {
+    lines: Vec<String>,
+    /// Indentation of the `accDescr {` line
+    indentation: usize,
+    /// Whether a later line closes the block
+    terminated: bool,
+    closed: bool,
+}
+
+impl DescriptionBlock {
+    /// Adds the next line, or returns `false` if the block ended before it
+    pub(crate) fn push(&mut self, line: &str) -> bool {
+        let statement = line.trim();
+        if let Some(end) = closing_brace(statement) {
+            self.lines.push(statement[..end].trim().to_string());
+            self.closed = true;
+        } else if self.terminated || statement.is_empty() || indentation(line) > self.indentation {
+            self.lines.push(statement.to_string());
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Returns `true` once the closing `}` was added
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Returns the description, or `None` if the block is empty
+    pub(crate) fn text(&self) -> Option<String> {
+        non_empty(&self.lines.join("\n"))
+    }
+}
+
+/// Returns the position of the `}` that closes a description block
+///
+/// Braces opened on the line pair up with later ones, so `Returns {id}`
+/// does not close the block.
+fn closing_brace(line: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (index, c) in line.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(index),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Width of a line's leading whitespace
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Trims non-empty lines and joins them, or `None` if nothing is left
+fn non_empty(text: &str) -> Option<String> {
+    let text = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    (!text.is_empty()).then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::statements::{extract_statements, Statements};
+
+    #[test]
+    fn test_single_line_statements() {
+        let input = "sequenceDiagram\n    accTitle: Login flow\n    accDescr: Credentials are checked\n    A->>B: Hi\n";
+        let (source, Statements { accessibility, .. }) = extract_statements(input);
+
+        assert_eq!(source, "sequenceDiagram\n\n\n    A->>B: Hi\n");
+        assert_eq!(accessibility.title.as_deref(), Some("Login flow"));
+        assert_eq!(
+            accessibility.description.as_deref(),
+            Some("Credentials are checked")
+        );
+    }
+
+    #[test]
+    fn test_multi_line_description() {
+        let input = "accDescr {\n    First line\n\n    second line\n}\nA->>B: Hi";
+        let (source, Statements { accessibility, .. }) = extract_statements(input);
+
+        assert_eq!(source, "\n\n\n\n\nA->>B: Hi");
+        assert_eq!(
+            accessibility.description.as_deref(),
+            Some("First line\nsecond line")
+        );
+
+        let inline = extract_statements("accDescr { On one line }")
+            .1
+            .accessibility;
+        assert_eq!(inline.description.as_deref(), Some("On one line"));
+    }
+
+    #[test]
+    fn test_unterminated_block_ends_at_unindented_line() {
+        let input = "sequenceDiagram\n    accDescr {\n        Runs on\n\n    A->>B: Hi\n";
+        let (source, Statements { accessibility, .. }) = extract_statements(input);
+
+        assert_eq!(source, "sequenceDiagram\n\n\n\n    A->>B: Hi\n");
+        assert_eq!(accessibility.description.as_deref(), Some("Runs on"));
+        assert_eq!(
+            accessibility.warnings(),
+            ["accDescr block on line 2 is missing its closing '}'"]
+        );
+
+        // A closed block may hold unindented lines
+        let closed = extract_statements("accDescr {\nFirst\n}\nA->>B: Hi")
+            .1
+            .accessibility;
+        assert_eq!(closed.description.as_deref(), Some("First"));
+        assert!(closed.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_braces_within_a_line_do_not_end_the_block() {
+        // Without its own closing brace the block ends at the message
+        let input = "sequenceDiagram\n    accDescr {\n        Lookup\n    A->>B: returns {}\n    \
+                     links B: {\"Docs\": \"https://example.com\"}\n";
+        let (source, statements) = extract_statements(input);
+        let accessibility = statements.accessibility;
+        assert_eq!(source, "sequenceDiagram\n\n\n    A->>B: returns {}\n\n");
+        assert_eq!(statements.links.of("B").len(), 1);
+        assert_eq!(accessibility.description.as_deref(), Some("Lookup"));
+        assert_eq!(accessibility.warnings().len(), 1);
+
+        // Balanced braces are description text
+        let input = "accDescr {\n    Returns {id}\n    Then {done} }\nA->>B: Hi";
+        let (source, Statements { accessibility, .. }) = extract_statements(input);
+        assert_eq!(source, "\n\n\nA->>B: Hi");
+        assert_eq!(
+            accessibility.description.as_deref(),
+            Some("Returns {id}\nThen {done}")
+        );
+        assert!(accessibility.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_other_statements_are_kept() {
+        let input = "accTitleX: nope\nA->>B: accTitle: not a statement";
+        let (source, Statements { accessibility, .. }) = extract_statements(input);
+        assert_eq!(source, input);
+        assert_eq!(accessibility, Accessibility::default());
+    }
+}
@@ -1,16 +1,20 @@
 //! msv - Mermaid SVG Render CLI
 //!
-//! A command-line tool to render Mermaid diagrams to SVG.
+//! A command-line tool to render Mermaid diagrams to SVG, or to PNG when
+//! built with the `png` feature.
 
 use std::fs;
 use std::io::{self, Write};
 use std::path::PathBuf;
 use std::process::ExitCode;
 
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use colored::Colorize;
 
-use mermaid_svg_render::{render_sequence_diagram, RenderOptions, Theme};
+use mermaid_svg_render::metadata::extract_source;
+use mermaid_svg_render::{
+    render_sequence_diagram_with_warnings, RenderError, RenderOptions, Theme,
+};
 
 /// Exit codes
 const EXIT_SUCCESS: u8 = 0;
@@ -22,22 +26,101 @@ const EXIT_PARSE_ERROR: u8 = 2;
 #[command(name = "msv")]
 #[command(version, about, long_about = None)]
 #[command(arg_required_else_help = true)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Input .mmd file path
-    #[arg(value_name = "INPUT")]
-    input: PathBuf,
+    #[arg(value_name = "INPUT", required = true)]
+    input: Option<PathBuf>,
 
-    /// Output SVG file (default: stdout)
+    /// Output file (default: stdout)
     #[arg(short, long, value_name = "FILE")]
     output: Option<PathBuf>,
 
-    /// Theme: light or dark
+    /// Output format (default: png for a .png output file, svg otherwise)
+    #[arg(short, long, value_name = "FORMAT")]
+    format: Option<Format>,
+
+    /// PNG size multiplier
+    #[arg(long, value_name = "FACTOR")]
+    scale: Option<f32>,
+
+    /// PNG pixel density; 192 doubles the pixel size (default: 96)
+    #[arg(long, value_name = "DPI")]
+    dpi: Option<f32>,
+
+    /// PNG background colour painted behind the diagram (CSS colour)
+    #[arg(long, value_name = "COLOR")]
+    background: Option<String>,
+
+    /// Theme: light, dark, auto, default, neutral, forest or base
     #[arg(short, long, value_name = "THEME", default_value = "light")]
     theme: String,
 
     /// Use transparent background
     #[arg(long)]
     transparent: bool,
+
+    /// Emit colours in an embedded <style> block instead of inline attributes
+    #[arg(long)]
+    style_block: bool,
+
+    /// Draw arrowheads as shapes instead of <marker> references
+    #[arg(long)]
+    inline_arrowheads: bool,
+
+    /// Describe the participants and messages in the SVG's <desc> for screen readers
+    #[arg(long)]
+    accessible_summary: bool,
+
+    /// Render no links (participant link statements, Markdown links) for untrusted input
+    #[arg(long)]
+    no_links: bool,
+
+    /// Prefix for the ids of participant, lifeline and message groups
+    #[arg(long, value_name = "PREFIX")]
+    id_prefix: Option<String>,
+
+    /// Embed the diagram source, version and options in the SVG's <metadata>
+    #[arg(long)]
+    embed_source: bool,
+
+    /// Decimals written for coordinates (0-6, default: 2)
+    #[arg(long, value_name = "DIGITS", value_parser = clap::value_parser!(u8).range(0..=6))]
+    precision: Option<u8>,
+
+    /// Write the smallest equivalent SVG (shared classes, merged lines, no whitespace)
+    #[arg(long)]
+    minify: bool,
+
+    /// Write canonical, diff-friendly SVG (fixed attribute order, sorted definitions)
+    #[arg(long, conflicts_with = "minify")]
+    pretty: bool,
+}
+
+/// Output formats
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    /// SVG markup
+    Svg,
+    /// PNG image (requires the `png` feature)
+    Png,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the Mermaid source embedded in an SVG rendered with --embed-source
+    Extract {
+        /// SVG file path
+        #[arg(value_name = "SVG")]
+        svg: PathBuf,
+
+        /// Output .mmd file (default: stdout)
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
 }
 
 fn main() -> ExitCode {
@@ -51,8 +134,15 @@ fn main() -> ExitCode {
 }
 
 fn run(args: Args) -> Result<(), u8> {
+    if let Some(Command::Extract { svg, output }) = &args.command {
+        return extract(svg, output.as_ref());
+    }
+
     // Read input file
-    let input = read_input(&args.input)?;
+    let Some(input_path) = &args.input else {
+        return Err(EXIT_GENERAL_ERROR);
+    };
+    let input = read_input(input_path)?;
 
     // Parse theme
     let theme = parse_theme(&args.theme)?;
@@ -62,14 +152,112 @@ fn run(args: Args) -> Result<(), u8> {
     if args.transparent {
         options = options.transparent();
     }
+    if args.style_block {
+        options = options.with_style_block();
+    }
+    if args.inline_arrowheads {
+        options = options.with_inline_arrowheads();
+    }
+    if args.accessible_summary {
+        options = options.with_accessible_summary();
+    }
+    if args.no_links {
+        options = options.without_links();
+    }
+    if let Some(prefix) = &args.id_prefix {
+        options = options.with_id_prefix(prefix.as_str());
+    }
+    if args.embed_source {
+        options = options.with_embedded_source();
+    }
+    if let Some(precision) = args.precision {
+        options = options.with_precision(precision.into());
+    }
+    if args.minify {
+        options = options.minified();
+    }
+    if args.pretty {
+        options = options.pretty_printed();
+    }
 
-    // Render the diagram
-    let svg = render_diagram(&input, &options)?;
+    let format = args.format.unwrap_or_else(|| {
+        match args.output.as_ref().and_then(|path| path.extension()) {
+            Some(extension) if extension.eq_ignore_ascii_case("png") => Format::Png,
+            _ => Format::Svg,
+        }
+    });
+    if format == Format::Svg
+        && (args.scale.is_some() || args.dpi.is_some() || args.background.is_some())
+    {
+        eprintln!(
+            "{} --scale, --dpi and --background apply to PNG output only. Add --format png.",
+            "error:".red().bold()
+        );
+        return Err(EXIT_GENERAL_ERROR);
+    }
+    // Render the diagram and write output
+    match format {
+        Format::Svg => {
+            let svg = render_diagram(&input, &options)?;
+            write_output(svg.as_bytes(), args.output.as_ref(), "SVG")
+        }
+        Format::Png => {
+            let png = render_png(&input, &options, &args)?;
+            write_output(&png, args.output.as_ref(), "PNG")
+        }
+    }
+}
 
-    // Write output
-    write_output(&svg, args.output.as_ref())?;
+#[cfg(feature = "png")]
+fn render_png(input: &str, options: &RenderOptions, args: &Args) -> Result<Vec<u8>, u8> {
+    use mermaid_svg_render::png::{render_sequence_diagram_png_with_warnings, PngOptions};
+    use mermaid_svg_render::theme::Color;
 
-    Ok(())
+    let mut png = PngOptions::default();
+    if let Some(scale) = args.scale {
+        png = png.with_scale(scale);
+    }
+    if let Some(dpi) = args.dpi {
+        png = png.with_dpi(dpi);
+    }
+    if let Some(background) = &args.background {
+        let color = Color::parse(background).ok_or_else(|| {
+            eprintln!(
+                "{} Invalid background colour '{}'.",
+                "error:".red().bold(),
+                background
+            );
+            EXIT_GENERAL_ERROR
+        })?;
+        png = png.with_background(color);
+    }
+
+    let output =
+        render_sequence_diagram_png_with_warnings(input, options, &png).map_err(report_error)?;
+    print_warnings(&output.warnings);
+    Ok(output.png)
+}
+
+#[cfg(not(feature = "png"))]
+fn render_png(_input: &str, _options: &RenderOptions, _args: &Args) -> Result<Vec<u8>, u8> {
+    eprintln!(
+        "{} PNG output needs msv built with the 'png' feature (cargo install mermaid-svg-render --features cli,png).",
+        "error:".red().bold()
+    );
+    Err(EXIT_GENERAL_ERROR)
+}
+
+fn extract(svg_path: &PathBuf, output: Option<&PathBuf>) -> Result<(), u8> {
+    let svg = read_input(svg_path)?;
+    let source = extract_source(&svg).ok_or_else(|| {
+        eprintln!(
+            "{} No embedded source in '{}'. Render it with --embed-source.",
+            "error:".red().bold(),
+            svg_path.display()
+        );
+        EXIT_GENERAL_ERROR
+    })?;
+    write_output(source.as_bytes(), output, "source")
 }
 
 fn read_input(path: &PathBuf) -> Result<String, u8> {
@@ -85,37 +273,43 @@ fn read_input(path: &PathBuf) -> Result<String, u8> {
 }
 
 fn parse_theme(theme_str: &str) -> Result<Theme, u8> {
-    match theme_str.to_lowercase().as_str() {
-        "light" => Ok(Theme::Light),
-        "dark" => Ok(Theme::Dark),
-        _ => {
-            eprintln!(
-                "{} Invalid theme '{}'. Use 'light' or 'dark'.",
-                "error:".red().bold(),
-                theme_str
-            );
-            Err(EXIT_GENERAL_ERROR)
-        }
-    }
+    Theme::from_name(theme_str).ok_or_else(|| {
+        eprintln!(
+            "{} Invalid theme '{}'. Use 'light', 'dark', 'auto', 'default', 'neutral', 'forest' or 'base'.",
+            "error:".red().bold(),
+            theme_str
+        );
+        EXIT_GENERAL_ERROR
+    })
 }
 
 fn render_diagram(input: &str, options: &RenderOptions) -> Result<String, u8> {
-    render_sequence_diagram(input, options).map_err(|e| {
-        let error_msg = e.to_string();
-        if error_msg.contains("parse") || error_msg.contains("Parse") {
-            eprintln!("{} {}", "parse error:".red().bold(), error_msg);
-            EXIT_PARSE_ERROR
-        } else {
-            eprintln!("{} {}", "error:".red().bold(), error_msg);
-            EXIT_GENERAL_ERROR
-        }
-    })
+    let output = render_sequence_diagram_with_warnings(input, options).map_err(report_error)?;
+    print_warnings(&output.warnings);
+    Ok(output.svg)
+}
+
+fn report_error(error: RenderError) -> u8 {
+    let error_msg = error.to_string();
+    if error_msg.contains("parse") || error_msg.contains("Parse") {
+        eprintln!("{} {}", "parse error:".red().bold(), error_msg);
+        EXIT_PARSE_ERROR
+    } else {
+        eprintln!("{} {}", "error:".red().bold(), error_msg);
+        EXIT_GENERAL_ERROR
+    }
+}
+
+fn print_warnings(warnings: &[String]) {
+    for warning in warnings {
+        eprintln!("{} {}", "warning:".yellow().bold(), warning);
+    }
 }
 
-fn write_output(svg: &str, output: Option<&PathBuf>) -> Result<(), u8> {
+fn write_output(content: &[u8], output: Option<&PathBuf>, kind: &str) -> Result<(), u8> {
     match output {
         Some(path) => {
-            fs::write(path, svg).map_err(|e| {
+            fs::write(path, content).map_err(|e| {
                 eprintln!(
                     "{} Failed to write '{}': {}",
                     "error:".red().bold(),
@@ -125,13 +319,14 @@ fn write_output(svg: &str, output: Option<&PathBuf>) -> Result<(), u8> {
                 EXIT_GENERAL_ERROR
             })?;
             eprintln!(
-                "{} Wrote SVG to '{}'",
+                "{} Wrote {} to '{}'",
                 "success:".green().bold(),
+                kind,
                 path.display()
             );
         }
         None => {
-            io::stdout().write_all(svg.as_bytes()).map_err(|e| {
+            io::stdout().write_all(content).map_err(|e| {
                 eprintln!("{} Failed to write to stdout: {}", "error:".red().bold(), e);
                 EXIT_GENERAL_ERROR
             })?;
@@ -0,0 +1,561 @@
+//! Diagram configuration from init directives and front-matter
+//!
+//! Mermaid diagrams can carry their own configuration, either as YAML
+//! front-matter with a `config` key or as `%%{init: ...}%%` directives:
+//!
+//! ```text
+//! ---
+//! config:
+//!   theme: forest
+//! ---
+//! %%{init: {'sequence': {'wrap': true}}}%%
+//! sequenceDiagram
+//!     Alice->>Bob: Hello
+//! ```
+//!
+//! [`DiagramConfig::apply`] merges both onto [`RenderOptions`]. Later sources
+//! win, so the precedence from lowest to highest is:
+//!
+//! 1. the caller's [`RenderOptions`]
+//! 2. front-matter `config`
+//! 3. init directives, in source order
+//!
+//! Set [`RenderOptions::use_diagram_config`] to `false` to ignore diagram
+//! configuration. Unknown keys, invalid values and malformed directives are
+//! reported as warnings and never fail the render.
+
+use serde_json::{Map, Value};
+
+use crate::options::{FontOverride, RenderOptions};
+use crate::theme::{Theme, ThemeColors, ThemeVariables};
+
+/// Configuration collected from a diagram's front-matter and init directives
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiagramConfig {
+    values: Map<String, Value>,
+    warnings: Vec<String>,
+}
+
+impl DiagramConfig {
+    /// Returns `true` if the diagram carried no configuration
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns a top-level configuration value
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.values.get(key)
+    }
+
+    /// Returns problems found while extracting the configuration
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Merges the configuration onto `options`
+    ///
+    /// Returns the updated options and every warning, including those from
+    /// extraction. Supported keys are `theme`, `themeVariables`,
+    /// `fontFamily`, `fontSize`, `wrap` and, under `sequence`, `wrap`,
+    /// `useMaxWidth`, `mirrorActors`, `width`, `height`, `actorMargin`,
+    /// `messageMargin` and the `actor`/`message` `FontSize`, `FontFamily`
+    /// and `FontWeight` settings.
+    ///
+    /// Mermaid's other `sequence` keys have nothing to act on yet and are
+    /// reported as unsupported: `diagramMarginX`, `diagramMarginY`,
+    /// `boxMargin`, `boxTextMargin`, `noteMargin`, `bottomMarginAdj`,
+    /// `activationWidth`, `rightAngles`, `showSequenceNumbers`,
+    /// `hideUnusedParticipants`, `forceMenus`, `messageAlign`, `noteAlign`,
+    /// `wrapPadding`, `labelBoxWidth`, `labelBoxHeight`,
+    /// `arrowMarkerAbsolute` and the `noteFont*` settings.
+    pub fn apply(&self, options: &RenderOptions) -> (RenderOptions, Vec<String>) {
+        let mut options = options.clone();
+        let mut warnings = self.warnings.clone();
+        let mut theme_variables = None;
+
+        for (key, value) in &self.values {
+            match key.as_str() {
+                "theme" => set(
+                    value.as_str().and_then(Theme::from_name),
+                    &mut options.theme,
+                    key,
+                    value,
+                    &mut warnings,
+                ),
+                // Applied last so they resolve against the final theme
+                "themeVariables" => theme_variables = Some(value),
+                "fontFamily" => set(
+                    scalar_text(value),
+                    &mut options.font_family,
+                    key,
+                    value,
+                    &mut warnings,
+                ),
+                "fontSize" => set(
+                    font_size(value),
+                    &mut options.font_size,
+                    key,
+                    value,
+                    &mut warnings,
+                ),
+                "wrap" => set(as_bool(value), &mut options.wrap, key, value, &mut warnings),
+                "sequence" => apply_sequence(value, &mut options, &mut warnings),
+                _ => warnings.push(unsupported(key)),
+            }
+        }
+
+        if let Some(value) = theme_variables {
+            apply_theme_variables(value, &mut options, &mut warnings);
+        }
+
+        (options, warnings)
+    }
+
+    /// Merges the `config` key of front-matter YAML, warning about any
+    /// other key
+    pub(crate) fn add_front_matter(&mut self, yaml: &str) {
+        match serde_yaml::from_str::<Value>(yaml) {
+            Ok(Value::Object(mut front_matter)) => {
+                if let Some(value) = front_matter.remove("config") {
+                    self.merge(value, "front-matter config");
+                }
+                for key in front_matter.keys() {
+                    self.warnings
+                        .push(format!("unsupported front-matter key '{}' ignored", key));
+                }
+            }
+            Ok(Value::Null) => {}
+            Ok(_) => self
+                .warnings
+                .push("front-matter must be a mapping, ignored".to_string()),
+            Err(e) => self
+                .warnings
+                .push(format!("could not parse front-matter: {}", e)),
+        }
+    }
+
+    /// Removes `%%{init: ...}%%` and `%%{initialize: ...}%%` directives
+    /// from `text`, merging their configuration
+    ///
+    /// The directives' line breaks are kept; other directives are left in
+    /// place.
+    pub(crate) fn strip_directives(&mut self, text: &str) -> String {
+        let mut output = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(start) = rest.find("%%{") {
+            let after = &rest[start + 3..];
+            let end = match after.find("}%%") {
+                Some(end) => end,
+                None => break,
+            };
+            let directive = &rest[start..start + end + 6];
+            output.push_str(&rest[..start]);
+
+            match init_body(&after[..end]) {
+                Some(body) => {
+                    match json5::from_str::<Value>(body) {
+                        Ok(value) => self.merge(value, "init directive"),
+                        Err(e) => self
+                            .warnings
+                            .push(format!("could not parse init directive: {}", e)),
+                    }
+                    output.extend(directive.chars().filter(|&c| c == '\n'));
+                }
+                None => output.push_str(directive),
+            }
+            rest = &after[end + 3..];
+        }
+
+        output.push_str(rest);
+        output
+    }
+
+    /// Deep-merges a parsed configuration object over the current values
+    fn merge(&mut self, value: Value, origin: &str) {
+        match value {
+            Value::Object(map) => merge_maps(&mut self.values, map),
+            Value::Null => {}
+            _ => self
+                .warnings
+                .push(format!("{} must be an object, ignored", origin)),
+        }
+    }
+}
+
+/// Splits leading `---` delimited front-matter from the source
+///
+/// Returns the YAML and the source with the front-matter lines blanked.
+pub(crate) fn split_front_matter(input: &str) -> Option<(String, String)> {
+    let mut lines = input.split_inclusive('\n');
+    let mut rest = String::new();
+
+    loop {
+        let line = lines.next()?;
+        if line.trim().is_empty() {
+            rest.push_str(line);
+            continue;
+        }
+        if line.trim_end() != "---" {
+            return None;
+        }
+        rest.push('\n');
+        break;
+    }
+
+    let mut yaml = String::new();
+    loop {
+        let line = lines.next()?;
+        rest.push('\n');
+        if line.trim_end() == "---" {
+            break;
+        }
+        yaml.push_str(line);
+    }
+
+    rest.extend(lines);
+    Some((yaml, rest))
+}
+
+/// Returns the JSON body of an init directive
+fn init_body(content: &str) -> Option<&str> {
+    let (name, body) = content.split_once(':')?;
+    matches!(name.trim(), "init" | "initialize").then_some(body.trim())
+}
+
+/// Recursively merges `source` into `target`, replacing non-object values
+fn merge_maps(target: &mut Map<String, Value>, source: Map<String, Value>) {
+    for (key, value) in source {
+        match (target.get_mut(&key), value) {
+            (Some(Value::Object(existing)), Value::Object(incoming)) => {
+                merge_maps(existing, incoming)
+            }
+            (_, value) => {
+                target.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Applies the `sequence` section
+fn apply_sequence(value: &Value, options: &mut RenderOptions, warnings: &mut Vec<String>) {
+    let map = match value.as_object() {
+        Some(map) => map,
+        None => return warnings.push(invalid("sequence", value)),
+    };
+
+    for (key, value) in map {
+        let path = format!("sequence.{}", key);
+        match key.as_str() {
+            "wrap" => set(as_bool(value), &mut options.wrap, &path, value, warnings),
+            "useMaxWidth" => set(
+                as_bool(value),
+                &mut options.use_max_width,
+                &path,
+                value,
+                warnings,
+            ),
+            "mirrorActors" => set(
+                as_bool(value),
+                &mut options.sequence.mirror_actors,
+                &path,
+                value,
+                warnings,
+            ),
+            "width" => set(
+                as_length(value),
+                &mut options.sequence.actor_width,
+                &path,
+                value,
+                warnings,
+            ),
+            "height" => set(
+                as_length(value),
+                &mut options.sequence.actor_height,
+                &path,
+                value,
+                warnings,
+            ),
+            "actorMargin" => set(
+                as_length(value),
+                &mut options.sequence.actor_margin,
+                &path,
+                value,
+                warnings,
+            ),
+            "messageMargin" => set(
+                as_length(value),
+                &mut options.sequence.message_margin,
+                &path,
+                value,
+                warnings,
+            ),
+            _ => match font_setting(key, options) {
+                Some((font, "Size")) => set(
+                    font_size(value).map(Some),
+                    &mut font.size,
+                    &path,
+                    value,
+                    warnings,
+                ),
+                Some((font, "Family")) => set(
+                    scalar_text(value).map(Some),
+                    &mut font.family,
+                    &path,
+                    value,
+                    warnings,
+                ),
+                Some((font, "Weight")) => set(
+                    scalar_text(value).map(Some),
+                    &mut font.weight,
+                    &path,
+                    value,
+                    warnings,
+                ),
+                _ => warnings.push(unsupported(&path)),
+            },
+        }
+    }
+}
+
+/// Maps `actorFont*` and `messageFont*` keys to a font override
+///
+/// `noteFont*` keys are left unsupported until notes are rendered.
+fn font_setting<'a, 'k>(
+    key: &'k str,
+    options: &'a mut RenderOptions,
+) -> Option<(&'a mut FontOverride, &'k str)> {
+    if let Some(property) = key.strip_prefix("actorFont") {
+        Some((&mut options.participant_font, property))
+    } else {
+        key.strip_prefix("messageFont")
+            .map(|property| (&mut options.message_font, property))
+    }
+}
+
+/// Resolves `themeVariables` on top of the selected theme
+///
+/// Overrides start from the selected theme's variables (see
+/// [`Theme::variables`]) and keep its corner radius; custom palettes start
+/// from the `base` theme.
+fn apply_theme_variables(value: &Value, options: &mut RenderOptions, warnings: &mut Vec<String>) {
+    let map = match value.as_object() {
+        Some(map) => map,
+        None => return warnings.push(invalid("themeVariables", value)),
+    };
+
+    let mut overrides = ThemeVariables::new();
+    for (key, value) in map {
+        match scalar_text(value) {
+            Some(text) => overrides.set(key.as_str(), text),
+            None => warnings.push(invalid(&format!("themeVariables.{}", key), value)),
+        }
+    }
+    for key in overrides.unknown_keys() {
+        warnings.push(format!("unknown theme variable '{}' ignored", key));
+    }
+
+    let mut variables = options.theme.variables().unwrap_or_default();
+    for (key, value) in overrides.iter() {
+        variables.set(key, value);
+    }
+    match variables.to_colors() {
+        Ok(colors) => {
+            let corner_radius = options.theme.colors().corner_radius;
+            options.theme = Theme::Custom(ThemeColors {
+                corner_radius,
+                ..colors
+            })
+        }
+        Err(e) => warnings.push(format!("themeVariables ignored: {}", e)),
+    }
+}
+
+/// Stores a parsed value, or records a warning if it could not be parsed
+fn set<T>(
+    parsed: Option<T>,
+    target: &mut T,
+    path: &str,
+    value: &Value,
+    warnings: &mut Vec<String>,
+) {
+    match parsed {
+        Some(parsed) => *target = parsed,
+        None => warnings.push(invalid(path, value)),
+    }
+}
+
+fn unsupported(path: &str) -> String {
+    format!("unsupported config key '{}' ignored", path)
+}
+
+fn invalid(path: &str, value: &Value) -> String {
+    format!("invalid value {} for config key '{}' ignored", value, path)
+}
+
+/// Accepts booleans and the strings `true` and `false`
+fn as_bool(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(flag) => Some(*flag),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Accepts non-negative numbers and strings such as `"16px"`
+fn as_length(value: &Value) -> Option<f64> {
+    let length = match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().trim_end_matches("px").trim().parse().ok(),
+        _ => None,
+    }?;
+    (length.is_finite() && length >= 0.0).then_some(length)
+}
+
+/// Accepts lengths of at least one pixel, rounded to whole pixels
+fn font_size(value: &Value) -> Option<u32> {
+    as_length(value)
+        .filter(|&size| size >= 1.0)
+        .map(|size| size.round() as u32)
+}
+
+/// Converts non-empty strings, numbers and booleans to text
+fn scalar_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) if !text.trim().is_empty() => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(flag) => Some(flag.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::statements::{extract_statements, Statements};
+
+    #[test]
+    fn test_extracts_init_directive() {
+        let input = "%%{init: {'theme': 'forest'}}%%\nsequenceDiagram\n    A->>B: Hi\n";
+        let (source, Statements { config, .. }) = extract_statements(input);
+
+        assert_eq!(source, "\nsequenceDiagram\n    A->>B: Hi\n");
+        assert_eq!(config.get("theme"), Some(&Value::from("forest")));
+        assert!(config.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_keeps_other_directives_and_line_numbers() {
+        let input = "%%{wrap}%%\n%%{init: {\n  'theme': 'dark'\n}}%%\nsequenceDiagram";
+        let (source, Statements { config, .. }) = extract_statements(input);
+
+        assert_eq!(source, "%%{wrap}%%\n\n\n\nsequenceDiagram");
+        assert!(!config.is_empty());
+    }
+
+    #[test]
+    fn test_extracts_front_matter() {
+        let input = "\n---\ntitle: Demo\nconfig:\n  theme: neutral\n---\nsequenceDiagram\n";
+        let (source, Statements { config, .. }) = extract_statements(input);
+
+        assert_eq!(source, "\n\n\n\n\n\nsequenceDiagram\n");
+        assert_eq!(config.get("theme"), Some(&Value::from("neutral")));
+        assert_eq!(config.warnings().len(), 1); // title
+    }
+
+    #[test]
+    fn test_directives_override_front_matter() {
+        let input = "---\nconfig:\n  theme: neutral\n  sequence:\n    wrap: true\n---\n\
+                     %%{init: {'theme': 'forest', 'sequence': {'useMaxWidth': true}}}%%\n\
+                     sequenceDiagram";
+        let (_, Statements { config, .. }) = extract_statements(input);
+        let (options, warnings) = config.apply(&RenderOptions::default());
+
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(options.theme, Theme::Forest);
+        // Nested sections merge rather than replace
+        assert!(options.wrap);
+        assert!(options.use_max_width);
+    }
+
+    #[test]
+    fn test_unknown_keys_and_bad_values_warn() {
+        let input = "%%{init: {'theme': 'sparkly', 'flowchart': {}, \
+                     'sequence': {'actorFontSize': '18px', 'noteFontSize': 12, 'boxMargin': 5, \
+                     'bogus': 1}}}%%\n\
+                     sequenceDiagram";
+        let (_, Statements { config, .. }) = extract_statements(input);
+        let (options, warnings) = config.apply(&RenderOptions::default());
+
+        assert_eq!(options.theme, Theme::Light);
+        assert_eq!(options.participant_font.size, Some(18));
+        assert_eq!(warnings.len(), 5, "{:?}", warnings);
+        assert!(warnings.iter().any(|w| w.contains("sequence.bogus")));
+        assert!(warnings
+            .contains(&"unsupported config key 'sequence.noteFontSize' ignored".to_string()));
+        assert!(
+            warnings.contains(&"unsupported config key 'sequence.boxMargin' ignored".to_string())
+        );
+    }
+
+    #[test]
+    fn test_sequence_layout_keys() {
+        let input = "%%{init: {'sequence': {'mirrorActors': false, 'actorMargin': '30px', \
+                     'messageMargin': 35, 'width': 120, 'height': -1}}}%%";
+        let (_, Statements { config, .. }) = extract_statements(input);
+        let (options, warnings) = config.apply(&RenderOptions::default());
+
+        assert!(!options.sequence.mirror_actors);
+        assert_eq!(options.sequence.actor_margin, 30.0);
+        assert_eq!(options.sequence.message_margin, 35.0);
+        assert_eq!(options.sequence.actor_width, 120.0);
+        assert_eq!(options.sequence.actor_height, 40.0);
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+    }
+
+    #[test]
+    fn test_malformed_directive_warns() {
+        let (source, Statements { config, .. }) =
+            extract_statements("%%{init: {theme: }}%%\nsequenceDiagram");
+
+        assert_eq!(source, "\nsequenceDiagram");
+        assert_eq!(config.warnings().len(), 1);
+    }
+
+    #[test]
+    fn test_theme_variables_resolve_against_theme() {
+        let input = "%%{init: {'theme': 'forest', 'themeVariables': {'noteBkgColor': '#ffffff', \
+                     'madeUp': 'x'}}}%%";
+        let (_, Statements { config, .. }) = extract_statements(input);
+        let (options, warnings) = config.apply(&RenderOptions::default());
+
+        let colors = options.colors();
+        assert_eq!(colors.note_bg, "#ffffff");
+        assert_eq!(colors.participant_bg, "#cde498");
+        assert_eq!(warnings, vec!["unknown theme variable 'madeUp' ignored"]);
+    }
+
+    #[test]
+    fn test_theme_variables_keep_dark_palette() {
+        let input = "%%{init: {'theme': 'dark', 'themeVariables': {'primaryColor': '#333'}}}%%";
+        let (_, Statements { config, .. }) = extract_statements(input);
+        let (options, warnings) = config.apply(&RenderOptions::default());
+
+        let colors = options.colors();
+        let dark = ThemeColors::dark();
+        assert_eq!(colors.background, dark.background);
+        assert_eq!(colors.participant_bg, dark.participant_bg);
+        assert_eq!(colors.note_bg, dark.note_bg);
+        assert_eq!(colors.corner_radius, dark.corner_radius);
+        assert!(warnings.is_empty());
+
+        // Without a theme, overrides apply to the light default
+        let (_, Statements { config, .. }) =
+            extract_statements("%%{init: {'themeVariables': {'noteBkgColor': '#fafafa'}}}%%");
+        let (options, _) = config.apply(&RenderOptions::default());
+        assert_eq!(
+            options.colors(),
+            ThemeColors::light().with_note("#fafafa", "#aaaa33", "#333333")
+        );
+    }
+}
@@ -16,6 +16,10 @@ pub enum RenderError {
     SvgError(String),
     /// Invalid render options
     InvalidOptions(String),
+    /// Error loading or parsing a font file
+    FontError(String),
+    /// Error rasterizing an SVG to PNG
+    PngError(String),
 }
 
 impl fmt::Display for RenderError {
@@ -25,6 +29,8 @@ impl fmt::Display for RenderError {
             RenderError::UnsupportedDiagram(msg) => write!(f, "Unsupported diagram: {}", msg),
             RenderError::SvgError(msg) => write!(f, "SVG error: {}", msg),
             RenderError::InvalidOptions(msg) => write!(f, "Invalid options: {}", msg),
+            RenderError::FontError(msg) => write!(f, "Font error: {}", msg),
+            RenderError::PngError(msg) => write!(f, "PNG error: {}", msg),
         }
     }
 }
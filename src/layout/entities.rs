@@ -0,0 +1,191 @@
+//! Character escape decoding for labels
+//!
+//! Mermaid labels can't contain some characters literally (`;` ends a
+//! statement, `#` starts a comment in some contexts), so Mermaid accepts
+//! escape codes written as `#59;` (decimal code point) or `#quot;` (entity
+//! name). Standard HTML character references (`&lt;`, `&#59;`, `&#x3B;`) are
+//! accepted as well. Unknown names and invalid code points are left as
+//! literal text.
+
+/// Named character references understood in `&name;` and `#name;` form
+const NAMED_ENTITIES: &[(&str, char)] = &[
+    ("amp", '&'),
+    ("lt", '<'),
+    ("gt", '>'),
+    ("quot", '"'),
+    ("apos", '\''),
+    ("nbsp", '\u{A0}'),
+    ("semi", ';'),
+    ("colon", ':'),
+    ("comma", ','),
+    ("num", '#'),
+    ("excl", '!'),
+    ("quest", '?'),
+    ("percnt", '%'),
+    ("lpar", '('),
+    ("rpar", ')'),
+    ("lbrace", '{'),
+    ("rbrace", '}'),
+    ("lsqb", '['),
+    ("rsqb", ']'),
+    ("ast", '*'),
+    ("verbar", '|'),
+    ("bsol", '\\'),
+    ("grave", '`'),
+    ("equals", '='),
+    ("plus", '+'),
+    ("copy", '©'),
+    ("reg", '®'),
+    ("trade", '™'),
+    ("deg", '°'),
+    ("plusmn", '±'),
+    ("times", '×'),
+    ("divide", '÷'),
+    ("micro", 'µ'),
+    ("middot", '·'),
+    ("para", '¶'),
+    ("sect", '§'),
+    ("cent", '¢'),
+    ("pound", '£'),
+    ("yen", '¥'),
+    ("euro", '€'),
+    ("laquo", '«'),
+    ("raquo", '»'),
+    ("lsquo", '‘'),
+    ("rsquo", '’'),
+    ("ldquo", '“'),
+    ("rdquo", '”'),
+    ("ndash", '–'),
+    ("mdash", '—'),
+    ("hellip", '…'),
+    ("bull", '•'),
+    ("larr", '←'),
+    ("rarr", '→'),
+    ("uarr", '↑'),
+    ("darr", '↓'),
+    ("harr", '↔'),
+    ("lArr", '⇐'),
+    ("rArr", '⇒'),
+    ("hArr", '⇔'),
+    ("le", '≤'),
+    ("ge", '≥'),
+    ("ne", '≠'),
+    ("asymp", '≈'),
+    ("infin", '∞'),
+    ("check", '✓'),
+    ("hearts", '♥'),
+    ("spades", '♠'),
+    ("clubs", '♣'),
+    ("diams", '♦'),
+];
+
+/// Decodes a character reference at the start of `text`
+///
+/// Recognises `&name;`, `&#NNN;` and `&#xHHH;` as well as Mermaid's
+/// `#name;` and `#NNN;`.
+///
+/// # Returns
+///
+/// The decoded character and the byte length of the reference, or `None` if
+/// `text` does not start with a valid reference
+pub fn decode_entity(text: &str) -> Option<(char, usize)> {
+    let (body, prefix_len) = if let Some(rest) = text.strip_prefix("&#") {
+        (rest, 2)
+    } else if let Some(rest) = text.strip_prefix('&').or_else(|| text.strip_prefix('#')) {
+        (rest, 1)
+    } else {
+        return None;
+    };
+
+    let end = body.find(';')?;
+    let name = &body[..end];
+    let numeric = prefix_len == 2 || name.starts_with(|c: char| c.is_ascii_digit());
+
+    let decoded = if numeric {
+        let code = match name.strip_prefix(['x', 'X']) {
+            Some(hex) if prefix_len == 2 => u32::from_str_radix(hex, 16).ok()?,
+            _ if name.bytes().all(|b| b.is_ascii_digit()) => name.parse().ok()?,
+            _ => return None,
+        };
+        // Control characters are not allowed in SVG text
+        char::from_u32(code).filter(|c| !c.is_control() || *c == '\t')?
+    } else {
+        NAMED_ENTITIES
+            .iter()
+            .find(|(entity, _)| *entity == name)
+            .map(|(_, c)| *c)?
+    };
+
+    Some((decoded, prefix_len + end + 1))
+}
+
+/// Decodes every character reference in `text`
+///
+/// # Example
+///
+/// ```rust
+/// use mermaid_svg_render::layout::decode_entities;
+///
+/// assert_eq!(decode_entities("a #59; b &lt;T&gt; #9829;"), "a ; b <T> ♥");
+/// ```
+pub fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while let Some(offset) = text[i..].find(['&', '#']) {
+        let start = i + offset;
+        decoded.push_str(&text[i..start]);
+        match decode_entity(&text[start..]) {
+            Some((c, len)) => {
+                decoded.push(c);
+                i = start + len;
+            }
+            None => {
+                decoded.push_str(&text[start..start + 1]);
+                i = start + 1;
+            }
+        }
+    }
+    decoded.push_str(&text[i..]);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_mermaid_codes() {
+        assert_eq!(decode_entity("#59; rest"), Some((';', 4)));
+        assert_eq!(decode_entity("#quot;"), Some(('"', 6)));
+        assert_eq!(decode_entity("#9829;"), Some(('♥', 6)));
+    }
+
+    #[test]
+    fn test_decode_html_references() {
+        assert_eq!(decode_entity("&lt;"), Some(('<', 4)));
+        assert_eq!(decode_entity("&#59;"), Some((';', 5)));
+        assert_eq!(decode_entity("&#x2665;"), Some(('♥', 8)));
+        assert_eq!(decode_entity("&#X2665;"), Some(('♥', 8)));
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid() {
+        assert_eq!(decode_entity("#5"), None);
+        assert_eq!(decode_entity("#unknown;"), None);
+        assert_eq!(decode_entity("#x2665;"), None);
+        assert_eq!(decode_entity("&#xD800;"), None); // surrogate
+        assert_eq!(decode_entity("&#0;"), None);
+        assert_eq!(decode_entity("#5;"), None); // control character
+        assert_eq!(decode_entity("&;"), None);
+        assert_eq!(decode_entity("# 1;"), None);
+    }
+
+    #[test]
+    fn test_decode_entities_in_text() {
+        assert_eq!(decode_entities("issue #5; #42"), "issue #5; #42");
+        assert_eq!(decode_entities("A &amp; B"), "A & B");
+        assert_eq!(decode_entities("&amp;lt;"), "&lt;");
+        assert_eq!(decode_entities("plain"), "plain");
+    }
+}
@@ -0,0 +1,607 @@
+//! Inline label markup
+//!
+//! Labels support a small Markdown subset: `**bold**`, `*italic*`,
+//! `` `code` `` and `[links](https://example.com)`. A label line is parsed
+//! into [`TextRun`]s that share one [`TextStyle`] each; runs are measured
+//! with their own font so styled text still fits its box, and rendered as
+//! `<tspan>` elements.
+//!
+//! Delimiters without a matching closer are kept as literal text, and a
+//! backslash escapes the next markup character. Underscores are never
+//! treated as emphasis so `snake_case` identifiers render unchanged.
+//!
+//! # HTML subset
+//!
+//! Exactly these tags are interpreted (case-insensitive, without
+//! attributes):
+//!
+//! - `<br>`, `<br/>`, `<br />` - line break (split off before parsing, see
+//!   [`split_by_line_breaks`](super::split_by_line_breaks))
+//! - `<b>`...`</b>` - bold
+//! - `<i>`...`</i>` - italic
+//! - `<sub>`...`</sub>`, `<sup>`...`</sup>` - subscript and superscript
+//!
+//! An unclosed tag applies to the rest of the line and a stray closing tag
+//! is dropped. Every other tag is shown as literal text.
+//!
+//! Formulas between `$$` delimiters are typeset as a TeX subset; see the
+//! `math` module for what is supported.
+//!
+//! Character references (`&lt;`, `&#9829;`, Mermaid's `#59;` and `#quot;`)
+//! are decoded to literal characters, so an escaped `*` or `<` is never
+//! treated as markup.
+
+use std::ops::Range;
+
+use crate::options::Font;
+
+use super::entities::{decode_entities, decode_entity};
+use super::math::typeset;
+use super::measure::TextMeasurer;
+
+/// Font family used for `code` runs
+pub const CODE_FONT_FAMILY: &str = "monospace";
+
+/// Font size of subscripts and superscripts relative to the label font
+pub const SCRIPT_SCALE: f64 = 0.7;
+
+/// Characters that can be escaped with a backslash
+const ESCAPABLE: &[char] = &['\\', '*', '`', '[', ']', '(', ')', '$'];
+
+/// Vertical position of a subscript or superscript run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaselineShift {
+    /// Lowered, smaller text
+    Sub,
+    /// Raised, smaller text
+    Super,
+}
+
+/// Formatting applied to a run of text
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextStyle {
+    /// Bold weight
+    pub bold: bool,
+    /// Italic style
+    pub italic: bool,
+    /// Monospace code font
+    pub code: bool,
+    /// Link target (rendered underlined)
+    pub href: Option<String>,
+    /// Subscript or superscript
+    pub shift: Option<BaselineShift>,
+}
+
+impl TextStyle {
+    /// Returns `true` if the style adds no formatting
+    pub fn is_plain(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns the font a run with this style is drawn with
+    pub fn font(&self, base: &Font) -> Font {
+        let mut font = base.clone();
+        if self.bold {
+            font.weight = Some("bold".to_string());
+        }
+        if self.code {
+            font.family = CODE_FONT_FAMILY.to_string();
+        }
+        if self.shift.is_some() {
+            font.size = (font.size as f64 * SCRIPT_SCALE).round().max(1.0) as u32;
+        }
+        font
+    }
+}
+
+/// A run of text sharing one style
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextRun {
+    /// Literal text of the run
+    pub text: String,
+    /// Formatting of the run
+    pub style: TextStyle,
+}
+
+/// A single line of styled label text
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextLine {
+    /// Runs in reading order (no empty runs)
+    pub runs: Vec<TextRun>,
+}
+
+impl TextLine {
+    /// Creates a line of unstyled text without interpreting markup
+    pub fn plain(text: &str) -> Self {
+        let mut line = Self::default();
+        line.push(text, &TextStyle::default());
+        line
+    }
+
+    /// Parses inline markup into a styled line
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use mermaid_svg_render::layout::TextLine;
+    ///
+    /// let line = TextLine::parse("set **user_id** to `42`");
+    /// assert_eq!(line.text(), "set user_id to 42");
+    /// assert!(line.runs[1].style.bold);
+    /// assert!(line.runs[3].style.code);
+    /// ```
+    pub fn parse(text: &str) -> Self {
+        parse_markdown(text)
+    }
+
+    /// Returns the text of all runs without formatting
+    pub fn text(&self) -> String {
+        self.runs.iter().map(|run| run.text.as_str()).collect()
+    }
+
+    /// Returns `true` if the line has no text
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    /// Returns `true` if no run carries formatting
+    pub fn is_plain(&self) -> bool {
+        self.runs.iter().all(|run| run.style.is_plain())
+    }
+
+    /// Measures the line, using each run's own font
+    pub fn width(&self, measurer: &dyn TextMeasurer, font: &Font) -> f64 {
+        self.runs
+            .iter()
+            .map(|run| measurer.measure(&run.text, &run.style.font(font)))
+            .sum()
+    }
+
+    /// Returns the part of the line covering a byte range of [`text`](Self::text)
+    pub fn slice(&self, range: Range<usize>) -> Self {
+        let mut line = Self::default();
+        let mut offset = 0;
+
+        for run in &self.runs {
+            let run_range = offset..offset + run.text.len();
+            offset = run_range.end;
+
+            let start = range.start.max(run_range.start);
+            let end = range.end.min(run_range.end);
+            if start < end {
+                line.push(
+                    &run.text[start - run_range.start..end - run_range.start],
+                    &run.style,
+                );
+            }
+        }
+        line
+    }
+
+    /// Returns the line with link targets removed, keeping the text
+    pub fn without_links(&self) -> Self {
+        let mut line = Self::default();
+        for run in &self.runs {
+            let style = TextStyle {
+                href: None,
+                ..run.style.clone()
+            };
+            line.push(&run.text, &style);
+        }
+        line
+    }
+
+    /// Appends text, extending the last run if it has the same style
+    pub fn push(&mut self, text: &str, style: &TextStyle) {
+        if text.is_empty() {
+            return;
+        }
+        match self.runs.last_mut() {
+            Some(last) if last.style == *style => last.text.push_str(text),
+            _ => self.runs.push(TextRun {
+                text: text.to_string(),
+                style: style.clone(),
+            }),
+        }
+    }
+
+    /// Appends text in the style of the last run
+    pub fn push_continued(&mut self, text: &str) {
+        let style = self
+            .runs
+            .last()
+            .map(|run| run.style.clone())
+            .unwrap_or_default();
+        self.push(text, &style);
+    }
+}
+
+/// Checks whether a link target is safe to emit as an `href`
+///
+/// Allows `http`, `https` and `mailto` URLs plus relative references;
+/// anything else (such as `javascript:`) is rejected.
+pub fn is_safe_url(url: &str) -> bool {
+    let url = url.trim();
+    if url.is_empty() || url.chars().any(char::is_control) {
+        return false;
+    }
+    match url.split_once(':') {
+        // A colon after a slash, `?` or `#` is not a scheme separator
+        Some((scheme, _)) if !scheme.contains(['/', '?', '#']) => {
+            let scheme = scheme.to_ascii_lowercase();
+            scheme == "http" || scheme == "https" || scheme == "mailto"
+        }
+        _ => true,
+    }
+}
+
+/// Parses the Markdown and HTML subsets into runs, decoding references
+fn parse_markdown(text: &str) -> TextLine {
+    let mut line = TextLine::default();
+    let mut style = TextStyle::default();
+    let mut i = 0;
+
+    while i < text.len() {
+        let rest = &text[i..];
+        let c = rest.chars().next().unwrap_or_default();
+
+        if c == '\\' {
+            if let Some(next) = rest[1..].chars().next().filter(|n| ESCAPABLE.contains(n)) {
+                line.push(&next.to_string(), &style);
+                i += 1 + next.len_utf8();
+                continue;
+            }
+        } else if let Some(formula) = rest.strip_prefix("$$") {
+            if let Some(len) = formula.find("$$") {
+                typeset(&formula[..len], &style, &mut line);
+                i += len + 4;
+                continue;
+            }
+        } else if c == '`' {
+            if let Some(len) = rest[1..].find('`').filter(|&len| len > 0) {
+                let code = TextStyle {
+                    code: true,
+                    ..style.clone()
+                };
+                line.push(&decode_entities(&rest[1..1 + len]), &code);
+                i += len + 2;
+                continue;
+            }
+        } else if c == '[' {
+            if let Some((label, url, len)) = parse_link(rest) {
+                let link = TextStyle {
+                    href: Some(url),
+                    ..style.clone()
+                };
+                line.push(&label, &link);
+                i += len;
+                continue;
+            }
+        } else if c == '&' || c == '#' {
+            if let Some((decoded, len)) = decode_entity(rest) {
+                line.push(decoded.encode_utf8(&mut [0; 4]), &style);
+                i += len;
+                continue;
+            }
+        } else if c == '<' {
+            if let Some((tag, closing, len)) = parse_tag(rest) {
+                apply_tag(&mut style, tag, closing);
+                i += len;
+                continue;
+            }
+        } else if let Some(after) = rest.strip_prefix("**") {
+            if style.bold && closes(text, i) {
+                style.bold = false;
+                i += 2;
+                continue;
+            }
+            if !style.bold && opens(after, "**") {
+                style.bold = true;
+            } else {
+                // Never split an unmatched `**` into two italic delimiters
+                line.push("**", &style);
+            }
+            i += 2;
+            continue;
+        }
+
+        if c == '*' {
+            if style.italic && closes(text, i) {
+                style.italic = false;
+                i += 1;
+                continue;
+            }
+            if !style.italic && opens(&rest[1..], "*") {
+                style.italic = true;
+                i += 1;
+                continue;
+            }
+        }
+
+        line.push(&rest[..c.len_utf8()], &style);
+        i += c.len_utf8();
+    }
+
+    line
+}
+
+/// An opening delimiter must be followed by text and a later closer
+fn opens(after: &str, delimiter: &str) -> bool {
+    after.chars().next().is_some_and(|c| !c.is_whitespace()) && after.contains(delimiter)
+}
+
+/// A closing delimiter must follow non-whitespace text
+fn closes(text: &str, index: usize) -> bool {
+    text[..index]
+        .chars()
+        .next_back()
+        .is_some_and(|c| !c.is_whitespace())
+}
+
+/// Parses `[label](url)` at the start of `text`
+///
+/// Returns the decoded label and URL and the byte length of the whole link,
+/// or `None` if the link is malformed or its URL is unsafe. Parentheses in
+/// the URL must be balanced, as in Markdown, so `[x](https://w.org/A_(b))`
+/// keeps its last `)`.
+fn parse_link(text: &str) -> Option<(String, String, usize)> {
+    let label_end = text.find("](")?;
+    let label = &text[1..label_end];
+    let url_start = label_end + 2;
+    let url_len = closing_paren(&text[url_start..])?;
+    let url = decode_entities(text[url_start..url_start + url_len].trim());
+
+    if label.is_empty() || label.contains('[') || !is_safe_url(&url) {
+        return None;
+    }
+    Some((decode_entities(label), url, url_start + url_len + 1))
+}
+
+/// Byte index of the first `)` that closes no `(` before it
+fn closing_paren(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(index),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Formatting tags of the HTML subset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tag {
+    Bold,
+    Italic,
+    Sub,
+    Sup,
+}
+
+/// Parses a supported opening or closing tag at the start of `text`
+///
+/// Returns the tag, whether it is a closing tag, and its byte length.
+fn parse_tag(text: &str) -> Option<(Tag, bool, usize)> {
+    let end = text.find('>')?;
+    let inner = &text[1..end];
+    let (closing, name) = match inner.strip_prefix('/') {
+        Some(name) => (true, name),
+        None => (false, inner),
+    };
+
+    let tag = match name.trim_end().to_ascii_lowercase().as_str() {
+        "b" => Tag::Bold,
+        "i" => Tag::Italic,
+        "sub" => Tag::Sub,
+        "sup" => Tag::Sup,
+        _ => return None,
+    };
+    Some((tag, closing, end + 1))
+}
+
+/// Updates the current style for an HTML tag
+fn apply_tag(style: &mut TextStyle, tag: Tag, closing: bool) {
+    match tag {
+        Tag::Bold => style.bold = !closing,
+        Tag::Italic => style.italic = !closing,
+        Tag::Sub | Tag::Sup if closing => style.shift = None,
+        Tag::Sub => style.shift = Some(BaselineShift::Sub),
+        Tag::Sup => style.shift = Some(BaselineShift::Super),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::measure::HeuristicMeasurer;
+    use super::*;
+
+    fn styles(line: &TextLine) -> Vec<(&str, bool, bool, bool)> {
+        line.runs
+            .iter()
+            .map(|run| {
+                (
+                    run.text.as_str(),
+                    run.style.bold,
+                    run.style.italic,
+                    run.style.code,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_plain() {
+        let line = TextLine::parse("Hello World");
+        assert!(line.is_plain());
+        assert_eq!(line.text(), "Hello World");
+    }
+
+    #[test]
+    fn test_parse_bold_and_italic() {
+        let line = TextLine::parse("a **b** *c* ***d***");
+        assert_eq!(
+            styles(&line),
+            vec![
+                ("a ", false, false, false),
+                ("b", true, false, false),
+                (" ", false, false, false),
+                ("c", false, true, false),
+                (" ", false, false, false),
+                ("d", true, true, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_code_is_literal() {
+        let line = TextLine::parse("use `**raw**` here");
+        assert_eq!(line.text(), "use **raw** here");
+        assert_eq!(styles(&line)[1], ("**raw**", false, false, true));
+    }
+
+    #[test]
+    fn test_parse_unmatched_delimiters_are_literal() {
+        assert!(TextLine::parse("2 * 3 = 6").is_plain());
+        assert_eq!(TextLine::parse("**open").text(), "**open");
+        assert_eq!(TextLine::parse("a ` b").text(), "a ` b");
+    }
+
+    #[test]
+    fn test_parse_underscores_are_literal() {
+        let line = TextLine::parse("user_id and _private_");
+        assert!(line.is_plain());
+        assert_eq!(line.text(), "user_id and _private_");
+    }
+
+    #[test]
+    fn test_parse_escapes() {
+        let line = TextLine::parse(r"\*not italic\*");
+        assert!(line.is_plain());
+        assert_eq!(line.text(), "*not italic*");
+    }
+
+    #[test]
+    fn test_parse_link() {
+        let line = TextLine::parse("see [docs](https://example.com) now");
+        assert_eq!(line.text(), "see docs now");
+        assert_eq!(
+            line.runs[1].style.href.as_deref(),
+            Some("https://example.com")
+        );
+    }
+
+    #[test]
+    fn test_parse_link_with_parentheses_in_url() {
+        let line = TextLine::parse("[spec](https://en.wikipedia.org/wiki/Foo_(bar)) here");
+        assert_eq!(line.text(), "spec here");
+        assert_eq!(
+            line.runs[0].style.href.as_deref(),
+            Some("https://en.wikipedia.org/wiki/Foo_(bar)")
+        );
+    }
+
+    #[test]
+    fn test_parse_unsafe_link_is_literal() {
+        let line = TextLine::parse("[x](javascript:alert(1))");
+        assert!(line.is_plain());
+    }
+
+    #[test]
+    fn test_safe_urls() {
+        assert!(is_safe_url("https://example.com/a?b=c:d"));
+        assert!(is_safe_url("mailto:team@example.com"));
+        assert!(is_safe_url("/docs/api#section"));
+        assert!(!is_safe_url("javascript:alert(1)"));
+        assert!(!is_safe_url(" JavaScript:alert(1)"));
+        assert!(!is_safe_url("data:text/html,hi"));
+    }
+
+    #[test]
+    fn test_parse_html_subset() {
+        let line = TextLine::parse("<b>H</b><sub>2</sub>O <I>x</I><sup>n</sup>");
+        assert_eq!(line.text(), "H2O xn");
+        assert!(line.runs[0].style.bold);
+        assert_eq!(line.runs[1].style.shift, Some(BaselineShift::Sub));
+        assert!(line.runs[3].style.italic);
+        assert_eq!(line.runs[4].style.shift, Some(BaselineShift::Super));
+    }
+
+    #[test]
+    fn test_parse_other_tags_are_literal() {
+        let line = TextLine::parse("<span>x</span> <b class=\"y\">z <u>");
+        assert!(line.is_plain());
+        assert_eq!(line.text(), "<span>x</span> <b class=\"y\">z <u>");
+    }
+
+    #[test]
+    fn test_parse_unclosed_and_stray_tags() {
+        let line = TextLine::parse("a</i> <b>b");
+        assert_eq!(line.text(), "a b");
+        assert!(!line.runs[0].style.italic);
+        assert!(line.runs[1].style.bold);
+    }
+
+    #[test]
+    fn test_parse_decodes_references() {
+        let line = TextLine::parse("a #59; b &lt;T&gt; #quot;x#quot; #9829;");
+        assert!(line.is_plain());
+        assert_eq!(line.text(), "a ; b <T> \"x\" ♥");
+    }
+
+    #[test]
+    fn test_parse_decoded_markup_is_literal() {
+        let line = TextLine::parse("#42;not italic#42; &lt;b&gt;x");
+        assert!(line.is_plain());
+        assert_eq!(line.text(), "*not italic* <b>x");
+    }
+
+    #[test]
+    fn test_parse_math_segment() {
+        let line = TextLine::parse("weight $$\\sum_i w_i$$ total");
+        assert_eq!(line.text(), "weight ∑iwi total");
+        assert_eq!(line.runs[1].style.shift, Some(BaselineShift::Sub));
+    }
+
+    #[test]
+    fn test_parse_unclosed_math_is_literal() {
+        let line = TextLine::parse("price \\$\\$5 and $$ more");
+        assert!(line.is_plain());
+        assert_eq!(line.text(), "price $$5 and $$ more");
+    }
+
+    #[test]
+    fn test_script_runs_use_smaller_font() {
+        let font = Font::new("Arial", 20);
+        let style = TextStyle {
+            shift: Some(BaselineShift::Super),
+            ..TextStyle::default()
+        };
+        assert_eq!(style.font(&font).size, 14);
+    }
+
+    #[test]
+    fn test_width_uses_run_fonts() {
+        let font = Font::new("Arial", 14);
+        let plain = TextLine::parse("width").width(&HeuristicMeasurer, &font);
+        let bold = TextLine::parse("**width**").width(&HeuristicMeasurer, &font);
+        let code = TextLine::parse("`width`").width(&HeuristicMeasurer, &font);
+        assert!(bold > plain);
+        assert!((code - 5.0 * 14.0 * 0.6).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_slice_across_runs() {
+        let line = TextLine::parse("ab**cd**ef");
+        let slice = line.slice(1..5);
+        assert_eq!(
+            styles(&slice),
+            vec![
+                ("b", false, false, false),
+                ("cd", true, false, false),
+                ("e", false, false, false)
+            ]
+        );
+    }
+}
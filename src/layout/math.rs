@@ -0,0 +1,426 @@
+//! TeX-subset typesetting for `$$...$$` label segments
+//!
+//! Formulas are laid out inline as styled runs, so they are measured and
+//! wrapped like any other label text and render as `<tspan>` elements:
+//!
+//! - Latin letters are italic; digits and punctuation are upright
+//! - `x^2`, `x_i`, `x^{n+1}` become superscript and subscript runs
+//! - `\frac{a}{b}` becomes a superscript numerator, a fraction slash and a
+//!   subscript denominator
+//! - `\alpha` ... `\Omega` and common symbols (`\sum`, `\infty`, `\leq`,
+//!   `\to`, ...) map to their Unicode characters
+//! - `\sqrt{x}`, `\text{...}`/`\mathrm{...}`, `\mathbf{...}` and the spacing
+//!   commands `\,`, `\;`, `\quad` are supported; `\left`/`\right` are ignored
+//!
+//! Scripts do not nest: a script inside a script keeps the outer position.
+//! Unknown commands are shown literally, as is everything past
+//! [`MAX_DEPTH`] nested groups and command arguments. Binary operators and relations are
+//! spaced with non-breaking spaces so wrapping never splits a formula
+//! around them.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+use super::markup::{BaselineShift, TextLine, TextStyle};
+
+/// Deepest nesting of groups and command arguments that is typeset, so a
+/// run of `{` cannot overflow the stack
+const MAX_DEPTH: usize = 64;
+
+/// Space placed around binary operators and relations
+const OPERATOR_SPACE: &str = "\u{A0}";
+
+/// Narrow space for `\,` and `\;`
+const THIN_SPACE: &str = "\u{202F}";
+
+/// Greek letters by command name
+const GREEK: &[(&str, char)] = &[
+    ("alpha", 'α'),
+    ("beta", 'β'),
+    ("gamma", 'γ'),
+    ("delta", 'δ'),
+    ("epsilon", 'ϵ'),
+    ("varepsilon", 'ε'),
+    ("zeta", 'ζ'),
+    ("eta", 'η'),
+    ("theta", 'θ'),
+    ("vartheta", 'ϑ'),
+    ("iota", 'ι'),
+    ("kappa", 'κ'),
+    ("lambda", 'λ'),
+    ("mu", 'μ'),
+    ("nu", 'ν'),
+    ("xi", 'ξ'),
+    ("pi", 'π'),
+    ("rho", 'ρ'),
+    ("sigma", 'σ'),
+    ("tau", 'τ'),
+    ("upsilon", 'υ'),
+    ("phi", 'ϕ'),
+    ("varphi", 'φ'),
+    ("chi", 'χ'),
+    ("psi", 'ψ'),
+    ("omega", 'ω'),
+    ("Gamma", 'Γ'),
+    ("Delta", 'Δ'),
+    ("Theta", 'Θ'),
+    ("Lambda", 'Λ'),
+    ("Xi", 'Ξ'),
+    ("Pi", 'Π'),
+    ("Sigma", 'Σ'),
+    ("Upsilon", 'Υ'),
+    ("Phi", 'Φ'),
+    ("Psi", 'Ψ'),
+    ("Omega", 'Ω'),
+];
+
+/// Upright symbols by command name
+const SYMBOLS: &[(&str, char)] = &[
+    ("sum", '∑'),
+    ("prod", '∏'),
+    ("int", '∫'),
+    ("oint", '∮'),
+    ("infty", '∞'),
+    ("partial", '∂'),
+    ("nabla", '∇'),
+    ("forall", '∀'),
+    ("exists", '∃'),
+    ("emptyset", '∅'),
+    ("neg", '¬'),
+    ("ldots", '…'),
+    ("cdots", '⋯'),
+    ("prime", '′'),
+    ("circ", '∘'),
+    ("deg", '°'),
+    ("hbar", 'ℏ'),
+    ("ell", 'ℓ'),
+];
+
+/// Binary operators and relations by command name
+const OPERATORS: &[(&str, char)] = &[
+    ("pm", '±'),
+    ("mp", '∓'),
+    ("times", '×'),
+    ("div", '÷'),
+    ("cdot", '·'),
+    ("leq", '≤'),
+    ("le", '≤'),
+    ("geq", '≥'),
+    ("ge", '≥'),
+    ("neq", '≠'),
+    ("ne", '≠'),
+    ("approx", '≈'),
+    ("equiv", '≡'),
+    ("sim", '∼'),
+    ("propto", '∝'),
+    ("in", '∈'),
+    ("notin", '∉'),
+    ("subset", '⊂'),
+    ("subseteq", '⊆'),
+    ("cup", '∪'),
+    ("cap", '∩'),
+    ("land", '∧'),
+    ("wedge", '∧'),
+    ("lor", '∨'),
+    ("vee", '∨'),
+    ("oplus", '⊕'),
+    ("otimes", '⊗'),
+    ("to", '→'),
+    ("rightarrow", '→'),
+    ("leftarrow", '←'),
+    ("gets", '←'),
+    ("leftrightarrow", '↔'),
+    ("Rightarrow", '⇒'),
+    ("Leftarrow", '⇐'),
+    ("Leftrightarrow", '⇔'),
+    ("implies", '⇒'),
+    ("iff", '⇔'),
+    ("mapsto", '↦'),
+];
+
+/// Typesets a TeX formula, appending its runs to `line`
+///
+/// # Arguments
+///
+/// * `tex` - Formula source without the `$$` delimiters
+/// * `base` - Style of the surrounding text (bold, links, ...)
+/// * `line` - Line the formula runs are appended to
+pub(super) fn typeset(tex: &str, base: &TextStyle, line: &mut TextLine) {
+    let mut typesetter = Typesetter {
+        chars: tex.chars().peekable(),
+        base,
+        line,
+        depth: 0,
+    };
+    typesetter.sequence(None, false);
+}
+
+/// Recursive-descent typesetter over the formula characters
+struct Typesetter<'a, 'b> {
+    chars: Peekable<Chars<'a>>,
+    base: &'b TextStyle,
+    line: &'b mut TextLine,
+    /// Atoms currently being typeset, one per enclosing group or argument
+    depth: usize,
+}
+
+impl Typesetter<'_, '_> {
+    /// Typesets atoms until the end of input (or a closing brace in a group)
+    fn sequence(&mut self, shift: Option<BaselineShift>, in_group: bool) {
+        while let Some(&c) = self.chars.peek() {
+            match c {
+                '}' if in_group => {
+                    self.chars.next();
+                    return;
+                }
+                '^' | '_' => {
+                    self.chars.next();
+                    let script = match (shift, c) {
+                        (Some(outer), _) => outer,
+                        (None, '^') => BaselineShift::Super,
+                        (None, _) => BaselineShift::Sub,
+                    };
+                    self.atom(Some(script));
+                }
+                _ => self.atom(shift),
+            }
+        }
+    }
+
+    /// Typesets a single atom: a character, a command or a braced group
+    fn atom(&mut self, shift: Option<BaselineShift>) {
+        if self.depth == MAX_DEPTH {
+            let rest: String = self.chars.by_ref().collect();
+            if !rest.is_empty() {
+                self.emit(&rest, false, shift);
+            }
+            return;
+        }
+        let Some(c) = self.chars.next() else {
+            return;
+        };
+
+        self.depth += 1;
+        match c {
+            '{' => self.sequence(shift, true),
+            '\\' => self.command(shift),
+            c if c.is_whitespace() => {}
+            c if c.is_alphabetic() => self.emit(&c.to_string(), true, shift),
+            '-' => self.operator('−', shift),
+            '+' | '=' | '<' | '>' => self.operator(c, shift),
+            '\'' => self.emit("′", false, shift),
+            c => self.emit(&c.to_string(), false, shift),
+        }
+        self.depth -= 1;
+    }
+
+    /// Typesets a command after its backslash
+    fn command(&mut self, shift: Option<BaselineShift>) {
+        let mut name = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !c.is_ascii_alphabetic() {
+                break;
+            }
+            name.push(c);
+            self.chars.next();
+        }
+
+        if name.is_empty() {
+            // Single-character commands: spacing and escaped symbols
+            match self.chars.next() {
+                Some(',' | ';' | ':') => self.emit(THIN_SPACE, false, shift),
+                Some(' ') => self.emit(OPERATOR_SPACE, false, shift),
+                Some(c) => self.emit(&c.to_string(), false, shift),
+                None => self.emit("\\", false, shift),
+            }
+            return;
+        }
+
+        let lookup = |table: &[(&str, char)]| {
+            table
+                .iter()
+                .find(|(command, _)| *command == name)
+                .map(|(_, c)| *c)
+        };
+
+        if let Some(c) = lookup(GREEK) {
+            self.emit(&c.to_string(), c.is_lowercase(), shift);
+        } else if let Some(c) = lookup(SYMBOLS) {
+            self.emit(&c.to_string(), false, shift);
+        } else if let Some(c) = lookup(OPERATORS) {
+            self.operator(c, shift);
+        } else {
+            match name.as_str() {
+                "frac" => {
+                    self.atom(Some(shift.unwrap_or(BaselineShift::Super)));
+                    self.emit("⁄", false, shift);
+                    self.atom(Some(shift.unwrap_or(BaselineShift::Sub)));
+                }
+                "sqrt" => {
+                    self.emit("√", false, shift);
+                    self.atom(shift);
+                }
+                "text" | "mathrm" | "operatorname" => {
+                    let text = self.raw_group();
+                    self.emit(&text, false, shift);
+                }
+                "mathbf" => {
+                    let text = self.raw_group();
+                    let style = TextStyle {
+                        bold: true,
+                        shift: shift.or(self.base.shift),
+                        ..self.base.clone()
+                    };
+                    self.line.push(&text, &style);
+                }
+                "quad" => self.emit("\u{A0}\u{A0}", false, shift),
+                "qquad" => self.emit("\u{A0}\u{A0}\u{A0}\u{A0}", false, shift),
+                "left" | "right" => {}
+                _ => self.emit(&format!("\\{}", name), false, shift),
+            }
+        }
+    }
+
+    /// Reads a braced group (or a single character) as literal text
+    fn raw_group(&mut self) -> String {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        if self.chars.next_if_eq(&'{').is_none() {
+            return self.chars.next().map(String::from).unwrap_or_default();
+        }
+
+        let mut text = String::new();
+        let mut depth = 0;
+        for c in self.chars.by_ref() {
+            match c {
+                '{' => depth += 1,
+                '}' if depth == 0 => break,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            text.push(c);
+        }
+        text
+    }
+
+    /// Emits an operator, spaced on the main line and tight in scripts
+    fn operator(&mut self, op: char, shift: Option<BaselineShift>) {
+        if shift.is_some() {
+            self.emit(&op.to_string(), false, shift);
+        } else {
+            self.emit(&format!("{0}{1}{0}", OPERATOR_SPACE, op), false, shift);
+        }
+    }
+
+    /// Appends text with the math style on top of the surrounding style
+    fn emit(&mut self, text: &str, italic: bool, shift: Option<BaselineShift>) {
+        let style = TextStyle {
+            italic: self.base.italic || italic,
+            shift: shift.or(self.base.shift),
+            ..self.base.clone()
+        };
+        self.line.push(text, &style);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn math(tex: &str) -> TextLine {
+        let mut line = TextLine::default();
+        typeset(tex, &TextStyle::default(), &mut line);
+        line
+    }
+
+    fn runs(line: &TextLine) -> Vec<(&str, bool, Option<BaselineShift>)> {
+        line.runs
+            .iter()
+            .map(|run| (run.text.as_str(), run.style.italic, run.style.shift))
+            .collect()
+    }
+
+    #[test]
+    fn test_variables_italic_digits_upright() {
+        assert_eq!(
+            runs(&math("2x")),
+            vec![("2", false, None), ("x", true, None)]
+        );
+    }
+
+    #[test]
+    fn test_scripts() {
+        assert_eq!(
+            runs(&math("x^2 w_i")),
+            vec![
+                ("x", true, None),
+                ("2", false, Some(BaselineShift::Super)),
+                ("w", true, None),
+                ("i", true, Some(BaselineShift::Sub)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_group_script() {
+        let line = math("e^{n+1}");
+        assert_eq!(line.text(), "en+1");
+        assert!(line.runs[1..]
+            .iter()
+            .all(|run| run.style.shift == Some(BaselineShift::Super)));
+    }
+
+    #[test]
+    fn test_sum_with_subscript() {
+        let line = math(r"\sum_i w_i");
+        assert_eq!(line.text(), "∑iwi");
+        assert_eq!(line.runs[0].text, "∑");
+        assert!(!line.runs[0].style.italic);
+    }
+
+    #[test]
+    fn test_fraction() {
+        assert_eq!(
+            runs(&math(r"\frac{a}{b}")),
+            vec![
+                ("a", true, Some(BaselineShift::Super)),
+                ("⁄", false, None),
+                ("b", true, Some(BaselineShift::Sub)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_greek_and_operators() {
+        let line = math(r"\alpha \leq \Omega - 1");
+        assert_eq!(line.text(), "α\u{A0}≤\u{A0}Ω\u{A0}−\u{A0}1");
+        assert!(line.runs[0].style.italic);
+    }
+
+    #[test]
+    fn test_text_and_unknown_commands() {
+        assert_eq!(math(r"\text{if } x").text(), "if x");
+        assert_eq!(math(r"\foo").text(), r"\foo");
+    }
+
+    #[test]
+    fn test_deep_nesting_is_emitted_literally() {
+        let tex = format!("{}x{}", "{".repeat(50_000), "}".repeat(50_000));
+        let text = math(&tex).text();
+        assert!(text.starts_with(&format!("{}x}}", "{".repeat(50_000 - MAX_DEPTH))));
+
+        let fracs = r"\frac".repeat(50_000);
+        assert!(math(&fracs).text().contains(r"\frac\frac"));
+    }
+
+    #[test]
+    fn test_keeps_surrounding_style() {
+        let mut line = TextLine::default();
+        let bold = TextStyle {
+            bold: true,
+            ..TextStyle::default()
+        };
+        typeset("x^2", &bold, &mut line);
+        assert!(line.runs.iter().all(|run| run.style.bold));
+    }
+}
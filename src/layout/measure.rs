@@ -0,0 +1,358 @@
+//! Pluggable text measurement
+//!
+//! Layout code measures text through the [`TextMeasurer`] trait. The default
+//! [`HeuristicMeasurer`] estimates widths from character tables, while
+//! [`FontFileMeasurer`] reads advance widths and kerning from TrueType or
+//! OpenType font files.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use ttf_parser::{Face, GlyphId};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::error::{RenderError, RenderResult};
+use crate::options::Font;
+
+use super::text::{font_families, is_emoji_cluster, measure_text};
+
+/// Measures the rendered width of a line of text
+///
+/// Implementations must be thread-safe so render options can be shared.
+pub trait TextMeasurer: fmt::Debug + Send + Sync {
+    /// Returns the width in pixels of a single line of text drawn with `font`
+    fn measure(&self, text: &str, font: &Font) -> f64;
+}
+
+/// Default measurer based on per-character width estimates
+///
+/// See [`measure_text`](crate::layout::measure_text) for the heuristic.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicMeasurer;
+
+impl TextMeasurer for HeuristicMeasurer {
+    fn measure(&self, text: &str, font: &Font) -> f64 {
+        measure_text(text, font)
+    }
+}
+
+/// Measurer that reads glyph metrics from font files
+///
+/// Fonts are matched against each family in [`Font::family`] by their
+/// family name, picking the face whose weight is closest to the requested
+/// one. Text in families that were not loaded, emoji, and grapheme clusters
+/// with characters missing from the matched face fall back to
+/// [`HeuristicMeasurer`].
+///
+/// Kerning is read from the legacy `kern` table; GPOS pair adjustments are
+/// not applied. Glyph advances and kerning pairs are cached per face, and
+/// cloning the measurer shares the loaded faces and their caches.
+///
+/// ```rust,no_run
+/// use mermaid_svg_render::layout::FontFileMeasurer;
+/// use mermaid_svg_render::RenderOptions;
+///
+/// let measurer = FontFileMeasurer::new()
+///     .with_font_file("fonts/Inter-Regular.ttf")?
+///     .with_font_file("fonts/Inter-Bold.ttf")?;
+/// let options = RenderOptions::default()
+///     .with_font_family("Inter, sans-serif")
+///     .with_text_measurer(measurer);
+/// # Ok::<(), mermaid_svg_render::RenderError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FontFileMeasurer {
+    faces: Vec<Arc<FontFace>>,
+}
+
+impl FontFileMeasurer {
+    /// Creates a measurer with no fonts loaded
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads every face from TrueType/OpenType font data (or a collection)
+    ///
+    /// Returns [`RenderError::FontError`] if the data is not a valid font.
+    pub fn add_font_data(&mut self, data: impl Into<Vec<u8>>) -> RenderResult<()> {
+        let data: Arc<[u8]> = data.into().into();
+        let count = ttf_parser::fonts_in_collection(&data).unwrap_or(1);
+
+        for index in 0..count {
+            let face = FontFace::parse(Arc::clone(&data), index)?;
+            self.faces.push(Arc::new(face));
+        }
+        Ok(())
+    }
+
+    /// Loads every face from a TrueType/OpenType font file
+    ///
+    /// Returns [`RenderError::FontError`] if the file cannot be read or is
+    /// not a valid font.
+    pub fn add_font_file(&mut self, path: impl AsRef<Path>) -> RenderResult<()> {
+        let path = path.as_ref();
+        let data = fs::read(path).map_err(|e| {
+            RenderError::FontError(format!("failed to read '{}': {}", path.display(), e))
+        })?;
+        self.add_font_data(data)
+    }
+
+    /// Loads font data (builder pattern)
+    pub fn with_font_data(mut self, data: impl Into<Vec<u8>>) -> RenderResult<Self> {
+        self.add_font_data(data)?;
+        Ok(self)
+    }
+
+    /// Loads a font file (builder pattern)
+    pub fn with_font_file(mut self, path: impl AsRef<Path>) -> RenderResult<Self> {
+        self.add_font_file(path)?;
+        Ok(self)
+    }
+
+    /// Returns the family names of all loaded faces
+    pub fn families(&self) -> Vec<&str> {
+        self.faces
+            .iter()
+            .filter_map(|face| face.families.first().map(String::as_str))
+            .collect()
+    }
+
+    /// Finds the loaded face best matching a font
+    fn select_face(&self, font: &Font) -> Option<&FontFace> {
+        let target_weight: i32 = if font.is_bold() { 700 } else { 400 };
+
+        font_families(&font.family).find_map(|family| {
+            self.faces
+                .iter()
+                .filter(|face| face.families.contains(&family))
+                .min_by_key(|face| (face.weight as i32 - target_weight).abs())
+                .map(Arc::as_ref)
+        })
+    }
+}
+
+impl TextMeasurer for FontFileMeasurer {
+    fn measure(&self, text: &str, font: &Font) -> f64 {
+        match self.select_face(font) {
+            Some(face) => face.measure(text, font),
+            None => HeuristicMeasurer.measure(text, font),
+        }
+    }
+}
+
+/// A parsed font face with metric caches
+struct FontFace {
+    data: Arc<[u8]>,
+    index: u32,
+    /// Lowercase family names (legacy and typographic)
+    families: Vec<String>,
+    weight: u16,
+    units_per_em: f64,
+    /// Glyph id and advance (in font units) per character
+    glyphs: Mutex<HashMap<char, Option<(GlyphId, f64)>>>,
+    /// Kerning adjustment (in font units) per glyph pair
+    kerning: Mutex<HashMap<(GlyphId, GlyphId), f64>>,
+}
+
+impl FontFace {
+    /// Parses a face and reads the metadata needed for font matching
+    fn parse(data: Arc<[u8]>, index: u32) -> RenderResult<Self> {
+        let face = Face::parse(&data, index)
+            .map_err(|e| RenderError::FontError(format!("invalid font data: {}", e)))?;
+
+        let mut families: Vec<String> = face
+            .names()
+            .into_iter()
+            .filter(|name| {
+                name.name_id == ttf_parser::name_id::TYPOGRAPHIC_FAMILY
+                    || name.name_id == ttf_parser::name_id::FAMILY
+            })
+            .filter_map(|name| name.to_string())
+            .map(|name| name.to_lowercase())
+            .collect();
+        families.dedup();
+
+        let weight = face.weight().to_number();
+        let units_per_em = face.units_per_em().max(1) as f64;
+
+        Ok(Self {
+            data: Arc::clone(&data),
+            index,
+            families,
+            weight,
+            units_per_em,
+            glyphs: Mutex::new(HashMap::new()),
+            kerning: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Measures text, parsing the face only when a cache lookup misses
+    ///
+    /// Emoji clusters and clusters with a character missing from the face
+    /// are measured with the heuristic instead.
+    fn measure(&self, text: &str, font: &Font) -> f64 {
+        let scale = font.size as f64 / self.units_per_em;
+        let mut glyphs = self.glyphs.lock().unwrap_or_else(|e| e.into_inner());
+        let mut kerning = self.kerning.lock().unwrap_or_else(|e| e.into_inner());
+        let mut face: Option<Face<'_>> = None;
+
+        let mut width = 0.0;
+        let mut previous: Option<GlyphId> = None;
+
+        for cluster in text.graphemes(true) {
+            let cluster_glyphs: Option<Vec<(GlyphId, f64)>> = if is_emoji_cluster(cluster) {
+                None
+            } else {
+                cluster
+                    .chars()
+                    .map(|c| {
+                        *glyphs.entry(c).or_insert_with(|| {
+                            let face = self.parsed(&mut face)?;
+                            let id = face.glyph_index(c)?;
+                            let advance = face.glyph_hor_advance(id)?;
+                            Some((id, advance as f64))
+                        })
+                    })
+                    .collect()
+            };
+
+            let Some(cluster_glyphs) = cluster_glyphs else {
+                width += HeuristicMeasurer.measure(cluster, font);
+                previous = None;
+                continue;
+            };
+
+            for (id, advance) in cluster_glyphs {
+                if let Some(left) = previous {
+                    let kern = *kerning.entry((left, id)).or_insert_with(|| {
+                        self.parsed(&mut face)
+                            .map(|face| pair_kerning(face, left, id))
+                            .unwrap_or(0.0)
+                    });
+                    width += kern * scale;
+                }
+                width += advance * scale;
+                previous = Some(id);
+            }
+        }
+
+        width
+    }
+
+    /// Returns the parsed face, parsing it on first use
+    fn parsed<'a, 'f>(&'f self, slot: &'a mut Option<Face<'f>>) -> Option<&'a Face<'f>> {
+        if slot.is_none() {
+            *slot = Face::parse(&self.data, self.index).ok();
+        }
+        slot.as_ref()
+    }
+}
+
+impl fmt::Debug for FontFace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FontFace")
+            .field("families", &self.families)
+            .field("weight", &self.weight)
+            .finish()
+    }
+}
+
+/// Sums horizontal kerning for a glyph pair across all `kern` subtables
+fn pair_kerning(face: &Face<'_>, left: GlyphId, right: GlyphId) -> f64 {
+    let Some(kern) = face.tables().kern else {
+        return 0.0;
+    };
+
+    kern.subtables
+        .into_iter()
+        .filter(|subtable| subtable.horizontal && !subtable.variable && !subtable.has_cross_stream)
+        .filter_map(|subtable| subtable.glyphs_kerning(left, right))
+        .map(f64::from)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEJAVU_SANS: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans.ttf");
+
+    fn dejavu() -> FontFileMeasurer {
+        FontFileMeasurer::new().with_font_data(DEJAVU_SANS).unwrap()
+    }
+
+    #[test]
+    fn test_heuristic_measurer_matches_measure_text() {
+        let font = Font::new("Arial", 14);
+        let width = HeuristicMeasurer.measure("Hello", &font);
+        assert!((width - measure_text("Hello", &font)).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_font_file_families() {
+        assert_eq!(dejavu().families(), vec!["dejavu sans"]);
+    }
+
+    #[test]
+    fn test_font_file_advance_widths() {
+        let measurer = dejavu();
+        let font = Font::new("DejaVu Sans, sans-serif", 20);
+        let narrow = measurer.measure("iiii", &font);
+        let wide = measurer.measure("mmmm", &font);
+        assert!(wide > narrow * 2.0);
+        // Width scales linearly with font size
+        let double = measurer.measure("mmmm", &Font::new("DejaVu Sans", 40));
+        assert!((double - wide * 2.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_font_file_applies_kerning() {
+        let measurer = dejavu();
+        let font = Font::new("DejaVu Sans", 100);
+        let pair = measurer.measure("AV", &font);
+        let separate = measurer.measure("A", &font) + measurer.measure("V", &font);
+        assert!(pair < separate);
+    }
+
+    #[test]
+    fn test_font_file_cache_is_stable() {
+        let measurer = dejavu();
+        let font = Font::new("DejaVu Sans", 14);
+        let first = measurer.measure("Hello World", &font);
+        let second = measurer.measure("Hello World", &font);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_font_file_unknown_family_falls_back() {
+        let measurer = dejavu();
+        let font = Font::new("Arial", 14);
+        assert!((measurer.measure("Hello", &font) - measure_text("Hello", &font)).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_font_file_missing_glyph_falls_back() {
+        let measurer = dejavu();
+        let font = Font::new("DejaVu Sans", 14);
+        // U+E000 (private use) has no glyph in DejaVu Sans
+        let width = measurer.measure("\u{E000}", &font);
+        assert!((width - measure_text("\u{E000}", &font)).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_font_file_emoji_falls_back() {
+        let measurer = dejavu();
+        let font = Font::new("DejaVu Sans", 14);
+        let family = "👨\u{200D}👩\u{200D}👧";
+        assert!((measurer.measure(family, &font) - measure_text(family, &font)).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_font_file_invalid_data() {
+        let result = FontFileMeasurer::new().with_font_data(vec![0u8; 16]);
+        assert!(matches!(result, Err(RenderError::FontError(_))));
+    }
+}
@@ -1,9 +1,23 @@
 //! Shared layout utilities for diagram rendering
 
 mod bounds;
+mod entities;
+mod markup;
+mod math;
+mod measure;
 mod text;
+mod viewport;
+mod wrap;
 
 pub use bounds::ContentBounds;
+pub use entities::{decode_entities, decode_entity};
+pub use markup::{
+    is_safe_url, BaselineShift, TextLine, TextRun, TextStyle, CODE_FONT_FAMILY, SCRIPT_SCALE,
+};
+pub use measure::{FontFileMeasurer, HeuristicMeasurer, TextMeasurer};
 pub use text::{
-    calculate_text_box_height, calculate_text_box_width, split_by_line_breaks, text_width,
+    calculate_text_box_height, calculate_text_box_width, measure_text, split_by_line_breaks,
+    text_width,
 };
+pub use viewport::{fit_content, Viewport};
+pub use wrap::{wrap_line, wrap_text};
@@ -3,6 +3,65 @@
 //! Provides approximate text width calculations for layout purposes.
 //! These utilities help position text elements without requiring
 //! actual font rendering.
+//!
+//! Text is measured per grapheme cluster, so combining marks, emoji
+//! sequences (ZWJ, skin tones, flags) and East Asian wide characters
+//! each get a sensible width.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
+
+use crate::options::Font;
+
+use super::markup::TextLine;
+use super::measure::TextMeasurer;
+
+/// Broad classification of a font family used to pick width estimates
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FontClass {
+    /// Proportional sans-serif fonts (measured with the Arial table)
+    SansSerif,
+    /// Proportional serif fonts (slightly narrower than Arial)
+    Serif,
+    /// Fixed-width fonts
+    Monospace,
+}
+
+/// Width of a monospace glyph relative to the font size
+const MONOSPACE_ADVANCE: f64 = 0.6;
+
+/// Width of serif text relative to the Arial estimate
+const SERIF_SCALE: f64 = 0.92;
+
+/// Extra width of bold text relative to regular weight
+const BOLD_SCALE: f64 = 1.08;
+
+/// Splits a CSS font family list into unquoted, lowercase family names
+pub(crate) fn font_families(family: &str) -> impl Iterator<Item = String> + '_ {
+    family
+        .split(',')
+        .map(|f| {
+            f.trim()
+                .trim_matches(|c| c == '"' || c == '\'')
+                .to_lowercase()
+        })
+        .filter(|f| !f.is_empty())
+}
+
+/// Classifies a CSS font family list by its first family
+fn classify_family(family: &str) -> FontClass {
+    let first = font_families(family).next().unwrap_or_default();
+
+    match first.as_str() {
+        "monospace" | "courier" | "courier new" | "consolas" | "menlo" | "monaco" => {
+            FontClass::Monospace
+        }
+        f if f.contains("mono") || f.contains("code") => FontClass::Monospace,
+        "serif" | "times" | "times new roman" | "georgia" | "garamond" | "cambria" | "palatino"
+        | "book antiqua" => FontClass::Serif,
+        _ => FontClass::SansSerif,
+    }
+}
 
 /// Returns approximate character width for Arial font at 14px base size.
 ///
@@ -39,10 +98,65 @@ fn char_width(c: char) -> f64 {
     }
 }
 
+/// Width of an East Asian wide or fullwidth character at 14px base size
+const WIDE_CHAR_WIDTH: f64 = 14.0;
+
+/// Width of an emoji (including ZWJ sequences and flags) at 14px base size
+const EMOJI_WIDTH: f64 = 17.0;
+
+/// Returns approximate width of a grapheme cluster at 14px base size.
+///
+/// Only the base character determines the width: combining marks,
+/// variation selectors and joiners inside the cluster add nothing.
+fn cluster_width(cluster: &str) -> f64 {
+    let Some(base) = cluster.chars().next() else {
+        return 0.0;
+    };
+
+    if is_emoji_cluster(cluster) {
+        return EMOJI_WIDTH;
+    }
+
+    match base.width() {
+        // Control characters, zero-width characters and stray combining marks
+        None | Some(0) => 0.0,
+        Some(1) => char_width(base),
+        Some(_) => WIDE_CHAR_WIDTH,
+    }
+}
+
+/// Returns the number of monospace columns a grapheme cluster occupies
+fn cluster_columns(cluster: &str) -> usize {
+    let Some(base) = cluster.chars().next() else {
+        return 0;
+    };
+
+    if is_emoji_cluster(cluster) {
+        return 2;
+    }
+    base.width().unwrap_or(0)
+}
+
+/// Checks whether a grapheme cluster renders as an emoji
+///
+/// Covers pictographs, regional indicator flags, and sequences forced to
+/// emoji presentation with U+FE0F or a keycap (U+20E3).
+pub(crate) fn is_emoji_cluster(cluster: &str) -> bool {
+    let mut chars = cluster.chars();
+    let Some(base) = chars.next() else {
+        return false;
+    };
+
+    matches!(base as u32, 0x1F000..=0x1FAFF) || chars.any(|c| c == '\u{FE0F}' || c == '\u{20E3}')
+}
+
 /// Calculates approximate pixel width of text for layout purposes
 ///
 /// Uses character-width approximations for Arial font family,
-/// scaled proportionally to the specified font size.
+/// scaled proportionally to the specified font size. Each grapheme
+/// cluster is measured by its base character: East Asian wide and
+/// fullwidth characters take a full em, emoji sequences count as a single
+/// emoji, and zero-width characters and combining marks add nothing.
 ///
 /// # Arguments
 ///
@@ -53,14 +167,45 @@ fn char_width(c: char) -> f64 {
 ///
 /// Approximate width in pixels
 pub fn text_width(text: &str, font_size: u32) -> f64 {
-    let base_width: f64 = text.chars().map(char_width).sum();
+    let base_width: f64 = text.graphemes(true).map(cluster_width).sum();
     base_width * (font_size as f64 / 14.0)
 }
 
+/// Calculates approximate pixel width of text drawn with a specific font
+///
+/// Sans-serif families use the Arial width table, serif families a slightly
+/// narrower estimate and monospace families a fixed advance per character.
+/// Bold weights are widened slightly.
+///
+/// # Arguments
+///
+/// * `text` - The text string to measure
+/// * `font` - Font family, size and weight
+///
+/// # Returns
+///
+/// Approximate width in pixels
+pub fn measure_text(text: &str, font: &Font) -> f64 {
+    let width = match classify_family(&font.family) {
+        FontClass::SansSerif => text_width(text, font.size),
+        FontClass::Serif => text_width(text, font.size) * SERIF_SCALE,
+        FontClass::Monospace => {
+            let columns: usize = text.graphemes(true).map(cluster_columns).sum();
+            columns as f64 * font.size as f64 * MONOSPACE_ADVANCE
+        }
+    };
+
+    if font.is_bold() {
+        width * BOLD_SCALE
+    } else {
+        width
+    }
+}
+
 /// Splits text by HTML line break markers or newlines
 ///
-/// Recognizes `<br>`, `<br/>`, and `\n` as line separators.
-/// Trims whitespace from each line and filters empty lines.
+/// Recognizes `<br>`, `<br/>`, `<br />` (in any letter case) and `\n` as
+/// line separators. Trims whitespace from each line and filters empty lines.
 ///
 /// # Arguments
 ///
@@ -70,31 +215,68 @@ pub fn text_width(text: &str, font_size: u32) -> f64 {
 ///
 /// Vector of trimmed, non-empty line strings
 pub fn split_by_line_breaks(text: &str) -> Vec<String> {
-    text.replace("<br/>", "\n")
-        .replace("<br>", "\n")
-        .split('\n')
-        .map(|s| s.trim().to_string())
+    let mut lines = Vec::new();
+    let mut line_start = 0;
+    let mut search_from = 0;
+
+    while let Some(offset) = text[search_from..].find(['<', '\n']) {
+        let start = search_from + offset;
+        let marker_len = if text[start..].starts_with('\n') {
+            Some(1)
+        } else {
+            line_break_tag_len(&text[start..])
+        };
+
+        match marker_len {
+            Some(len) => {
+                lines.push(&text[line_start..start]);
+                line_start = start + len;
+                search_from = line_start;
+            }
+            None => search_from = start + 1,
+        }
+    }
+    lines.push(&text[line_start..]);
+
+    lines
+        .into_iter()
+        .map(str::trim)
         .filter(|s| !s.is_empty())
+        .map(str::to_string)
         .collect()
 }
 
+/// Returns the length of a `<br>` tag at the start of `text`
+fn line_break_tag_len(text: &str) -> Option<usize> {
+    let end = text.find('>')?;
+    let inner = text[1..end].trim_end();
+    let inner = inner.strip_suffix('/').unwrap_or(inner).trim_end();
+    inner.eq_ignore_ascii_case("br").then_some(end + 1)
+}
+
 /// Calculates the width of a text box containing multiple lines
 ///
 /// Finds the widest line and adds horizontal padding.
 ///
 /// # Arguments
 ///
-/// * `lines` - Lines of text to measure
-/// * `font_size` - Font size in pixels
+/// * `measurer` - Text measurer used for each line
+/// * `lines` - Lines of text to measure (each run with its own font)
+/// * `font` - Base font used to draw the lines
 /// * `padding` - Total horizontal padding (both sides combined)
 ///
 /// # Returns
 ///
 /// Width in pixels (max line width + padding)
-pub fn calculate_text_box_width(lines: &[String], font_size: u32, padding: f64) -> f64 {
+pub fn calculate_text_box_width(
+    measurer: &dyn TextMeasurer,
+    lines: &[TextLine],
+    font: &Font,
+    padding: f64,
+) -> f64 {
     let max_line_width = lines
         .iter()
-        .map(|line| text_width(line, font_size))
+        .map(|line| line.width(measurer, font))
         .fold(0.0_f64, f64::max);
     max_line_width + padding
 }
@@ -117,6 +299,7 @@ pub fn calculate_text_box_height(num_lines: usize, line_height: f64, padding: f6
 
 #[cfg(test)]
 mod tests {
+    use super::super::measure::HeuristicMeasurer;
     use super::*;
 
     #[test]
@@ -132,6 +315,81 @@ mod tests {
         assert!((width_28 - width_14 * 2.0).abs() < 0.001);
     }
 
+    #[test]
+    fn test_text_width_east_asian_wide() {
+        // Hangul, Han and Katakana are full-width
+        assert!((text_width("한국", 14) - 28.0).abs() < 0.001);
+        assert!((text_width("你好", 14) - 28.0).abs() < 0.001);
+        assert!((text_width("サーバー", 14) - 56.0).abs() < 0.001);
+        // Fullwidth Latin
+        assert!((text_width("ＡＢ", 14) - 28.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_text_width_combining_marks() {
+        // Decomposed "é" measures like "e"
+        assert!((text_width("e\u{301}", 14) - text_width("e", 14)).abs() < 0.001);
+        // Decomposed Hangul syllable measures like the precomposed one
+        assert!((text_width("\u{1112}\u{1161}\u{11AB}", 14) - text_width("한", 14)).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_text_width_zero_width_characters() {
+        assert!((text_width("a\u{200B}b", 14) - text_width("ab", 14)).abs() < 0.001);
+        assert!((text_width("a\u{FEFF}b", 14) - text_width("ab", 14)).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_text_width_emoji_sequences() {
+        let single = text_width("👋", 14);
+        assert!(single > text_width("a", 14));
+        // Skin tone modifier
+        assert!((text_width("👋🏽", 14) - single).abs() < 0.001);
+        // ZWJ family sequence
+        assert!((text_width("👨\u{200D}👩\u{200D}👧", 14) - single).abs() < 0.001);
+        // Regional indicator flag
+        assert!((text_width("🇰🇷", 14) - single).abs() < 0.001);
+        // Text symbol with emoji presentation selector
+        assert!((text_width("❤\u{FE0F}", 14) - single).abs() < 0.001);
+        // Keycap sequence
+        assert!((text_width("1\u{FE0F}\u{20E3}", 14) - single).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_measure_text_monospace_wide_columns() {
+        let font = Font::new("monospace", 10);
+        assert!((measure_text("한국", &font) - 24.0).abs() < 0.001); // 4 columns
+        assert!((measure_text("e\u{301}", &font) - 6.0).abs() < 0.001); // 1 column
+    }
+
+    #[test]
+    fn test_measure_text_sans_matches_text_width() {
+        let font = Font::new("Arial, sans-serif", 14);
+        assert!((measure_text("Hello", &font) - text_width("Hello", 14)).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_measure_text_monospace_uses_fixed_advance() {
+        let font = Font::new("\"Fira Mono\", monospace", 10);
+        assert!((measure_text("iiii", &font) - measure_text("MMMM", &font)).abs() < 0.001);
+        assert!((measure_text("abcd", &font) - 24.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_measure_text_serif_is_narrower() {
+        let sans = Font::new("Helvetica", 14);
+        let serif = Font::new("'Times New Roman', serif", 14);
+        assert!(measure_text("Hello", &serif) < measure_text("Hello", &sans));
+    }
+
+    #[test]
+    fn test_measure_text_bold_is_wider() {
+        let regular = Font::new("Arial", 14);
+        let mut bold = regular.clone();
+        bold.weight = Some("700".to_string());
+        assert!(measure_text("Hello", &bold) > measure_text("Hello", &regular));
+    }
+
     #[test]
     fn test_split_by_line_breaks_br() {
         let lines = split_by_line_breaks("Hello<br>World");
@@ -144,6 +402,12 @@ mod tests {
         assert_eq!(lines, vec!["Hello", "World"]);
     }
 
+    #[test]
+    fn test_split_by_line_breaks_br_variants() {
+        let lines = split_by_line_breaks("A<br />B<BR>C<Br/>D<brx>E");
+        assert_eq!(lines, vec!["A", "B", "C", "D<brx>E"]);
+    }
+
     #[test]
     fn test_split_by_line_breaks_newline() {
         let lines = split_by_line_breaks("Hello\nWorld");
@@ -158,8 +422,9 @@ mod tests {
 
     #[test]
     fn test_text_box_width_single_line() {
-        let lines = vec!["Hello".to_string()];
-        let width = calculate_text_box_width(&lines, 14, 20.0);
+        let lines = vec![TextLine::plain("Hello")];
+        let width =
+            calculate_text_box_width(&HeuristicMeasurer, &lines, &Font::new("Arial", 14), 20.0);
         // Width should be text width + padding
         let text_w = text_width("Hello", 14);
         assert!((width - (text_w + 20.0)).abs() < 0.001);
@@ -168,19 +433,21 @@ mod tests {
     #[test]
     fn test_text_box_width_uses_widest_line() {
         let lines = vec![
-            "Hi".to_string(),
-            "Hello World".to_string(),
-            "Hey".to_string(),
+            TextLine::plain("Hi"),
+            TextLine::plain("Hello World"),
+            TextLine::plain("Hey"),
         ];
-        let width = calculate_text_box_width(&lines, 14, 20.0);
+        let width =
+            calculate_text_box_width(&HeuristicMeasurer, &lines, &Font::new("Arial", 14), 20.0);
         let widest = text_width("Hello World", 14);
         assert!((width - (widest + 20.0)).abs() < 0.001);
     }
 
     #[test]
     fn test_text_box_width_empty_lines() {
-        let lines: Vec<String> = vec![];
-        let width = calculate_text_box_width(&lines, 14, 20.0);
+        let lines: Vec<TextLine> = vec![];
+        let width =
+            calculate_text_box_width(&HeuristicMeasurer, &lines, &Font::new("Arial", 14), 20.0);
         // Empty lines should return just padding
         assert!((width - 20.0).abs() < 0.001);
     }
@@ -0,0 +1,166 @@
+//! Fitting diagram content into the output canvas
+//!
+//! Maps the natural content size onto the canvas requested through
+//! [`RenderOptions`], honouring fixed sizes, maximum sizes and alignment.
+
+use crate::options::{Align, RenderOptions};
+
+/// Placement of the diagram content within the SVG canvas
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    /// Canvas width in pixels
+    pub width: u32,
+    /// Canvas height in pixels
+    pub height: u32,
+    /// Uniform scale applied to the content
+    pub scale: f64,
+    /// Horizontal offset of the scaled content
+    pub offset_x: f64,
+    /// Vertical offset of the scaled content
+    pub offset_y: f64,
+}
+
+impl Viewport {
+    /// Creates a viewport that shows the content at its natural size
+    pub fn unscaled(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            scale: 1.0,
+            offset_x: 0.0,
+            offset_y: 0.0,
+        }
+    }
+
+    /// Returns `true` if the content needs no transform
+    pub fn is_identity(&self) -> bool {
+        self.scale == 1.0 && self.offset_x == 0.0 && self.offset_y == 0.0
+    }
+}
+
+/// Calculates the canvas size and content transform for the given options
+///
+/// # Arguments
+///
+/// * `content_width` - Natural content width including padding
+/// * `content_height` - Natural content height including padding
+/// * `options` - Render options providing fixed/maximum sizes and alignment
+///
+/// # Returns
+///
+/// A [`Viewport`] whose content scale preserves the aspect ratio
+pub fn fit_content(content_width: u32, content_height: u32, options: &RenderOptions) -> Viewport {
+    if content_width == 0 || content_height == 0 {
+        return Viewport::unscaled(content_width, content_height);
+    }
+
+    let natural_w = content_width as f64;
+    let natural_h = content_height as f64;
+
+    // Fixed sizes; a single fixed dimension derives the other from the aspect ratio
+    let (mut width, mut height) = match (options.width, options.height) {
+        (Some(w), Some(h)) => (w as f64, h as f64),
+        (Some(w), None) => (w as f64, natural_h * w as f64 / natural_w),
+        (None, Some(h)) => (natural_w * h as f64 / natural_h, h as f64),
+        (None, None) => (natural_w, natural_h),
+    };
+
+    // Maximum sizes shrink the whole canvas uniformly
+    let mut shrink: f64 = 1.0;
+    if let Some(max_w) = options.max_width {
+        shrink = shrink.min(max_w as f64 / width);
+    }
+    if let Some(max_h) = options.max_height {
+        shrink = shrink.min(max_h as f64 / height);
+    }
+    width = (width * shrink).round().max(1.0);
+    height = (height * shrink).round().max(1.0);
+
+    if width == natural_w && height == natural_h {
+        return Viewport::unscaled(content_width, content_height);
+    }
+
+    let scale = (width / natural_w).min(height / natural_h);
+    let offset_x = align_offset(options.align_x, width - natural_w * scale);
+    let offset_y = align_offset(options.align_y, height - natural_h * scale);
+
+    Viewport {
+        width: width as u32,
+        height: height as u32,
+        scale,
+        offset_x,
+        offset_y,
+    }
+}
+
+/// Distributes leftover space on one axis according to the alignment
+fn align_offset(align: Align, slack: f64) -> f64 {
+    match align {
+        Align::Start => 0.0,
+        Align::Center => slack / 2.0,
+        Align::End => slack,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_default_is_unscaled() {
+        let viewport = fit_content(300, 200, &RenderOptions::default());
+        assert_eq!(viewport, Viewport::unscaled(300, 200));
+        assert!(viewport.is_identity());
+    }
+
+    #[test]
+    fn test_fit_fixed_size_preserves_aspect_ratio() {
+        let options = RenderOptions::default().with_size(600, 600);
+        let viewport = fit_content(300, 200, &options);
+        assert_eq!((viewport.width, viewport.height), (600, 600));
+        assert!((viewport.scale - 2.0).abs() < 0.001);
+        assert!((viewport.offset_x - 0.0).abs() < 0.001);
+        assert!((viewport.offset_y - 100.0).abs() < 0.001); // (600 - 400) / 2
+    }
+
+    #[test]
+    fn test_fit_alignment() {
+        let options = RenderOptions::default()
+            .with_size(600, 600)
+            .with_alignment(Align::Start, Align::End);
+        let viewport = fit_content(300, 200, &options);
+        assert!((viewport.offset_x - 0.0).abs() < 0.001);
+        assert!((viewport.offset_y - 200.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_fit_width_only_derives_height() {
+        let options = RenderOptions::default().with_width(150);
+        let viewport = fit_content(300, 200, &options);
+        assert_eq!((viewport.width, viewport.height), (150, 100));
+        assert!((viewport.scale - 0.5).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_fit_max_width_shrinks() {
+        let options = RenderOptions::default().with_max_width(150);
+        let viewport = fit_content(300, 200, &options);
+        assert_eq!((viewport.width, viewport.height), (150, 100));
+    }
+
+    #[test]
+    fn test_fit_max_width_does_not_enlarge() {
+        let options = RenderOptions::default().with_max_width(1000);
+        let viewport = fit_content(300, 200, &options);
+        assert_eq!(viewport, Viewport::unscaled(300, 200));
+    }
+
+    #[test]
+    fn test_fit_max_height_applies_after_fixed_width() {
+        let options = RenderOptions::default()
+            .with_width(600)
+            .with_max_height(200);
+        let viewport = fit_content(300, 200, &options);
+        assert_eq!((viewport.width, viewport.height), (300, 200));
+    }
+}
@@ -0,0 +1,270 @@
+//! Automatic line wrapping for labels
+//!
+//! Breaks text at Unicode line-break opportunities (UAX #14), so Latin text
+//! wraps between words while CJK text may wrap between characters without
+//! starting a line with closing punctuation or ending one with opening
+//! punctuation. Tokens wider than the limit are split between grapheme
+//! clusters, with a hyphen when the split falls inside a word.
+
+use std::ops::Range;
+
+use unicode_linebreak::{linebreaks, BreakOpportunity};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
+
+use crate::options::Font;
+
+use super::markup::TextLine;
+use super::measure::TextMeasurer;
+
+/// A wrapped line: a byte range of the source text, and whether a hyphen
+/// is appended because the line ends inside a word
+type WrappedRange = (Range<usize>, bool);
+
+/// Wraps text into lines no wider than `max_width`
+///
+/// # Arguments
+///
+/// * `measurer` - Text measurer used to check line widths
+/// * `text` - Text to wrap (newlines force a break)
+/// * `font` - Font the text is drawn with
+/// * `max_width` - Maximum line width in pixels
+///
+/// # Returns
+///
+/// Trimmed lines (at least one, possibly empty)
+pub fn wrap_text(
+    measurer: &dyn TextMeasurer,
+    text: &str,
+    font: &Font,
+    max_width: f64,
+) -> Vec<String> {
+    let line_text = |(range, hyphen): WrappedRange| {
+        let mut line = text[range].to_string();
+        if hyphen {
+            line.push('-');
+        }
+        line
+    };
+
+    wrap_ranges(text, max_width, |range| {
+        measurer.measure(&line_text(range), font)
+    })
+    .into_iter()
+    .map(line_text)
+    .collect()
+}
+
+/// Wraps a styled line into lines no wider than `max_width`
+///
+/// Works like [`wrap_text`], measuring each run with its own font and
+/// keeping run styles across the breaks.
+pub fn wrap_line(
+    measurer: &dyn TextMeasurer,
+    line: &TextLine,
+    font: &Font,
+    max_width: f64,
+) -> Vec<TextLine> {
+    let line_part = |(range, hyphen): WrappedRange| {
+        let mut part = line.slice(range);
+        if hyphen {
+            part.push_continued("-");
+        }
+        part
+    };
+
+    wrap_ranges(&line.text(), max_width, |range| {
+        line_part(range).width(measurer, font)
+    })
+    .into_iter()
+    .map(line_part)
+    .collect()
+}
+
+/// Breaks text into line ranges whose measured width fits `max_width`
+fn wrap_ranges(
+    text: &str,
+    max_width: f64,
+    measure: impl Fn(WrappedRange) -> f64,
+) -> Vec<WrappedRange> {
+    let fits = |range: Range<usize>| measure((trim_end(text, range), false)) <= max_width;
+
+    let mut lines = Vec::new();
+    let mut line = 0..0;
+
+    for (end, opportunity) in linebreaks(text) {
+        let segment_start = line.end;
+
+        if line.is_empty() || fits(line.start..end) {
+            line.end = end;
+        } else {
+            lines.push((trim_end(text, line), false));
+            line = segment_start..end;
+        }
+
+        // A single token wider than the limit is split between clusters
+        while !fits(line.clone()) {
+            let (head, split) = split_overlong(text, line.clone(), max_width, &measure);
+            lines.push(head);
+            line.start = trim_start(text, split..line.end).start;
+        }
+
+        if opportunity == BreakOpportunity::Mandatory && end < text.len() {
+            lines.push((trim_end(text, line), false));
+            line = end..end;
+        }
+    }
+
+    if !text[line.clone()].trim().is_empty() || lines.is_empty() {
+        lines.push((trim_end(text, line), false));
+    }
+
+    lines
+}
+
+/// Splits the longest fitting prefix off an overlong token
+///
+/// Always takes at least one grapheme cluster so wrapping makes progress.
+/// Returns the head and the byte offset where the remainder starts.
+fn split_overlong(
+    text: &str,
+    token: Range<usize>,
+    max_width: f64,
+    measure: &impl Fn(WrappedRange) -> f64,
+) -> (WrappedRange, usize) {
+    let start = token.start;
+    let clusters: Vec<(usize, &str)> = text[token]
+        .grapheme_indices(true)
+        .map(|(offset, cluster)| (start + offset, cluster))
+        .collect();
+    let first_end = start + clusters[0].1.len();
+    let mut best = ((start..first_end, false), first_end);
+
+    for &(split, next) in clusters.iter().skip(1) {
+        let hyphen = needs_hyphen(&text[start..split], next);
+
+        if measure((start..split, hyphen)) > max_width {
+            break;
+        }
+        best = ((start..split, hyphen), split);
+    }
+
+    best
+}
+
+/// Drops trailing whitespace from a range
+fn trim_end(text: &str, range: Range<usize>) -> Range<usize> {
+    range.start..range.start + text[range].trim_end().len()
+}
+
+/// Drops leading whitespace from a range
+fn trim_start(text: &str, range: Range<usize>) -> Range<usize> {
+    range.end - text[range.clone()].trim_start().len()..range.end
+}
+
+/// Checks whether a split between `head` and `next` falls inside a word
+///
+/// Wide (CJK) characters are never hyphenated.
+fn needs_hyphen(head: &str, next: &str) -> bool {
+    let is_narrow_letter = |c: char| c.is_alphanumeric() && c.width() == Some(1);
+
+    match (head.chars().last(), next.chars().next()) {
+        (Some(last), Some(first)) => is_narrow_letter(last) && is_narrow_letter(first),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::measure::HeuristicMeasurer;
+    use super::*;
+
+    fn font() -> Font {
+        Font::new("monospace", 10) // 6px per column
+    }
+
+    fn wrap(text: &str, max_width: f64) -> Vec<String> {
+        wrap_text(&HeuristicMeasurer, text, &font(), max_width)
+    }
+
+    #[test]
+    fn test_wrap_fits_on_one_line() {
+        assert_eq!(wrap("hello world", 100.0), vec!["hello world"]);
+    }
+
+    #[test]
+    fn test_wrap_breaks_between_words() {
+        // 10 columns per line
+        assert_eq!(
+            wrap("the quick brown fox jumps", 60.0),
+            vec!["the quick", "brown fox", "jumps"]
+        );
+    }
+
+    #[test]
+    fn test_wrap_hyphenates_overlong_token() {
+        assert_eq!(
+            wrap("internationalization", 60.0),
+            vec!["internati-", "onalizati-", "on"]
+        );
+    }
+
+    #[test]
+    fn test_wrap_overlong_token_after_words() {
+        assert_eq!(wrap("a abcdefghijkl", 60.0), vec!["a", "abcdefghi-", "jkl"]);
+    }
+
+    #[test]
+    fn test_wrap_cjk_between_characters() {
+        // Hangul and Han are two columns wide: 4 characters per line
+        assert_eq!(wrap("你好世界你好", 48.0), vec!["你好世界", "你好"]);
+    }
+
+    #[test]
+    fn test_wrap_cjk_no_hyphen() {
+        let lines = wrap("テストテストテスト", 48.0);
+        assert!(lines.iter().all(|line| !line.ends_with('-')));
+    }
+
+    #[test]
+    fn test_wrap_cjk_kinsoku() {
+        // Closing punctuation never starts a line
+        let lines = wrap("これは、テストです。", 48.0);
+        assert!(lines.len() > 1);
+        for line in &lines {
+            assert!(
+                !line.starts_with('、') && !line.starts_with('。'),
+                "{:?}",
+                lines
+            );
+        }
+    }
+
+    #[test]
+    fn test_wrap_respects_newlines() {
+        assert_eq!(wrap("one\ntwo", 100.0), vec!["one", "two"]);
+    }
+
+    #[test]
+    fn test_wrap_empty() {
+        assert_eq!(wrap("", 100.0), vec![""]);
+    }
+
+    #[test]
+    fn test_wrap_narrower_than_one_cluster() {
+        assert_eq!(wrap("ab", 1.0), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_wrap_line_keeps_styles() {
+        let line = TextLine::parse("the **quick brown** fox");
+        let lines = wrap_line(&HeuristicMeasurer, &line, &font(), 60.0);
+
+        let texts: Vec<String> = lines.iter().map(TextLine::text).collect();
+        assert_eq!(texts, vec!["the quick", "brown fox"]);
+        assert_eq!(lines[0].runs[1].text, "quick");
+        assert!(lines[0].runs[1].style.bold);
+        assert_eq!(lines[1].runs[0].text, "brown");
+        assert!(lines[1].runs[0].style.bold);
+    }
+}
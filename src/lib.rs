@@ -12,6 +12,7 @@
 //! - Pure Rust implementation (no JavaScript/npm dependencies)
 //! - Light and dark theme support
 //! - Sequence diagram rendering (more diagram types coming soon)
+//! - Optional PNG output in pure Rust (`png` feature, see [`png`])
 //!
 //! ## Quick Start
 //!
@@ -29,14 +30,25 @@
 //! println!("{}", svg);
 //! ```
 
+pub mod accessibility;
+pub mod config;
 pub mod error;
 pub mod layout;
+pub mod links;
+pub mod metadata;
 pub mod options;
+#[cfg(feature = "png")]
+pub mod png;
 pub mod sequence;
+pub mod statements;
+pub mod styles;
 pub mod svg;
+pub mod theme;
 
 pub use error::{RenderError, RenderResult};
-pub use options::{RenderOptions, Theme, ThemeColors};
+pub use options::{Align, Font, FontOverride, RenderOptions, SequenceConfig};
+pub use statements::Statements;
+pub use theme::{ColorRole, Theme, ThemeColors, ThemeVariables};
 
 // Re-export mermaid-parser for convenience
 pub use mermaid_parser::{parse_diagram, DiagramType, ParseError};
@@ -68,16 +80,100 @@ pub use mermaid_parser::{parse_diagram, DiagramType, ParseError};
 /// let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
 /// ```
 pub fn render_sequence_diagram(input: &str, options: &RenderOptions) -> RenderResult<String> {
-    let diagram = parse_diagram(input).map_err(|e| RenderError::ParseError(e.to_string()))?;
+    render_sequence_diagram_with_warnings(input, options).map(|output| output.svg)
+}
+
+/// Rendered SVG together with problems that did not stop rendering
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderOutput {
+    /// The SVG markup
+    pub svg: String,
+    /// Warnings such as unknown configuration keys or invalid values
+    pub warnings: Vec<String>,
+}
+
+/// Render a sequence diagram and report non-fatal warnings
+///
+/// Front-matter `config` and `%%{init: ...}%%` directives are applied on
+/// top of `options` (see [`config`] for the precedence rules) and
+/// `classDef`/`class`/`style` statements are applied to participants (see
+/// [`styles`]); `accTitle`/`accDescr` become the SVG's title and description
+/// (see [`accessibility`]) and `link`/`links` statements link participants
+/// (see [`links`]). Anything that cannot be applied is returned as a warning
+/// instead of failing.
+///
+/// ```rust,ignore
+/// use mermaid_svg_render::{render_sequence_diagram_with_warnings, RenderOptions};
+///
+/// let input = "%%{init: {'theme': 'forest', 'colour': 'blue'}}%%\nsequenceDiagram\n    A->>B: Hi";
+/// let output = render_sequence_diagram_with_warnings(input, &RenderOptions::default())?;
+/// assert_eq!(output.warnings, vec!["unsupported config key 'colour' ignored"]);
+/// ```
+pub fn render_sequence_diagram_with_warnings(
+    input: &str,
+    options: &RenderOptions,
+) -> RenderResult<RenderOutput> {
+    let (
+        source,
+        Statements {
+            config,
+            styles,
+            accessibility,
+            links,
+        },
+    ) = statements::extract_statements(input);
+    let (mut options, mut warnings) = if options.use_diagram_config {
+        config.apply(options)
+    } else {
+        (options.clone(), Vec::new())
+    };
+    if has_wrap_directive(&source) {
+        options.wrap = true;
+    }
+    if !options.adaptive_theme && matches!(options.theme, Theme::Auto) {
+        options.theme = Theme::Light;
+    }
+
+    let diagram = parse_diagram(&source).map_err(|e| RenderError::ParseError(e.to_string()))?;
 
     match diagram {
-        DiagramType::Sequence(seq) => sequence::render(&seq, options),
+        DiagramType::Sequence(mut seq) => {
+            warnings.extend_from_slice(accessibility.warnings());
+            if accessibility.title.is_some() {
+                seq.accessibility.title = accessibility.title;
+            }
+            if accessibility.description.is_some() {
+                seq.accessibility.description = accessibility.description;
+            }
+            warnings.extend_from_slice(styles.warnings());
+            warnings.extend(
+                styles.unknown_participants(seq.participants.iter().map(|p| p.actor.as_str())),
+            );
+            if options.links {
+                warnings.extend_from_slice(links.warnings());
+                warnings.extend(
+                    links.unknown_participants(seq.participants.iter().map(|p| p.actor.as_str())),
+                );
+            }
+            Ok(RenderOutput {
+                svg: sequence::render_document(&seq, &options, &styles, &links, Some(input))?,
+                warnings,
+            })
+        }
         _ => Err(RenderError::UnsupportedDiagram(
             "Expected a sequence diagram".to_string(),
         )),
     }
 }
 
+/// Checks for Mermaid's `%%{wrap}%%` directive, which enables label wrapping
+fn has_wrap_directive(input: &str) -> bool {
+    input.lines().any(|line| {
+        let compact: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+        compact == "%%{wrap}%%"
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -98,6 +194,13 @@ sequenceDiagram
         assert!(svg.contains("Bob"));
     }
 
+    #[test]
+    fn test_wrap_directive() {
+        assert!(has_wrap_directive("%%{wrap}%%\nsequenceDiagram"));
+        assert!(has_wrap_directive("sequenceDiagram\n  %%{ wrap }%%"));
+        assert!(!has_wrap_directive("sequenceDiagram\n  %% wrap"));
+    }
+
     #[test]
     fn test_dark_theme() {
         let input = r#"
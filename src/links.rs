@@ -0,0 +1,256 @@
+//! Mermaid `link` and `links` statements
+//!
+//! ```text
+//! sequenceDiagram
+//!     participant Alice
+//!     link Alice: Dashboard @ https://dashboard.example.com/alice
+//!     links Bob: {"Wiki": "https://wiki.example.com/bob", "Logs": "https://logs.example.com/bob"}
+//!     Alice->>Bob: Hello
+//! ```
+//!
+//! A participant with a single link is wrapped in `<a href>`; one with several
+//! gets a `<title>` tooltip listing them. URLs must pass [`is_safe_url`], so
+//! `javascript:` and similar schemes are dropped with a warning.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde_json::Value;
+
+use crate::layout::is_safe_url;
+
+/// A labelled link target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    /// Text shown for the link
+    pub label: String,
+    /// Target URL
+    pub url: String,
+}
+
+/// Links collected from a diagram, by participant
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParticipantLinks {
+    links: BTreeMap<String, Vec<Link>>,
+    warnings: Vec<String>,
+}
+
+impl ParticipantLinks {
+    /// Returns `true` if the diagram defined no links
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+
+    /// Returns the links of a participant, in the order they were defined
+    pub fn of(&self, participant: &str) -> &[Link] {
+        self.links
+            .get(participant)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// Returns problems found while extracting the statements
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Returns warnings for statements naming participants that are not in
+    /// `participants`
+    pub fn unknown_participants<'a>(
+        &self,
+        participants: impl IntoIterator<Item = &'a str>,
+    ) -> Vec<String> {
+        let known: Vec<&str> = participants.into_iter().collect();
+        self.links
+            .keys()
+            .filter(|name| !known.contains(&name.as_str()))
+            .map(|name| format!("unknown participant '{}' in link statement", name))
+            .collect()
+    }
+
+    /// Adds a link after checking its URL
+    fn add(&mut self, participant: &str, label: &str, url: &str) {
+        let (label, url) = (label.trim(), url.trim());
+        if !is_safe_url(url) {
+            self.warnings.push(format!(
+                "unsafe link URL '{}' for '{}' ignored",
+                url, participant
+            ));
+            return;
+        }
+        let label = if label.is_empty() { url } else { label };
+        self.links
+            .entry(participant.to_string())
+            .or_default()
+            .push(Link {
+                label: label.to_string(),
+                url: url.to_string(),
+            });
+    }
+
+    /// Handles a `link` or `links` statement, given the text after the
+    /// keyword
+    ///
+    /// Keys of a `links` object are taken in the order they are written.
+    pub(crate) fn add_statement(&mut self, keyword: &str, rest: &str) -> bool {
+        match keyword {
+            "link" => self.add_link(rest),
+            "links" => self.add_links(rest),
+            _ => false,
+        }
+    }
+
+    /// Handles `link <participant>: <label> @ <url>`
+    fn add_link(&mut self, rest: &str) -> bool {
+        let Some((participant, target)) = rest.split_once(':') else {
+            return false;
+        };
+        let participant = participant.trim();
+        match target.split_once('@') {
+            Some((label, url)) => self.add(participant, label, url),
+            None => self.warnings.push(format!(
+                "invalid link '{}' ignored, expected '<label> @ <url>'",
+                target.trim()
+            )),
+        }
+        true
+    }
+
+    /// Handles `links <participant>: {"<label>": "<url>", ...}`
+    fn add_links(&mut self, rest: &str) -> bool {
+        let Some((participant, object)) = rest.split_once(':') else {
+            return false;
+        };
+        let participant = participant.trim();
+        match serde_json::from_str::<Entries>(object.trim()) {
+            Ok(Entries(entries)) => {
+                for (label, url) in &entries {
+                    match url {
+                        Value::String(url) => self.add(participant, label, url),
+                        _ => self.warnings.push(format!(
+                            "link '{}' for '{}' is not a string, ignored",
+                            label, participant
+                        )),
+                    }
+                }
+            }
+            _ => self.warnings.push(format!(
+                "invalid links for '{}' ignored, expected a JSON object",
+                participant
+            )),
+        }
+        true
+    }
+}
+
+/// The entries of a JSON object in source order
+///
+/// `serde_json::Map` sorts its keys, which would reorder a participant's menu.
+struct Entries(Vec<(String, Value)>);
+
+impl<'de> Deserialize<'de> for Entries {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> Visitor<'de> for EntriesVisitor {
+            type Value = Entries;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a JSON object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Entries, A::Error> {
+                let mut entries: Vec<(String, Value)> = Vec::new();
+                while let Some((key, value)) = map.next_entry::<String, Value>()? {
+                    // Like `JSON.parse`, a repeated key keeps its place and its last value
+                    match entries.iter_mut().find(|(k, _)| *k == key) {
+                        Some(entry) => entry.1 = value,
+                        None => entries.push((key, value)),
+                    }
+                }
+                Ok(Entries(entries))
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::statements::{extract_statements, Statements};
+
+    #[test]
+    fn test_link_statements() {
+        let input = "sequenceDiagram\n    link Alice: Dashboard @ https://example.com/a\n    link Alice: Wiki @ /wiki/alice\n    A->>B: Hi\n";
+        let (source, Statements { links, .. }) = extract_statements(input);
+
+        assert_eq!(source, "sequenceDiagram\n\n\n    A->>B: Hi\n");
+        assert_eq!(
+            links.of("Alice"),
+            [
+                Link {
+                    label: "Dashboard".to_string(),
+                    url: "https://example.com/a".to_string()
+                },
+                Link {
+                    label: "Wiki".to_string(),
+                    url: "/wiki/alice".to_string()
+                },
+            ]
+        );
+        assert!(links.of("Bob").is_empty());
+    }
+
+    #[test]
+    fn test_links_object() {
+        let (_, Statements { links, .. }) = extract_statements(
+            r#"links Bob: {"Wiki": "https://wiki.example.com", "Logs": "https://logs.example.com"}"#,
+        );
+        let labels: Vec<&str> = links.of("Bob").iter().map(|l| l.label.as_str()).collect();
+        assert_eq!(labels, ["Wiki", "Logs"]);
+        assert!(links.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_repeated_key_keeps_its_place_and_last_url() {
+        let (_, Statements { links, .. }) = extract_statements(
+            r#"links Bob: {"b": "https://b.one", "a": "https://a", "b": "https://b.two"}"#,
+        );
+        let entries: Vec<(&str, &str)> = links
+            .of("Bob")
+            .iter()
+            .map(|l| (l.label.as_str(), l.url.as_str()))
+            .collect();
+        assert_eq!(entries, [("b", "https://b.two"), ("a", "https://a")]);
+    }
+
+    #[test]
+    fn test_rejects_unsafe_and_invalid_links() {
+        let (_, Statements { links, .. }) = extract_statements(
+            "link A: Evil @ javascript:alert(1)\nlink A: no target\nlinks B: [1, 2]\nlinks C: {\"x\": 1}",
+        );
+        assert!(links.is_empty());
+        assert_eq!(
+            links.warnings(),
+            [
+                "unsafe link URL 'javascript:alert(1)' for 'A' ignored",
+                "invalid link 'no target' ignored, expected '<label> @ <url>'",
+                "invalid links for 'B' ignored, expected a JSON object",
+                "link 'x' for 'C' is not a string, ignored",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_participants() {
+        let (_, Statements { links, .. }) =
+            extract_statements("link Carol: Home @ https://example.com");
+        assert_eq!(
+            links.unknown_participants(["Alice"]),
+            vec!["unknown participant 'Carol' in link statement"]
+        );
+    }
+}
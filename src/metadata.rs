@@ -0,0 +1,223 @@
+//! Mermaid source embedded in rendered SVGs
+//!
+//! With [`RenderOptions::with_embedded_source`] the SVG carries the diagram
+//! source, the crate version and the effective options in its `<metadata>`:
+//!
+//! ```xml
+//! <metadata>
+//!   <msv:diagram xmlns:msv="https://github.com/Hebububu/msv" version="0.1.0">
+//!     <msv:source>sequenceDiagram
+//!     Alice->>Bob: Hello</msv:source>
+//!     <msv:options>{"font_size":14,"theme":"light",...}</msv:options>
+//!   </msv:diagram>
+//! </metadata>
+//! ```
+//!
+//! [`extract_source`] recovers the source for editing.
+
+use serde_json::{json, Value};
+
+use crate::options::{Align, FontOverride, RenderOptions, SequenceConfig};
+use crate::svg::Element;
+use crate::theme::{ColorRole, Theme, ThemeColors};
+
+/// Namespace of the embedded metadata elements
+pub const NAMESPACE: &str = "https://github.com/Hebububu/msv";
+
+/// Builds the `<msv:diagram>` element describing a rendered diagram
+pub fn diagram_metadata(source: &str, options: &RenderOptions) -> Element {
+    Element::new("msv:diagram")
+        .with_attr("xmlns:msv", NAMESPACE)
+        .with_attr("version", env!("CARGO_PKG_VERSION"))
+        .with_child(Element::new("msv:source").with_text(source))
+        .with_child(Element::new("msv:options").with_text(options_json(options)))
+}
+
+/// Returns the Mermaid source embedded in an SVG
+///
+/// Returns `None` if the SVG was rendered without
+/// [`RenderOptions::with_embedded_source`].
+///
+/// ```rust,ignore
+/// use mermaid_svg_render::{metadata::extract_source, render_sequence_diagram, RenderOptions};
+///
+/// let source = "sequenceDiagram\n    Alice->>Bob: Hello";
+/// let svg = render_sequence_diagram(source, &RenderOptions::default().with_embedded_source())?;
+/// assert_eq!(extract_source(&svg).as_deref(), Some(source));
+/// ```
+pub fn extract_source(svg: &str) -> Option<String> {
+    let start = svg.find("<msv:source")?;
+    let open_end = start + svg[start..].find('>')?;
+    if svg[..open_end].ends_with('/') {
+        return Some(String::new());
+    }
+    let content = &svg[open_end + 1..];
+    let end = content.find("</msv:source>")?;
+    Some(unescape_xml(&content[..end]))
+}
+
+/// The options that shape the output, as a JSON object with sorted keys
+///
+/// A custom theme is recorded with its resolved colours.
+fn options_json(options: &RenderOptions) -> String {
+    let mut json = json!({
+        "theme": options.theme.name(),
+        "width": options.width,
+        "height": options.height,
+        "max_width": options.max_width,
+        "max_height": options.max_height,
+        "align_x": align_name(options.align_x),
+        "align_y": align_name(options.align_y),
+        "use_max_width": options.use_max_width,
+        "padding": options.padding,
+        "font_family": options.font_family,
+        "font_size": options.font_size,
+        "participant_font": font_json(&options.participant_font),
+        "message_font": font_json(&options.message_font),
+        "wrap": options.wrap,
+        "wrap_width": options.wrap_width,
+        "sequence": sequence_json(&options.sequence),
+        "transparent": options.transparent_bg,
+        "style_block": options.style_block,
+        "inline_arrowheads": options.inline_arrowheads,
+        "accessible_summary": options.accessible_summary,
+        "links": options.links,
+        "id_prefix": options.id_prefix,
+        "precision": options.precision,
+        "minify": options.minify,
+        "pretty": options.pretty,
+        "use_diagram_config": options.use_diagram_config,
+        "adaptive_theme": options.adaptive_theme,
+    });
+    if let Theme::Custom(colors) = &options.theme {
+        json["colors"] = colors_json(colors);
+    }
+    json.to_string()
+}
+
+fn align_name(align: Align) -> &'static str {
+    match align {
+        Align::Start => "start",
+        Align::Center => "center",
+        Align::End => "end",
+    }
+}
+
+fn font_json(font: &FontOverride) -> Value {
+    json!({
+        "family": font.family,
+        "size": font.size,
+        "weight": font.weight,
+    })
+}
+
+fn sequence_json(sequence: &SequenceConfig) -> Value {
+    json!({
+        "mirror_actors": sequence.mirror_actors,
+        "actor_width": sequence.actor_width,
+        "actor_height": sequence.actor_height,
+        "actor_margin": sequence.actor_margin,
+        "actor_padding": sequence.actor_padding,
+        "actor_vertical_padding": sequence.actor_vertical_padding,
+        "message_margin": sequence.message_margin,
+        "message_text_margin": sequence.message_text_margin,
+        "self_message_height": sequence.self_message_height,
+        "self_loop_width": sequence.self_loop_width,
+        "self_loop_text_offset": sequence.self_loop_text_offset,
+    })
+}
+
+/// The colours of each role, keyed by [`ColorRole::name`], and the
+/// palette and stroke settings
+fn colors_json(colors: &ThemeColors) -> Value {
+    let mut json = json!({
+        "palette": colors.palette,
+        "line_width": colors.line_width,
+        "border_width": colors.border_width,
+        "corner_radius": colors.corner_radius,
+    });
+    for role in ColorRole::ALL {
+        json[role.name()] = colors.get(role).into();
+    }
+    json
+}
+
+/// Decodes the predefined XML entities and character references
+fn unescape_xml(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let reference = rest
+            .find(';')
+            .and_then(|end| Some((decode_reference(&rest[1..end])?, end + 1)));
+        match reference {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Decodes the name of an entity or character reference (without `&` and `;`)
+fn decode_reference(name: &str) -> Option<char> {
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        _ => {
+            let code = match name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => name.strip_prefix('#')?.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_round_trip() {
+        let source = "sequenceDiagram\r\n    A->>B: a < b & \"c\" #59; &lt;\r\n";
+        let svg = diagram_metadata(source, &RenderOptions::default()).to_string();
+        assert_eq!(extract_source(&svg).as_deref(), Some(source));
+    }
+
+    #[test]
+    fn test_options_are_recorded() {
+        let options = RenderOptions::default().with_id_prefix("x");
+        let metadata = diagram_metadata("", &options).to_string();
+        assert!(metadata.starts_with(&format!(
+            "<msv:diagram xmlns:msv=\"{}\" version=\"{}\">",
+            NAMESPACE,
+            env!("CARGO_PKG_VERSION")
+        )));
+        assert!(metadata.contains("&quot;id_prefix&quot;:&quot;x&quot;"));
+        assert!(metadata.contains("&quot;theme&quot;:&quot;light&quot;"));
+        assert!(metadata.contains("&quot;align_x&quot;:&quot;center&quot;"));
+        assert!(metadata.contains("&quot;use_diagram_config&quot;:true"));
+        assert!(!metadata.contains("&quot;colors&quot;"));
+    }
+
+    #[test]
+    fn test_missing_source() {
+        assert_eq!(extract_source("<svg/>"), None);
+        assert_eq!(
+            extract_source("<svg><msv:source/></svg>").as_deref(),
+            Some("")
+        );
+    }
+}
@@ -1,61 +1,224 @@
 //! Render options and theme configuration
 //!
 //! This module provides configuration types for customizing diagram rendering,
-//! including theme selection and color schemes.
+//! including theme selection, color schemes and output sizing.
 
-/// Theme for rendering diagrams
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::error::{RenderError, RenderResult};
+use crate::layout::{FontFileMeasurer, HeuristicMeasurer, TextMeasurer};
+use crate::svg::number::{NumberFormat, MAX_PRECISION};
+use crate::svg::PostProcessor;
+
+use crate::theme::ThemeVariables;
+pub use crate::theme::{Theme, ThemeColors};
+
+/// Alignment of scaled content within a fixed-size canvas
 ///
-/// Controls the overall color scheme of the rendered SVG.
+/// When both a width and a height are requested, the content is scaled
+/// uniformly to fit and any leftover space is distributed according to
+/// the alignment on each axis (like SVG's `preserveAspectRatio`).
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
-pub enum Theme {
-    /// Light theme with white background and dark text
+pub enum Align {
+    /// Align to the left (horizontal) or top (vertical) edge
+    Start,
+    /// Center the content
     #[default]
-    Light,
-    /// Dark theme with dark background and light text
-    Dark,
+    Center,
+    /// Align to the right (horizontal) or bottom (vertical) edge
+    End,
 }
 
-/// Colors used for rendering a specific theme
-///
-/// Contains all color values needed to render diagram elements consistently.
-#[derive(Debug, Clone)]
-pub struct ThemeColors {
-    /// Background color of the SVG canvas
-    pub background: String,
-    /// Primary text color
-    pub text: String,
-    /// Color for lines and arrows
-    pub line: String,
-    /// Background color for participant boxes
-    pub participant_bg: String,
-    /// Border color for participant boxes
-    pub participant_border: String,
+/// Resolved font used to draw and measure a text element
+#[derive(Debug, Clone, PartialEq)]
+pub struct Font {
+    /// CSS font family list (e.g. `"Inter, Arial, sans-serif"`)
+    pub family: String,
+    /// Font size in pixels
+    pub size: u32,
+    /// Optional CSS font weight (e.g. `"bold"` or `"600"`)
+    pub weight: Option<String>,
 }
 
-impl ThemeColors {
-    /// Returns the light theme color palette
-    pub fn light() -> Self {
+impl Font {
+    /// Creates a font with normal weight
+    pub fn new(family: impl Into<String>, size: u32) -> Self {
         Self {
-            background: "#ffffff".to_string(),
-            text: "#333333".to_string(),
-            line: "#333333".to_string(),
-            participant_bg: "#ecf0f1".to_string(),
-            participant_border: "#333333".to_string(),
+            family: family.into(),
+            size,
+            weight: None,
         }
     }
 
-    /// Returns the dark theme color palette
-    pub fn dark() -> Self {
+    /// Returns `true` if the weight is bold (`bold`, `bolder` or 600 and above)
+    pub fn is_bold(&self) -> bool {
+        match self.weight.as_deref() {
+            Some("bold") | Some("bolder") => true,
+            Some(w) => w.parse::<u32>().map(|w| w >= 600).unwrap_or(false),
+            None => false,
+        }
+    }
+}
+
+/// Font settings for one category of text (participants, messages)
+///
+/// Notes get their own settings once they are rendered.
+///
+/// Unset fields fall back to [`RenderOptions::font_family`] and
+/// [`RenderOptions::font_size`].
+///
+/// ```rust
+/// use mermaid_svg_render::{FontOverride, RenderOptions};
+///
+/// let options = RenderOptions::default()
+///     .with_font_family("Inter, sans-serif")
+///     .with_participant_font(FontOverride::default().with_size(16).with_weight("bold"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FontOverride {
+    /// Font family override
+    pub family: Option<String>,
+    /// Font size override in pixels
+    pub size: Option<u32>,
+    /// Font weight override
+    pub weight: Option<String>,
+}
+
+impl FontOverride {
+    /// Sets the font family (builder pattern)
+    pub fn with_family(mut self, family: impl Into<String>) -> Self {
+        self.family = Some(family.into());
+        self
+    }
+
+    /// Sets the font size (builder pattern)
+    pub fn with_size(mut self, size: u32) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// Sets the font weight (builder pattern)
+    pub fn with_weight(mut self, weight: impl Into<String>) -> Self {
+        self.weight = Some(weight.into());
+        self
+    }
+}
+
+/// Spacing and sizing of sequence diagrams
+///
+/// Field docs name the matching Mermaid `sequence` config key where one
+/// exists.
+///
+/// ```rust
+/// use mermaid_svg_render::{RenderOptions, SequenceConfig};
+///
+/// // Compact layout for slides: no bottom participant boxes, tighter rows
+/// let options = RenderOptions::default().with_sequence(
+///     SequenceConfig::default()
+///         .with_mirror_actors(false)
+///         .with_actor_margin(30.0)
+///         .with_message_margin(35.0),
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequenceConfig {
+    /// Repeat the participant boxes below the lifelines (`mirrorActors`)
+    pub mirror_actors: bool,
+    /// Minimum participant box width (`width`)
+    pub actor_width: f64,
+    /// Minimum participant box height (`height`)
+    pub actor_height: f64,
+    /// Minimum horizontal gap between participant boxes (`actorMargin`)
+    pub actor_margin: f64,
+    /// Horizontal padding inside participant boxes, split between both sides
+    pub actor_padding: f64,
+    /// Vertical padding inside participant boxes, split between top and bottom
+    pub actor_vertical_padding: f64,
+    /// Vertical distance between message rows (`messageMargin`)
+    pub message_margin: f64,
+    /// Horizontal room kept free around message labels, split between both sides
+    pub message_text_margin: f64,
+    /// Extra row height taken by a self-message loop
+    pub self_message_height: f64,
+    /// Width of the self-message loop
+    pub self_loop_width: f64,
+    /// Distance from the lifeline to the start of a self-message label
+    pub self_loop_text_offset: f64,
+}
+
+impl Default for SequenceConfig {
+    fn default() -> Self {
         Self {
-            background: "#1a1a2e".to_string(),
-            text: "#eaeaea".to_string(),
-            line: "#eaeaea".to_string(),
-            participant_bg: "#16213e".to_string(),
-            participant_border: "#eaeaea".to_string(),
+            mirror_actors: true,
+            actor_width: 80.0,
+            actor_height: 40.0,
+            actor_margin: 50.0,
+            actor_padding: 20.0,
+            actor_vertical_padding: 16.0,
+            message_margin: 50.0,
+            message_text_margin: 40.0,
+            self_message_height: 40.0,
+            self_loop_width: 40.0,
+            self_loop_text_offset: 50.0,
         }
     }
 }
 
+impl SequenceConfig {
+    /// Sets whether participant boxes are repeated at the bottom (builder pattern)
+    pub fn with_mirror_actors(mut self, mirror: bool) -> Self {
+        self.mirror_actors = mirror;
+        self
+    }
+
+    /// Sets the minimum participant box size (builder pattern)
+    pub fn with_actor_size(mut self, width: f64, height: f64) -> Self {
+        self.actor_width = width;
+        self.actor_height = height;
+        self
+    }
+
+    /// Sets the minimum gap between participant boxes (builder pattern)
+    pub fn with_actor_margin(mut self, margin: f64) -> Self {
+        self.actor_margin = margin;
+        self
+    }
+
+    /// Sets the vertical distance between message rows (builder pattern)
+    pub fn with_message_margin(mut self, margin: f64) -> Self {
+        self.message_margin = margin;
+        self
+    }
+
+    /// Checks that every size is a finite, non-negative number
+    ///
+    /// Returns [`RenderError::InvalidOptions`] naming the offending field.
+    pub fn validate(&self) -> RenderResult<()> {
+        let sizes = [
+            ("actor_width", self.actor_width),
+            ("actor_height", self.actor_height),
+            ("actor_margin", self.actor_margin),
+            ("actor_padding", self.actor_padding),
+            ("actor_vertical_padding", self.actor_vertical_padding),
+            ("message_margin", self.message_margin),
+            ("message_text_margin", self.message_text_margin),
+            ("self_message_height", self.self_message_height),
+            ("self_loop_width", self.self_loop_width),
+            ("self_loop_text_offset", self.self_loop_text_offset),
+        ];
+        for (name, value) in sizes {
+            if !value.is_finite() || value < 0.0 {
+                return Err(RenderError::InvalidOptions(format!(
+                    "sequence {} must be a non-negative number",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Configuration options for rendering diagrams
 ///
 /// Use the builder pattern methods to customize rendering:
@@ -64,7 +227,8 @@ impl ThemeColors {
 /// use mermaid_svg_render::{RenderOptions, Theme};
 ///
 /// let options = RenderOptions::with_theme(Theme::Dark)
-///     .transparent();
+///     .transparent()
+///     .with_size(800, 600);
 /// ```
 #[derive(Debug, Clone)]
 pub struct RenderOptions {
@@ -74,14 +238,91 @@ pub struct RenderOptions {
     pub width: Option<u32>,
     /// Optional fixed height in pixels (auto-calculated if `None`)
     pub height: Option<u32>,
+    /// Optional upper bound on the output width in pixels
+    pub max_width: Option<u32>,
+    /// Optional upper bound on the output height in pixels
+    pub max_height: Option<u32>,
+    /// Horizontal alignment of scaled content within a fixed-size canvas
+    pub align_x: Align,
+    /// Vertical alignment of scaled content within a fixed-size canvas
+    pub align_y: Align,
+    /// Emit `width="100%"` with a `max-width` style so the SVG scales
+    /// with its container (Mermaid's `useMaxWidth`)
+    pub use_max_width: bool,
     /// Padding around the diagram content in pixels
     pub padding: u32,
     /// Font family for text rendering
     pub font_family: String,
     /// Font size in pixels
     pub font_size: u32,
+    /// Font settings for participant labels
+    pub participant_font: FontOverride,
+    /// Font settings for message labels
+    pub message_font: FontOverride,
+    /// Text measurer used for layout (character-width heuristic if `None`)
+    pub text_measurer: Option<Arc<dyn TextMeasurer>>,
+    /// Automatically wrap participant and message labels
+    ///
+    /// Individual labels can override this with Mermaid's `wrap:` and
+    /// `nowrap:` prefixes.
+    pub wrap: bool,
+    /// Maximum label width in pixels when wrapping
+    pub wrap_width: u32,
+    /// Sequence diagram spacing and sizing
+    pub sequence: SequenceConfig,
     /// Whether to use a transparent background instead of solid color
     pub transparent_bg: bool,
+    /// Emit colours as rules for the semantic classes in an embedded
+    /// `<style>` block instead of inline `fill`/`stroke` attributes
+    pub style_block: bool,
+    /// Draw arrowheads as shapes at every arrow end instead of shared
+    /// `<marker>` definitions, for consumers without marker support
+    pub inline_arrowheads: bool,
+    /// Describe the participants and messages in the SVG's `<desc>`
+    ///
+    /// The summary follows any `accDescr` description.
+    pub accessible_summary: bool,
+    /// Render links: participant `link`/`links` statements and Markdown
+    /// links in labels
+    ///
+    /// Disable for untrusted input; link text is then drawn as plain text.
+    pub links: bool,
+    /// Embed the diagram source, crate version and these options in the
+    /// SVG's `<metadata>`
+    ///
+    /// See [`metadata`](crate::metadata) for recovering the source.
+    pub embed_source: bool,
+    /// Decimals written for coordinates and sizes, at most
+    /// [`MAX_PRECISION`](crate::svg::number::MAX_PRECISION)
+    ///
+    /// Trailing zeros are trimmed and negative zero is written as `0`, so
+    /// identical input and options produce byte-identical SVG.
+    pub precision: usize,
+    /// Write the smallest equivalent SVG instead of an indented one
+    ///
+    /// See [`minify`](crate::svg::minify) for the rewrites applied.
+    pub minify: bool,
+    /// Write the canonical, diff-friendly form of the SVG
+    ///
+    /// See [`pretty`](crate::svg::pretty) for the canonical form.
+    pub pretty: bool,
+    /// Hooks run in order on the document tree before it is serialized
+    pub post_processors: Vec<Arc<dyn PostProcessor>>,
+    /// Prefix for the ids of participant, lifeline and message groups
+    ///
+    /// Set a distinct prefix for each diagram embedded in the same page.
+    pub id_prefix: String,
+    /// Apply configuration from the diagram's front-matter and
+    /// `%%{init: ...}%%` directives on top of these options
+    ///
+    /// See [`config`](crate::config) for the precedence rules.
+    pub use_diagram_config: bool,
+    /// Let [`Theme::Auto`] follow the viewer's colour scheme
+    ///
+    /// When disabled, an automatic theme renders with its light palette,
+    /// including one selected by the diagram's own configuration. Output
+    /// without a colour scheme, such as PNG, disables it.
+    pub adaptive_theme: bool,
 }
 
 impl Default for RenderOptions {
@@ -90,10 +331,33 @@ impl Default for RenderOptions {
             theme: Theme::Light,
             width: None,
             height: None,
+            max_width: None,
+            max_height: None,
+            align_x: Align::Center,
+            align_y: Align::Center,
+            use_max_width: false,
             padding: 20,
             font_family: "Arial, sans-serif".to_string(),
             font_size: 14,
+            participant_font: FontOverride::default(),
+            message_font: FontOverride::default(),
+            text_measurer: None,
+            wrap: false,
+            wrap_width: 200,
+            sequence: SequenceConfig::default(),
             transparent_bg: false,
+            style_block: false,
+            inline_arrowheads: false,
+            accessible_summary: false,
+            links: true,
+            embed_source: false,
+            precision: NumberFormat::default().precision(),
+            minify: false,
+            pretty: false,
+            post_processors: Vec::new(),
+            id_prefix: String::new(),
+            use_diagram_config: true,
+            adaptive_theme: true,
         }
     }
 }
@@ -111,9 +375,47 @@ impl RenderOptions {
 
     /// Returns the color palette for the current theme
     pub fn colors(&self) -> ThemeColors {
-        match self.theme {
-            Theme::Light => ThemeColors::light(),
-            Theme::Dark => ThemeColors::dark(),
+        self.theme.colors()
+    }
+
+    /// Uses a custom color palette (builder pattern)
+    ///
+    /// Shorthand for setting [`theme`](Self::theme) to [`Theme::Custom`].
+    pub fn with_colors(mut self, colors: ThemeColors) -> Self {
+        self.theme = Theme::Custom(colors);
+        self
+    }
+
+    /// Uses a palette built from Mermaid `themeVariables` (builder pattern)
+    ///
+    /// Returns [`RenderError::InvalidOptions`] if a variable holds an
+    /// invalid colour or stroke width.
+    pub fn with_theme_variables(self, variables: &ThemeVariables) -> RenderResult<Self> {
+        Ok(self.with_colors(variables.to_colors()?))
+    }
+
+    /// Returns the base font built from `font_family` and `font_size`
+    pub fn font(&self) -> Font {
+        Font::new(self.font_family.clone(), self.font_size)
+    }
+
+    /// Applies a category override on top of the base font
+    pub fn resolve_font(&self, overrides: &FontOverride) -> Font {
+        Font {
+            family: overrides
+                .family
+                .clone()
+                .unwrap_or_else(|| self.font_family.clone()),
+            size: overrides.size.unwrap_or(self.font_size),
+            weight: overrides.weight.clone(),
+        }
+    }
+
+    /// Returns the text measurer used for layout
+    pub fn measurer(&self) -> &dyn TextMeasurer {
+        match &self.text_measurer {
+            Some(measurer) => measurer.as_ref(),
+            None => &HeuristicMeasurer,
         }
     }
 
@@ -125,4 +427,326 @@ impl RenderOptions {
         self.transparent_bg = true;
         self
     }
+
+    /// Emits colours in an embedded `<style>` block (builder pattern)
+    ///
+    /// Elements then carry only their semantic classes (`actor`,
+    /// `messageLine0`, `messageText`, ...), so the output can be restyled
+    /// with CSS without fighting inline attributes.
+    pub fn with_style_block(mut self) -> Self {
+        self.style_block = true;
+        self
+    }
+
+    /// Draws arrowheads inline instead of with `<marker>` (builder pattern)
+    ///
+    /// Each arrow end becomes its own polygon or lines. Use this for
+    /// consumers that ignore markers, such as some SVG to PDF converters.
+    pub fn with_inline_arrowheads(mut self) -> Self {
+        self.inline_arrowheads = true;
+        self
+    }
+
+    /// Adds a generated summary of the diagram to its description (builder pattern)
+    ///
+    /// Screen readers then announce, for example, "Alice sends 'Hello' to Bob."
+    /// for each message.
+    pub fn with_accessible_summary(mut self) -> Self {
+        self.accessible_summary = true;
+        self
+    }
+
+    /// Renders no links at all (builder pattern)
+    ///
+    /// Participant `link`/`links` statements are ignored and Markdown links
+    /// in labels become plain text. Use this for untrusted diagrams.
+    pub fn without_links(mut self) -> Self {
+        self.links = false;
+        self
+    }
+
+    /// Embeds the diagram source in the output (builder pattern)
+    ///
+    /// [`extract_source`](crate::metadata::extract_source) (`msv extract`)
+    /// recovers it from the SVG for editing.
+    pub fn with_embedded_source(mut self) -> Self {
+        self.embed_source = true;
+        self
+    }
+
+    /// Sets the number of decimals written for coordinates (builder pattern)
+    ///
+    /// Two decimals by default; `0` rounds everything to whole pixels.
+    pub fn with_precision(mut self, precision: usize) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Returns the number format built from [`precision`](Self::precision)
+    pub fn number_format(&self) -> NumberFormat {
+        NumberFormat::new(self.precision)
+    }
+
+    /// Minifies the output (builder pattern)
+    ///
+    /// Repeated attributes move into shared classes, adjacent lines merge
+    /// into paths and all whitespace between elements is dropped. The
+    /// result renders the same as the indented output.
+    pub fn minified(mut self) -> Self {
+        self.minify = true;
+        self
+    }
+
+    /// Pretty-prints the output in canonical form (builder pattern)
+    ///
+    /// Attributes follow one fixed order, definitions are sorted and all
+    /// markup is indented, so committing the SVG gives small, readable diffs.
+    pub fn pretty_printed(mut self) -> Self {
+        self.pretty = true;
+        self
+    }
+
+    /// Adds a hook that modifies the document tree before serialization (builder pattern)
+    ///
+    /// Hooks run in the order they were added; see [`PostProcessor`].
+    pub fn with_post_processor(mut self, processor: impl PostProcessor + 'static) -> Self {
+        self.post_processors.push(Arc::new(processor));
+        self
+    }
+
+    /// Prefixes the ids of the diagram's groups (builder pattern)
+    ///
+    /// The prefix is slugged like participant names, so `"Login Flow"`
+    /// gives ids such as `login-flow-message-3`.
+    pub fn with_id_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.id_prefix = prefix.into();
+        self
+    }
+
+    /// Sets a fixed output size (builder pattern)
+    ///
+    /// The content is scaled uniformly to fit, preserving its aspect ratio,
+    /// and positioned according to [`align_x`](Self::align_x) and
+    /// [`align_y`](Self::align_y).
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.width = Some(width);
+        self.height = Some(height);
+        self
+    }
+
+    /// Sets a fixed output width (builder pattern)
+    ///
+    /// The height follows from the content's aspect ratio unless also set.
+    pub fn with_width(mut self, width: u32) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Sets a fixed output height (builder pattern)
+    ///
+    /// The width follows from the content's aspect ratio unless also set.
+    pub fn with_height(mut self, height: u32) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    /// Sets the maximum output width (builder pattern)
+    pub fn with_max_width(mut self, max_width: u32) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    /// Sets the maximum output height (builder pattern)
+    pub fn with_max_height(mut self, max_height: u32) -> Self {
+        self.max_height = Some(max_height);
+        self
+    }
+
+    /// Sets the padding around the diagram content (builder pattern)
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Sets the base font family for all text (builder pattern)
+    pub fn with_font_family(mut self, family: impl Into<String>) -> Self {
+        self.font_family = family.into();
+        self
+    }
+
+    /// Sets the base font size for all text (builder pattern)
+    pub fn with_font_size(mut self, size: u32) -> Self {
+        self.font_size = size;
+        self
+    }
+
+    /// Sets the font used for participant labels (builder pattern)
+    pub fn with_participant_font(mut self, font: FontOverride) -> Self {
+        self.participant_font = font;
+        self
+    }
+
+    /// Sets the font used for message labels (builder pattern)
+    pub fn with_message_font(mut self, font: FontOverride) -> Self {
+        self.message_font = font;
+        self
+    }
+
+    /// Sets the text measurer used for layout (builder pattern)
+    pub fn with_text_measurer(mut self, measurer: impl TextMeasurer + 'static) -> Self {
+        self.text_measurer = Some(Arc::new(measurer));
+        self
+    }
+
+    /// Measures text with the metrics of a font file (builder pattern)
+    ///
+    /// Replaces any previously configured measurer. Use
+    /// [`FontFileMeasurer`] directly to load several fonts.
+    pub fn with_font_file(self, path: impl AsRef<Path>) -> RenderResult<Self> {
+        Ok(self.with_text_measurer(FontFileMeasurer::new().with_font_file(path)?))
+    }
+
+    /// Measures text with the metrics of in-memory font data (builder pattern)
+    ///
+    /// Replaces any previously configured measurer. Use
+    /// [`FontFileMeasurer`] directly to load several fonts.
+    pub fn with_font_data(self, data: impl Into<Vec<u8>>) -> RenderResult<Self> {
+        Ok(self.with_text_measurer(FontFileMeasurer::new().with_font_data(data)?))
+    }
+
+    /// Enables label wrapping at the given width in pixels (builder pattern)
+    pub fn with_wrapping(mut self, max_width: u32) -> Self {
+        self.wrap = true;
+        self.wrap_width = max_width;
+        self
+    }
+
+    /// Sets how scaled content is aligned in a fixed-size canvas (builder pattern)
+    pub fn with_alignment(mut self, align_x: Align, align_y: Align) -> Self {
+        self.align_x = align_x;
+        self.align_y = align_y;
+        self
+    }
+
+    /// Enables responsive output (builder pattern)
+    ///
+    /// The SVG is emitted with `width="100%"` and a `max-width` style equal
+    /// to its natural width, so it shrinks with narrow containers.
+    pub fn responsive(mut self) -> Self {
+        self.use_max_width = true;
+        self
+    }
+
+    /// Sets the sequence diagram spacing and sizing (builder pattern)
+    pub fn with_sequence(mut self, sequence: SequenceConfig) -> Self {
+        self.sequence = sequence;
+        self
+    }
+
+    /// Ignores configuration embedded in the diagram (builder pattern)
+    ///
+    /// Front-matter and init directives are still removed from the source,
+    /// but have no effect on rendering.
+    pub fn ignore_diagram_config(mut self) -> Self {
+        self.use_diagram_config = false;
+        self
+    }
+
+    /// Renders [`Theme::Auto`] with its light palette (builder pattern)
+    ///
+    /// Applies after the diagram's configuration, so it also covers
+    /// `theme: auto` in front-matter and init directives.
+    pub fn without_adaptive_theme(mut self) -> Self {
+        self.adaptive_theme = false;
+        self
+    }
+
+    /// Checks the options for nonsensical values
+    ///
+    /// Returns [`RenderError::InvalidOptions`] if a dimension or a font
+    /// size is zero, a font family is empty, a fixed size exceeds its
+    /// maximum, the precision exceeds
+    /// [`MAX_PRECISION`](crate::svg::number::MAX_PRECISION), both
+    /// minified and pretty output are requested, a custom theme holds an
+    /// invalid colour, or a sequence spacing is negative.
+    pub fn validate(&self) -> RenderResult<()> {
+        let dimensions = [
+            ("width", self.width),
+            ("height", self.height),
+            ("max_width", self.max_width),
+            ("max_height", self.max_height),
+            ("wrap_width", Some(self.wrap_width)),
+        ];
+        for (name, value) in dimensions {
+            if value == Some(0) {
+                return Err(RenderError::InvalidOptions(format!(
+                    "{} must be greater than 0",
+                    name
+                )));
+            }
+        }
+
+        let fonts = [
+            ("font", Some(self.font_family.clone()), Some(self.font_size)),
+            (
+                "participant_font",
+                self.participant_font.family.clone(),
+                self.participant_font.size,
+            ),
+            (
+                "message_font",
+                self.message_font.family.clone(),
+                self.message_font.size,
+            ),
+        ];
+        for (name, family, size) in fonts {
+            if size == Some(0) {
+                return Err(RenderError::InvalidOptions(format!(
+                    "{} size must be greater than 0",
+                    name
+                )));
+            }
+            if family.is_some_and(|f| f.trim().is_empty()) {
+                return Err(RenderError::InvalidOptions(format!(
+                    "{} family must not be empty",
+                    name
+                )));
+            }
+        }
+
+        let limits = [
+            ("width", self.width, "max_width", self.max_width),
+            ("height", self.height, "max_height", self.max_height),
+        ];
+        for (name, value, max_name, max) in limits {
+            if let (Some(value), Some(max)) = (value, max) {
+                if value > max {
+                    return Err(RenderError::InvalidOptions(format!(
+                        "{} ({}) exceeds {} ({})",
+                        name, value, max_name, max
+                    )));
+                }
+            }
+        }
+
+        if self.precision > MAX_PRECISION {
+            return Err(RenderError::InvalidOptions(format!(
+                "precision ({}) exceeds {}",
+                self.precision, MAX_PRECISION
+            )));
+        }
+
+        if self.minify && self.pretty {
+            return Err(RenderError::InvalidOptions(
+                "minify and pretty output are mutually exclusive".to_string(),
+            ));
+        }
+
+        if let Theme::Custom(colors) = &self.theme {
+            colors.validate()?;
+        }
+        self.sequence.validate()?;
+
+        Ok(())
+    }
 }
@@ -0,0 +1,277 @@
+//! PNG output
+//!
+//! Available with the `png` feature. Rendered SVGs are rasterized in pure
+//! Rust with [resvg](https://github.com/linebender/resvg). DejaVu Sans is
+//! bundled and stands in for every font family, so text renders the same on
+//! machines without any fonts installed.
+//!
+//! ```rust,ignore
+//! use mermaid_svg_render::png::{render_sequence_diagram_png, PngOptions};
+//! use mermaid_svg_render::RenderOptions;
+//!
+//! let png = render_sequence_diagram_png(
+//!     "sequenceDiagram\n    Alice->>Bob: Hello",
+//!     &RenderOptions::default(),
+//!     &PngOptions::default().with_dpi(192.0),
+//! )?;
+//! std::fs::write("diagram.png", png)?;
+//! ```
+
+use std::sync::{Arc, OnceLock};
+
+use resvg::tiny_skia::{self, Pixmap, Transform};
+use resvg::usvg::{self, fontdb};
+
+use crate::error::{RenderError, RenderResult};
+use crate::options::RenderOptions;
+use crate::theme::Color;
+
+/// Pixel density at which one SVG pixel is one image pixel
+pub const DEFAULT_DPI: f32 = 96.0;
+
+/// The bundled font
+const FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSans.ttf");
+
+/// Family name of the bundled font
+const FONT_FAMILY: &str = "DejaVu Sans";
+
+/// Options for rasterizing an SVG
+#[derive(Debug, Clone, PartialEq)]
+pub struct PngOptions {
+    /// Size multiplier applied on top of the DPI
+    pub scale: f32,
+    /// Pixel density, recorded in the image; 192 doubles the pixel size
+    pub dpi: f32,
+    /// Colour painted behind the diagram; the SVG's own background (if
+    /// any) is drawn on top
+    pub background: Option<Color>,
+}
+
+impl Default for PngOptions {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            dpi: DEFAULT_DPI,
+            background: None,
+        }
+    }
+}
+
+impl PngOptions {
+    /// Sets the size multiplier (builder pattern)
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Sets the pixel density (builder pattern)
+    pub fn with_dpi(mut self, dpi: f32) -> Self {
+        self.dpi = dpi;
+        self
+    }
+
+    /// Paints a background colour behind the diagram (builder pattern)
+    ///
+    /// Combine with [`RenderOptions::transparent`] to replace the theme's
+    /// background.
+    pub fn with_background(mut self, background: Color) -> Self {
+        self.background = Some(background);
+        self
+    }
+
+    /// Returns the number of image pixels per SVG pixel
+    pub fn zoom(&self) -> f32 {
+        self.scale * self.dpi / DEFAULT_DPI
+    }
+
+    /// Checks the options for nonsensical values
+    ///
+    /// Returns [`RenderError::InvalidOptions`] if the scale or DPI is not a
+    /// positive number.
+    pub fn validate(&self) -> RenderResult<()> {
+        for (name, value) in [("scale", self.scale), ("dpi", self.dpi)] {
+            if !(value.is_finite() && value > 0.0) {
+                return Err(RenderError::InvalidOptions(format!(
+                    "{} must be a positive number",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Rasterizes an SVG document to PNG
+///
+/// Returns [`RenderError::PngError`] if the SVG cannot be parsed or the
+/// image would be empty or too large.
+pub fn svg_to_png(svg: &str, options: &PngOptions) -> RenderResult<Vec<u8>> {
+    options.validate()?;
+
+    let usvg_options = usvg::Options {
+        font_family: FONT_FAMILY.to_string(),
+        fontdb: fonts(),
+        ..Default::default()
+    };
+    let tree = usvg::Tree::from_str(svg, &usvg_options)
+        .map_err(|e| RenderError::PngError(e.to_string()))?;
+
+    let zoom = options.zoom();
+    let size = tree
+        .size()
+        .to_int_size()
+        .scale_by(zoom)
+        .ok_or_else(|| RenderError::PngError("image would be empty".to_string()))?;
+    let mut pixmap = Pixmap::new(size.width(), size.height()).ok_or_else(|| {
+        RenderError::PngError(format!(
+            "cannot allocate a {}x{} image",
+            size.width(),
+            size.height()
+        ))
+    })?;
+
+    if let Some(color) = options.background {
+        pixmap.fill(tiny_skia::Color::from_rgba8(
+            color.r,
+            color.g,
+            color.b,
+            (color.a.clamp(0.0, 1.0) * 255.0).round() as u8,
+        ));
+    }
+    resvg::render(
+        &tree,
+        Transform::from_scale(zoom, zoom),
+        &mut pixmap.as_mut(),
+    );
+
+    encode(&pixmap, options.dpi)
+}
+
+/// Renders a sequence diagram from Mermaid source text to PNG
+///
+/// [`Theme::Auto`](crate::Theme::Auto) renders with its light palette, since an image has no
+/// colour scheme to follow.
+pub fn render_sequence_diagram_png(
+    input: &str,
+    options: &RenderOptions,
+    png: &PngOptions,
+) -> RenderResult<Vec<u8>> {
+    render_sequence_diagram_png_with_warnings(input, options, png).map(|output| output.png)
+}
+
+/// Rendered PNG together with problems that did not stop rendering
+#[derive(Debug, Clone, PartialEq)]
+pub struct PngOutput {
+    /// The encoded image
+    pub png: Vec<u8>,
+    /// Warnings such as unknown configuration keys or invalid values
+    pub warnings: Vec<String>,
+}
+
+/// Renders a sequence diagram to PNG, returning warnings alongside it
+///
+/// See [`render_sequence_diagram_with_warnings`](crate::render_sequence_diagram_with_warnings).
+pub fn render_sequence_diagram_png_with_warnings(
+    input: &str,
+    options: &RenderOptions,
+    png: &PngOptions,
+) -> RenderResult<PngOutput> {
+    png.validate()?;
+    let options = options.clone().without_adaptive_theme();
+    let output = crate::render_sequence_diagram_with_warnings(input, &options)?;
+    Ok(PngOutput {
+        png: svg_to_png(&output.svg, png)?,
+        warnings: output.warnings,
+    })
+}
+
+/// The font database holding the bundled font, loaded once
+fn fonts() -> Arc<fontdb::Database> {
+    static FONTS: OnceLock<Arc<fontdb::Database>> = OnceLock::new();
+    FONTS
+        .get_or_init(|| {
+            let mut fonts = fontdb::Database::new();
+            fonts.load_font_data(FONT.to_vec());
+            fonts.set_sans_serif_family(FONT_FAMILY);
+            fonts.set_serif_family(FONT_FAMILY);
+            fonts.set_monospace_family(FONT_FAMILY);
+            fonts.set_cursive_family(FONT_FAMILY);
+            fonts.set_fantasy_family(FONT_FAMILY);
+            Arc::new(fonts)
+        })
+        .clone()
+}
+
+/// Encodes straight RGBA pixels with the pixel density recorded
+fn encode(pixmap: &Pixmap, dpi: f32) -> RenderResult<Vec<u8>> {
+    let data: Vec<u8> = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+
+    let error = |e: ::png::EncodingError| RenderError::PngError(e.to_string());
+    let mut png = Vec::new();
+    let mut encoder = ::png::Encoder::new(&mut png, pixmap.width(), pixmap.height());
+    encoder.set_color(::png::ColorType::Rgba);
+    encoder.set_depth(::png::BitDepth::Eight);
+    let pixels_per_meter = (f64::from(dpi) / 0.0254).round() as u32;
+    encoder.set_pixel_dims(Some(::png::PixelDimensions {
+        xppu: pixels_per_meter,
+        yppu: pixels_per_meter,
+        unit: ::png::Unit::Meter,
+    }));
+    let mut writer = encoder.write_header().map_err(error)?;
+    writer.write_image_data(&data).map_err(error)?;
+    writer.finish().map_err(error)?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SVG: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="20" viewBox="0 0 40 20"><rect x="10" width="10" height="10" fill="#ff0000"/></svg>"##;
+
+    fn decode(png: &[u8]) -> Pixmap {
+        Pixmap::decode_png(png).unwrap()
+    }
+
+    #[test]
+    fn test_size_follows_scale_and_dpi() {
+        let pixmap = decode(&svg_to_png(SVG, &PngOptions::default()).unwrap());
+        assert_eq!((pixmap.width(), pixmap.height()), (40, 20));
+
+        let options = PngOptions::default().with_scale(1.5).with_dpi(192.0);
+        assert_eq!(options.zoom(), 3.0);
+        let pixmap = decode(&svg_to_png(SVG, &options).unwrap());
+        assert_eq!((pixmap.width(), pixmap.height()), (120, 60));
+    }
+
+    #[test]
+    fn test_background() {
+        let transparent = decode(&svg_to_png(SVG, &PngOptions::default()).unwrap());
+        assert_eq!(transparent.pixel(0, 0).unwrap().alpha(), 0);
+        assert_eq!(transparent.pixel(15, 5).unwrap().demultiply().red(), 255);
+
+        let options = PngOptions::default().with_background(Color::rgb(0, 0, 255));
+        let filled = decode(&svg_to_png(SVG, &options).unwrap());
+        let corner = filled.pixel(0, 0).unwrap();
+        assert_eq!((corner.blue(), corner.alpha()), (255, 255));
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert!(matches!(
+            svg_to_png(SVG, &PngOptions::default().with_scale(0.0)),
+            Err(RenderError::InvalidOptions(_))
+        ));
+        assert!(matches!(
+            svg_to_png("<svg", &PngOptions::default()),
+            Err(RenderError::PngError(_))
+        ));
+    }
+}
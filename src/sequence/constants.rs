@@ -1,37 +1,10 @@
 //! Layout constants for sequence diagrams
+//!
+//! Spacing that callers can tune lives in
+//! [`SequenceConfig`](crate::options::SequenceConfig).
 
-/// Minimum participant box width
-pub const MIN_PARTICIPANT_WIDTH: f64 = 80.0;
-
-/// Minimum participant box height
-pub const MIN_PARTICIPANT_HEIGHT: f64 = 40.0;
-
-/// Horizontal padding inside participant box (10px each side)
-pub const PARTICIPANT_PADDING: f64 = 20.0;
-
-/// Vertical padding inside participant box (8px top + 8px bottom)
-pub const PARTICIPANT_VERTICAL_PADDING: f64 = 16.0;
-
-/// Line height for multi-line text
+/// Line height for multi-line text at the base font size
 pub const LINE_HEIGHT: f64 = 18.0;
 
-/// Minimum gap between participant boxes
-pub const MIN_PARTICIPANT_SPACING: f64 = 50.0;
-
-/// Margin around message text (20px padding on each side)
-pub const MESSAGE_TEXT_MARGIN: f64 = 40.0;
-
-/// Vertical spacing between messages
-pub const MESSAGE_SPACING: f64 = 50.0;
-
-/// Height of self-message loop
-pub const SELF_MESSAGE_HEIGHT: f64 = 40.0;
-
-/// Width of self-message loop
-pub const SELF_LOOP_WIDTH: f64 = 40.0;
-
-/// Text offset for self-message labels
-pub const SELF_LOOP_TEXT_OFFSET: f64 = 50.0;
-
-/// Padding around the diagram edges
-pub const PADDING: f64 = 20.0;
+/// Font size the line height is tuned for
+pub const BASE_FONT_SIZE: f64 = 14.0;
@@ -0,0 +1,114 @@
+//! Deterministic element ids for sequence diagrams
+//!
+//! Participants are identified by a slug of their name (`participant-alice`,
+//! `lifeline-alice`) and messages by their statement index (`message-3`).
+//! Names that slug to the same text get a numeric suffix in diagram order.
+//! An optional prefix keeps ids unique when several diagrams share a page.
+
+use std::collections::HashMap;
+
+/// Assigns ids to the elements of one diagram
+#[derive(Debug, Clone)]
+pub struct ElementIds {
+    prefix: String,
+    slugs: HashMap<String, String>,
+}
+
+impl ElementIds {
+    /// Assigns slugs to `participants`, in diagram order
+    pub fn new<'a>(prefix: &str, participants: impl IntoIterator<Item = &'a str>) -> Self {
+        let prefix = match slug(prefix) {
+            Some(prefix) => format!("{}-", prefix),
+            None => String::new(),
+        };
+
+        let mut slugs = HashMap::new();
+        let mut taken: Vec<String> = Vec::new();
+        for (index, name) in participants.into_iter().enumerate() {
+            let base = slug(name).unwrap_or_else(|| format!("p{}", index));
+            let mut candidate = base.clone();
+            let mut n = 2;
+            while taken.contains(&candidate) {
+                candidate = format!("{}-{}", base, n);
+                n += 1;
+            }
+            taken.push(candidate.clone());
+            slugs.insert(name.to_string(), candidate);
+        }
+
+        Self { prefix, slugs }
+    }
+
+    /// Id of a participant's group
+    pub fn participant(&self, name: &str) -> String {
+        format!("{}participant-{}", self.prefix, self.slug_of(name))
+    }
+
+    /// Id of a participant's lifeline group
+    pub fn lifeline(&self, name: &str) -> String {
+        format!("{}lifeline-{}", self.prefix, self.slug_of(name))
+    }
+
+    /// Id of a message's group
+    pub fn message(&self, index: usize) -> String {
+        format!("{}message-{}", self.prefix, index)
+    }
+
+    /// Id of the diagram's `<title>`
+    pub fn title(&self) -> String {
+        format!("{}diagram-title", self.prefix)
+    }
+
+    /// Id of the diagram's `<desc>`
+    pub fn description(&self) -> String {
+        format!("{}diagram-desc", self.prefix)
+    }
+
+    /// Prefix of generated class names, such as those of `style` statements
+    pub fn class_prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Prefix of the ids of arrowhead markers
+    pub fn marker_prefix(&self) -> String {
+        format!("{}marker-", self.prefix)
+    }
+
+    fn slug_of<'a>(&'a self, name: &'a str) -> &'a str {
+        self.slugs.get(name).map(String::as_str).unwrap_or(name)
+    }
+}
+
+/// Lowercases ASCII letters and digits and joins the runs between them with `-`
+///
+/// Returns `None` if nothing is left, for example for non-Latin names.
+fn slug(name: &str) -> Option<String> {
+    let slug = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("-");
+    (!slug.is_empty()).then_some(slug)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_participant_slugs() {
+        let ids = ElementIds::new("", ["Alice", "Web Server", "web-server", "用户"]);
+        assert_eq!(ids.participant("Alice"), "participant-alice");
+        assert_eq!(ids.participant("Web Server"), "participant-web-server");
+        assert_eq!(ids.participant("web-server"), "participant-web-server-2");
+        assert_eq!(ids.lifeline("用户"), "lifeline-p3");
+    }
+
+    #[test]
+    fn test_prefix() {
+        let ids = ElementIds::new("Login Flow", ["A"]);
+        assert_eq!(ids.message(4), "login-flow-message-4");
+        assert_eq!(ids.participant("A"), "login-flow-participant-a");
+    }
+}
@@ -1,42 +1,67 @@
 //! Layout calculation for sequence diagrams
 
-use mermaid_parser::common::ast::{Participant, SequenceDiagram, SequenceStatement};
+use mermaid_parser::common::ast::{Message, Participant, SequenceDiagram, SequenceStatement};
 
 use crate::layout::{
-    calculate_text_box_height, calculate_text_box_width, split_by_line_breaks, text_width,
-    ContentBounds,
+    calculate_text_box_height, calculate_text_box_width, split_by_line_breaks, wrap_line,
+    ContentBounds, TextLine, TextMeasurer,
 };
+use crate::options::{Font, RenderOptions, SequenceConfig};
 
 use super::constants::*;
-use super::types::{Layout, ParticipantLayout};
+use super::types::{Layout, MessageLayout, ParticipantLayout};
+
+/// A message with its label split into display lines
+struct MessageLabel<'a> {
+    message: &'a Message,
+    /// Index of the message among the diagram's statements
+    index: usize,
+    lines: Vec<TextLine>,
+}
 
 /// Calculate layout and content bounds (first pass - no rendering)
-pub fn calculate_layout(diagram: &SequenceDiagram, font_size: u32) -> Layout {
+pub fn calculate_layout(diagram: &SequenceDiagram, options: &RenderOptions) -> Layout {
+    let participant_font = options.resolve_font(&options.participant_font);
+    let message_font = options.resolve_font(&options.message_font);
+    let padding = options.padding as f64;
+    let config = &options.sequence;
+    let measurer = options.measurer();
     let mut bounds = ContentBounds::new();
 
     // Calculate participant dimensions (widths, heights, and lines)
     let (participant_widths, participant_heights, participant_lines) =
-        calculate_participant_dimensions(&diagram.participants, font_size);
+        calculate_participant_dimensions(
+            measurer,
+            &diagram.participants,
+            &participant_font,
+            options,
+        );
+
+    // Split message labels into lines up front; they drive both gaps and row heights
+    let labels = message_labels(&diagram.statements, measurer, &message_font, options);
+    let message_line_height = line_height(&message_font);
 
     // Use the maximum participant width and height for consistent box sizing
     let participant_width = participant_widths
         .iter()
         .copied()
-        .fold(MIN_PARTICIPANT_WIDTH, f64::max);
+        .fold(config.actor_width, f64::max);
     let participant_height = participant_heights
         .iter()
         .copied()
-        .fold(MIN_PARTICIPANT_HEIGHT, f64::max);
+        .fold(config.actor_height, f64::max);
 
     // Create uniform widths vector for gap spacing calculation
     let uniform_widths: Vec<f64> = vec![participant_width; diagram.participants.len()];
 
     // Calculate dynamic gap spacings based on message lengths and uniform participant widths
     let gap_spacings = calculate_gap_spacings(
+        measurer,
         &diagram.participants,
         &uniform_widths,
-        &diagram.statements,
-        font_size,
+        &labels,
+        &message_font,
+        config,
     );
 
     // Calculate participant layouts with uniform width
@@ -45,75 +70,103 @@ pub fn calculate_layout(diagram: &SequenceDiagram, font_size: u32) -> Layout {
         &uniform_widths,
         &participant_lines,
         &gap_spacings,
+        padding,
+        config,
     );
 
     // Calculate participant bounds
     for p in &participants {
         // Top participant box
-        bounds.include_rect(p.left_edge(), PADDING, p.width, participant_height);
+        bounds.include_rect(p.left_edge(), padding, p.width, participant_height);
 
         // Top participant name (centered text) - use widest line for bounds
         let max_line_width = p
             .lines
             .iter()
-            .map(|line| text_width(line, font_size))
+            .map(|line| line.width(measurer, &participant_font))
             .fold(0.0_f64, f64::max);
         bounds.include_text(
             p.center_x,
-            PADDING + participant_height,
+            padding + participant_height,
             max_line_width,
             "middle",
         );
     }
 
-    // Calculate message bounds and total height
-    let mut message_y = PADDING + participant_height + MESSAGE_SPACING;
-
-    for statement in &diagram.statements {
-        if let SequenceStatement::Message(msg) = statement {
-            let from_x = find_participant_center(&participants, &msg.from);
-            let to_x = find_participant_center(&participants, &msg.to);
-
-            if let (Some(fx), Some(tx)) = (from_x, to_x) {
-                if msg.from == msg.to {
-                    // Self-message bounds
-                    let loop_right = fx + SELF_LOOP_WIDTH;
-                    bounds.include_point(loop_right, message_y + SELF_MESSAGE_HEIGHT);
-
-                    // Self-message text (starts after loop)
-                    let msg_width = text_width(&msg.text, font_size);
-                    bounds.include_text(
-                        fx + SELF_LOOP_TEXT_OFFSET,
-                        message_y + SELF_MESSAGE_HEIGHT,
-                        msg_width,
-                        "start",
-                    );
-
-                    message_y += MESSAGE_SPACING + SELF_MESSAGE_HEIGHT;
-                } else {
-                    // Regular message bounds
-                    bounds.include_point(fx.max(tx), message_y);
-
-                    // Message text (centered between participants)
-                    let text_x = (fx + tx) / 2.0;
-                    let msg_width = text_width(&msg.text, font_size);
-                    bounds.include_text(text_x, message_y, msg_width, "middle");
-
-                    message_y += MESSAGE_SPACING;
-                }
+    // Calculate message rows, bounds and total height
+    let mut messages = Vec::new();
+    let mut message_y = padding + participant_height + config.message_margin;
+
+    for label in labels {
+        let msg = label.message;
+        let from_x = find_participant_center(&participants, &msg.from);
+        let to_x = find_participant_center(&participants, &msg.to);
+
+        if let (Some(fx), Some(tx)) = (from_x, to_x) {
+            // Extra label lines stack upward, so push the row down to make room
+            message_y += (label.lines.len() - 1) as f64 * message_line_height;
+
+            let msg_width = label
+                .lines
+                .iter()
+                .map(|line| line.width(measurer, &message_font))
+                .fold(0.0_f64, f64::max);
+            let is_self = msg.from == msg.to;
+
+            if is_self {
+                // Self-message bounds
+                let loop_right = fx + config.self_loop_width;
+                bounds.include_point(loop_right, message_y + config.self_message_height);
+
+                // Self-message text (starts after loop)
+                bounds.include_text(
+                    fx + config.self_loop_text_offset,
+                    message_y + config.self_message_height,
+                    msg_width,
+                    "start",
+                );
+            } else {
+                // Regular message bounds
+                bounds.include_point(fx.max(tx), message_y);
+
+                // Message text (centered between participants)
+                let text_x = (fx + tx) / 2.0;
+                bounds.include_text(text_x, message_y, msg_width, "middle");
             }
+
+            messages.push(MessageLayout {
+                index: label.index,
+                from: msg.from.clone(),
+                to: msg.to.clone(),
+                from_x: fx,
+                to_x: tx,
+                y: message_y,
+                lines: label.lines,
+                arrow_type: msg.arrow_type.clone(),
+                is_self,
+            });
+
+            message_y += if is_self {
+                config.message_margin + config.self_message_height
+            } else {
+                config.message_margin
+            };
         }
     }
 
-    // Bottom participant boxes
+    // Bottom participant boxes (or just the lifeline ends when not mirrored)
     let bottom_box_y = message_y;
     for p in &participants {
+        if !config.mirror_actors {
+            bounds.include_point(p.center_x, bottom_box_y);
+            continue;
+        }
         bounds.include_rect(p.left_edge(), bottom_box_y, p.width, participant_height);
 
         let max_line_width = p
             .lines
             .iter()
-            .map(|line| text_width(line, font_size))
+            .map(|line| line.width(measurer, &participant_font))
             .fold(0.0_f64, f64::max);
         bounds.include_text(
             p.center_x,
@@ -126,11 +179,17 @@ pub fn calculate_layout(diagram: &SequenceDiagram, font_size: u32) -> Layout {
     Layout {
         bounds,
         participants,
+        messages,
         participant_height,
         bottom_box_y,
     }
 }
 
+/// Line height for multi-line text, scaled from the base font size
+pub fn line_height(font: &Font) -> f64 {
+    LINE_HEIGHT * font.size as f64 / BASE_FONT_SIZE
+}
+
 /// Find participant center X position by name
 pub fn find_participant_center(participants: &[ParticipantLayout], name: &str) -> Option<f64> {
     participants
@@ -143,38 +202,141 @@ pub fn find_participant_center(participants: &[ParticipantLayout], name: &str) -
 // Internal Helper Functions
 // =============================================================================
 
+/// Strip Mermaid's `wrap:` / `nowrap:` label prefix
+///
+/// Returns the explicit wrap setting (if any) and the remaining text.
+fn strip_wrap_prefix(text: &str) -> (Option<bool>, &str) {
+    let unprefixed = text.strip_prefix(':').unwrap_or(text);
+    if let Some(rest) = unprefixed.strip_prefix("wrap:") {
+        (Some(true), rest.trim_start())
+    } else if let Some(rest) = unprefixed.strip_prefix("nowrap:") {
+        (Some(false), rest.trim_start())
+    } else {
+        (None, text)
+    }
+}
+
+/// Wrap each line to the configured width if wrapping applies to this label
+fn wrap_lines(
+    lines: Vec<TextLine>,
+    wrap: Option<bool>,
+    measurer: &dyn TextMeasurer,
+    font: &Font,
+    options: &RenderOptions,
+) -> Vec<TextLine> {
+    if !wrap.unwrap_or(options.wrap) {
+        return lines;
+    }
+    lines
+        .iter()
+        .flat_map(|line| wrap_line(measurer, line, font, options.wrap_width as f64))
+        .collect()
+}
+
+/// Split label text at line breaks and parse each line's inline markup
+fn parse_lines(text: &str) -> Vec<TextLine> {
+    split_by_line_breaks(text)
+        .iter()
+        .map(|line| TextLine::parse(line))
+        .collect()
+}
+
 /// Parse participant display text into lines
-fn get_participant_lines(participant: &Participant) -> Vec<String> {
+fn get_participant_lines(
+    participant: &Participant,
+    measurer: &dyn TextMeasurer,
+    font: &Font,
+    options: &RenderOptions,
+) -> Vec<TextLine> {
     let display = participant.alias.as_ref().unwrap_or(&participant.actor);
-    split_by_line_breaks(display)
+    let (wrap, display) = strip_wrap_prefix(display);
+    let lines = wrap_lines(parse_lines(display), wrap, measurer, font, options);
+    link_policy(lines, options)
+}
+
+/// Split message text into display lines
+fn get_message_lines(
+    text: &str,
+    measurer: &dyn TextMeasurer,
+    font: &Font,
+    options: &RenderOptions,
+) -> Vec<TextLine> {
+    let (wrap, text) = strip_wrap_prefix(text);
+    let mut lines = parse_lines(text);
+    if lines.is_empty() {
+        // Keep empty labels as a single blank line so every row has a label
+        lines.push(TextLine::default());
+    }
+    let lines = wrap_lines(lines, wrap, measurer, font, options);
+    link_policy(lines, options)
+}
+
+/// Drops inline link targets when links are disabled
+fn link_policy(lines: Vec<TextLine>, options: &RenderOptions) -> Vec<TextLine> {
+    if options.links {
+        lines
+    } else {
+        lines.iter().map(TextLine::without_links).collect()
+    }
+}
+
+/// Collect all messages with their label lines
+fn message_labels<'a>(
+    statements: &'a [SequenceStatement],
+    measurer: &dyn TextMeasurer,
+    font: &Font,
+    options: &RenderOptions,
+) -> Vec<MessageLabel<'a>> {
+    statements
+        .iter()
+        .enumerate()
+        .filter_map(|(index, statement)| match statement {
+            SequenceStatement::Message(message) => Some(MessageLabel {
+                message,
+                index,
+                lines: get_message_lines(&message.text, measurer, font, options),
+            }),
+            _ => None,
+        })
+        .collect()
 }
 
 /// Calculate participant box width based on widest line
-fn calculate_participant_width(lines: &[String], font_size: u32) -> f64 {
-    calculate_text_box_width(lines, font_size, PARTICIPANT_PADDING).max(MIN_PARTICIPANT_WIDTH)
+fn calculate_participant_width(
+    measurer: &dyn TextMeasurer,
+    lines: &[TextLine],
+    font: &Font,
+    config: &SequenceConfig,
+) -> f64 {
+    calculate_text_box_width(measurer, lines, font, config.actor_padding).max(config.actor_width)
 }
 
 /// Calculate participant box height based on number of lines
-fn calculate_participant_height(num_lines: usize) -> f64 {
-    calculate_text_box_height(num_lines, LINE_HEIGHT, PARTICIPANT_VERTICAL_PADDING)
-        .max(MIN_PARTICIPANT_HEIGHT)
+fn calculate_participant_height(num_lines: usize, font: &Font, config: &SequenceConfig) -> f64 {
+    calculate_text_box_height(num_lines, line_height(font), config.actor_vertical_padding)
+        .max(config.actor_height)
 }
 
 /// Calculate all participant widths and heights
 fn calculate_participant_dimensions(
+    measurer: &dyn TextMeasurer,
     participants: &[Participant],
-    font_size: u32,
-) -> (Vec<f64>, Vec<f64>, Vec<Vec<String>>) {
-    let all_lines: Vec<Vec<String>> = participants.iter().map(get_participant_lines).collect();
+    font: &Font,
+    options: &RenderOptions,
+) -> (Vec<f64>, Vec<f64>, Vec<Vec<TextLine>>) {
+    let all_lines: Vec<Vec<TextLine>> = participants
+        .iter()
+        .map(|p| get_participant_lines(p, measurer, font, options))
+        .collect();
 
     let widths: Vec<f64> = all_lines
         .iter()
-        .map(|lines| calculate_participant_width(lines, font_size))
+        .map(|lines| calculate_participant_width(measurer, lines, font, &options.sequence))
         .collect();
 
     let heights: Vec<f64> = all_lines
         .iter()
-        .map(|lines| calculate_participant_height(lines.len()))
+        .map(|lines| calculate_participant_height(lines.len(), font, &options.sequence))
         .collect();
 
     (widths, heights, all_lines)
@@ -187,10 +349,12 @@ fn find_participant_index(participants: &[Participant], name: &str) -> Option<us
 
 /// Calculate dynamic spacing for each gap between participants based on message lengths
 fn calculate_gap_spacings(
+    measurer: &dyn TextMeasurer,
     participants: &[Participant],
     participant_widths: &[f64],
-    statements: &[SequenceStatement],
-    font_size: u32,
+    labels: &[MessageLabel],
+    font: &Font,
+    config: &SequenceConfig,
 ) -> Vec<f64> {
     let num_gaps = participants.len().saturating_sub(1);
     if num_gaps == 0 {
@@ -202,42 +366,47 @@ fn calculate_gap_spacings(
         .map(|i| {
             let left_half = participant_widths[i] / 2.0;
             let right_half = participant_widths[i + 1] / 2.0;
-            left_half + MIN_PARTICIPANT_SPACING + right_half
+            left_half + config.actor_margin + right_half
         })
         .collect();
 
-    for statement in statements {
-        if let SequenceStatement::Message(msg) = statement {
-            // Skip self-messages (they don't affect gap spacing)
-            if msg.from == msg.to {
-                continue;
-            }
-
-            let from_idx = find_participant_index(participants, &msg.from);
-            let to_idx = find_participant_index(participants, &msg.to);
+    for label in labels {
+        let msg = label.message;
 
-            if let (Some(from_idx), Some(to_idx)) = (from_idx, to_idx) {
-                let (min_idx, max_idx) = if from_idx < to_idx {
-                    (from_idx, to_idx)
-                } else {
-                    (to_idx, from_idx)
-                };
-
-                // Calculate required width for this message
-                let required_width = text_width(&msg.text, font_size) + MESSAGE_TEXT_MARGIN;
-
-                // Calculate current total span across the gaps this message crosses
-                let current_span: f64 = spacings[min_idx..max_idx].iter().sum();
-
-                if required_width > current_span {
-                    // Need to expand - distribute extra width across spanned gaps
-                    let extra = required_width - current_span;
-                    let gaps_count = max_idx - min_idx;
-                    let extra_per_gap = extra / gaps_count as f64;
+        // Skip self-messages (they don't affect gap spacing)
+        if msg.from == msg.to {
+            continue;
+        }
 
-                    for spacing in spacings.iter_mut().take(max_idx).skip(min_idx) {
-                        *spacing += extra_per_gap;
-                    }
+        let from_idx = find_participant_index(participants, &msg.from);
+        let to_idx = find_participant_index(participants, &msg.to);
+
+        if let (Some(from_idx), Some(to_idx)) = (from_idx, to_idx) {
+            let (min_idx, max_idx) = if from_idx < to_idx {
+                (from_idx, to_idx)
+            } else {
+                (to_idx, from_idx)
+            };
+
+            // Calculate required width for this message (widest label line)
+            let text_width = label
+                .lines
+                .iter()
+                .map(|line| line.width(measurer, font))
+                .fold(0.0_f64, f64::max);
+            let required_width = text_width + config.message_text_margin;
+
+            // Calculate current total span across the gaps this message crosses
+            let current_span: f64 = spacings[min_idx..max_idx].iter().sum();
+
+            if required_width > current_span {
+                // Need to expand - distribute extra width across spanned gaps
+                let extra = required_width - current_span;
+                let gaps_count = max_idx - min_idx;
+                let extra_per_gap = extra / gaps_count as f64;
+
+                for spacing in spacings.iter_mut().take(max_idx).skip(min_idx) {
+                    *spacing += extra_per_gap;
                 }
             }
         }
@@ -250,26 +419,28 @@ fn calculate_gap_spacings(
 fn calculate_participant_layouts(
     participants: &[Participant],
     participant_widths: &[f64],
-    participant_lines: &[Vec<String>],
+    participant_lines: &[Vec<TextLine>],
     gap_spacings: &[f64],
+    padding: f64,
+    config: &SequenceConfig,
 ) -> Vec<ParticipantLayout> {
     let mut layouts = Vec::new();
-    let mut center_x = PADDING
+    let mut center_x = padding
         + participant_widths
             .first()
             .copied()
-            .unwrap_or(MIN_PARTICIPANT_WIDTH)
+            .unwrap_or(config.actor_width)
             / 2.0;
 
     for (i, participant) in participants.iter().enumerate() {
         let width = participant_widths
             .get(i)
             .copied()
-            .unwrap_or(MIN_PARTICIPANT_WIDTH);
+            .unwrap_or(config.actor_width);
         let lines = participant_lines
             .get(i)
             .cloned()
-            .unwrap_or_else(|| vec![participant.actor.clone()]);
+            .unwrap_or_else(|| vec![TextLine::plain(&participant.actor)]);
 
         layouts.push(ParticipantLayout {
             name: participant.actor.clone(),
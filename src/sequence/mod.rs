@@ -1,6 +1,7 @@
 //! Sequence diagram renderer
 
 mod constants;
+mod ids;
 mod layout;
 mod render;
 mod types;
@@ -8,42 +9,181 @@ mod types;
 use mermaid_parser::common::ast::SequenceDiagram;
 
 use crate::error::RenderResult;
+use crate::layout::{fit_content, TextLine};
+use crate::links::ParticipantLinks;
+use crate::metadata::diagram_metadata;
 use crate::options::RenderOptions;
-use crate::svg::SvgBuilder;
+use crate::styles::DiagramStyles;
+use crate::svg::minify::minify;
+use crate::svg::pretty::canonicalize;
+use crate::svg::{Palette, SvgBuilder};
 
-use constants::PADDING;
+use ids::ElementIds;
 use layout::calculate_layout;
 use render::{draw_messages, draw_participants};
+use types::Layout;
 
 /// Render a sequence diagram to SVG
+///
+/// Returns [`RenderError::InvalidOptions`](crate::RenderError::InvalidOptions)
+/// if the options fail [`RenderOptions::validate`].
 pub fn render(diagram: &SequenceDiagram, options: &RenderOptions) -> RenderResult<String> {
-    let colors = options.colors();
+    render_with_styles(diagram, options, &DiagramStyles::default())
+}
+
+/// Render a sequence diagram to SVG, applying `classDef`/`class`/`style` statements
+///
+/// See [`styles`](crate::styles) for how the statements are applied.
+pub fn render_with_styles(
+    diagram: &SequenceDiagram,
+    options: &RenderOptions,
+    styles: &DiagramStyles,
+) -> RenderResult<String> {
+    render_document(diagram, options, styles, &ParticipantLinks::default(), None)
+}
+
+/// Render a sequence diagram with the statements extracted before parsing
+///
+/// `source` is embedded in the metadata if
+/// [`embed_source`](RenderOptions::embed_source) is set.
+pub(crate) fn render_document(
+    diagram: &SequenceDiagram,
+    options: &RenderOptions,
+    styles: &DiagramStyles,
+    links: &ParticipantLinks,
+    source: Option<&str>,
+) -> RenderResult<String> {
+    options.validate()?;
+    let palette = Palette::new(&options.theme).with_style_block(options.style_block);
 
     // First pass: calculate layout and bounds
-    let layout = calculate_layout(diagram, options.font_size);
-    let (width, height) = layout.bounds.svg_size(PADDING);
+    let layout = calculate_layout(diagram, options);
+    let (width, height) = layout.bounds.svg_size(options.padding as f64);
+    let viewport = fit_content(width, height, options);
 
     // Second pass: render with calculated dimensions
-    let mut builder = SvgBuilder::new(width, height, colors.clone(), options.transparent_bg);
+    let ids = ElementIds::new(
+        &options.id_prefix,
+        layout.participants.iter().map(|p| p.name.as_str()),
+    );
+
+    let mut builder = SvgBuilder::new(
+        width,
+        height,
+        palette.colors().clone(),
+        options.transparent_bg,
+    )
+    .with_palette(palette.clone())
+    .with_style_rules(styles.stylesheet(ids.class_prefix()))
+    .with_viewport(viewport)
+    .responsive(options.use_max_width)
+    .with_number_format(options.number_format())
+    .with_role_description("sequence");
+
+    if let Some(source) = source.filter(|_| options.embed_source) {
+        builder = builder.with_metadata(diagram_metadata(source, options));
+    }
+
+    // Accessible title and description; `title` stands in for `accTitle`
+    let title = diagram
+        .accessibility
+        .title
+        .as_ref()
+        .or(diagram.title.as_ref());
+    if let Some(title) = title {
+        builder = builder.with_title(ids.title(), title);
+    }
+    let mut description: Vec<String> = diagram.accessibility.description.iter().cloned().collect();
+    if options.accessible_summary {
+        description.push(summary(&layout));
+    }
+    if !description.is_empty() {
+        builder = builder.with_description(ids.description(), description.join("\n"));
+    }
 
     // Draw participants
     draw_participants(
         &mut builder,
         &layout.participants,
         options,
+        &palette,
+        styles,
+        links,
+        &ids,
         layout.participant_height,
         layout.bottom_box_y,
     );
 
     // Draw messages
-    draw_messages(
-        &mut builder,
-        diagram,
-        &layout.participants,
-        options,
-        layout.participant_height,
-        layout.bottom_box_y,
-    );
+    draw_messages(&mut builder, &layout.messages, options, &palette, &ids);
+
+    let mut document = builder.document();
+    for processor in &options.post_processors {
+        processor.process(&mut document);
+    }
+    if options.minify {
+        minify(&mut document);
+        return Ok(document.to_compact_string());
+    }
+    if options.pretty {
+        canonicalize(&mut document);
+        return Ok(document.to_pretty_string());
+    }
+    Ok(document.to_string())
+}
+
+/// Describes the participants and messages in plain sentences
+fn summary(layout: &Layout) -> String {
+    let label = |name: &str| {
+        layout
+            .participants
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| plain_text(&p.lines))
+            .unwrap_or_else(|| name.to_string())
+    };
+    let participants: Vec<String> = layout
+        .participants
+        .iter()
+        .map(|p| plain_text(&p.lines))
+        .collect();
+
+    let mut sentences = vec![format!(
+        "Sequence diagram with {} {}: {}.",
+        participants.len(),
+        plural(participants.len(), "participant"),
+        participants.join(", ")
+    )];
+    for msg in &layout.messages {
+        let to = if msg.is_self {
+            "itself".to_string()
+        } else {
+            label(&msg.to)
+        };
+        let text = plain_text(&msg.lines);
+        sentences.push(if text.is_empty() {
+            format!("{} sends a message to {}.", label(&msg.from), to)
+        } else {
+            format!("{} sends '{}' to {}.", label(&msg.from), text, to)
+        });
+    }
+    sentences.join("\n")
+}
+
+/// Joins display lines into one line of plain text
+fn plain_text(lines: &[TextLine]) -> String {
+    lines
+        .iter()
+        .map(TextLine::text)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
 
-    Ok(builder.to_string())
+fn plural(count: usize, word: &str) -> String {
+    if count == 1 {
+        word.to_string()
+    } else {
+        format!("{}s", word)
+    }
 }
@@ -1,181 +1,382 @@
 //! Rendering functions for sequence diagrams
 
-use mermaid_parser::common::ast::{ArrowType, SequenceDiagram, SequenceStatement};
+use mermaid_parser::common::ast::ArrowType;
 
-use crate::options::RenderOptions;
+use crate::layout::TextLine;
+use crate::links::{Link, ParticipantLinks};
+use crate::options::{Font, RenderOptions};
+use crate::styles::DiagramStyles;
 use crate::svg::{
-    create_arrow, create_line, create_self_loop, draw_multiline_text, draw_rect, draw_text,
-    EndStyle, LineStyle, SvgBuilder,
+    arrow, class, line, marker_arrow, marker_self_loop, multiline_text, rect, rich_text, self_loop,
+    Element, EndStyle, LineStyle, Markers, Paint, Palette, SvgBuilder,
 };
+use crate::theme::ColorRole;
 
-use super::constants::*;
-use super::layout::find_participant_center;
-use super::types::ParticipantLayout;
+use super::ids::ElementIds;
+use super::layout::line_height;
+use super::types::{MessageLayout, ParticipantLayout};
 
 /// Draw all participants (boxes at top and bottom, lifelines)
+///
+/// Lifelines and participants go into separate layers, `<g class="lifelines">`
+/// and `<g class="participants">`, with one group per participant in each.
+#[allow(clippy::too_many_arguments)]
 pub fn draw_participants(
     builder: &mut SvgBuilder,
     participants: &[ParticipantLayout],
     options: &RenderOptions,
+    palette: &Palette,
+    styles: &DiagramStyles,
+    links: &ParticipantLinks,
+    ids: &ElementIds,
     participant_height: f64,
     bottom_box_y: f64,
 ) {
-    let colors = options.colors();
+    let colors = palette.colors();
+    let config = &options.sequence;
+    let padding = options.padding as f64;
+    let font = options.resolve_font(&options.participant_font);
+    let line_height = line_height(&font);
+
+    let mut lifelines = Element::new("g").with_attr("class", "lifelines");
+    let mut boxes = Element::new("g").with_attr("class", "participants");
+
+    for (index, p) in participants.iter().enumerate() {
+        // User classes from classDef/class/style statements
+        let user_classes: String = styles
+            .classes_of(&p.name, ids.class_prefix())
+            .iter()
+            .map(|c| format!(" {}", c))
+            .collect();
+        let label_class = format!("{}{}", class::ACTOR, user_classes);
+
+        // A single link wraps the participant; its labels lose their own links
+        // so anchors do not nest
+        let links = if options.links {
+            links.of(&p.name)
+        } else {
+            &[]
+        };
+        let lines: Vec<TextLine> = match links {
+            [_] => p.lines.iter().map(TextLine::without_links).collect(),
+            _ => p.lines.clone(),
+        };
+
+        let mut group = Element::new("g")
+            .with_attr("id", ids.participant(&p.name))
+            .with_attr("class", "participant")
+            .with_attr("data-participant", &p.name)
+            .with_attr("data-index", index);
 
-    for p in participants {
         // Top participant box
-        builder.add_element(draw_rect(
+        group.push(rect(
             p.left_edge(),
-            PADDING,
+            padding,
             p.width,
             participant_height,
-            &colors.participant_bg,
-            &colors.participant_border,
+            palette.paint(ColorRole::ParticipantBg),
+            palette.paint(ColorRole::ParticipantBorder),
+            colors.border_width,
+            colors.corner_radius,
+            &format!("{} {}{}", class::ACTOR, class::ACTOR_TOP, user_classes),
         ));
 
         // Top participant name (single or multi-line)
-        let center_y = PADDING + participant_height / 2.0;
-        if p.lines.len() == 1 {
-            builder.add_element(draw_text(
+        let center_y = padding + participant_height / 2.0;
+        if lines.len() == 1 {
+            group.push(rich_text(
                 p.center_x,
                 center_y + 5.0, // Baseline adjustment
-                &p.lines[0],
-                &colors.text,
-                options.font_size,
+                &lines[0],
+                palette.paint(ColorRole::ParticipantText),
+                &font,
                 "middle",
+                &label_class,
             ));
         } else {
-            builder.add_element(draw_multiline_text(
+            group.extend(multiline_text(
                 p.center_x,
                 center_y,
-                &p.lines,
-                &colors.text,
-                options.font_size,
-                LINE_HEIGHT,
+                &lines,
+                palette.paint(ColorRole::ParticipantText),
+                &font,
+                line_height,
                 "middle",
+                &label_class,
             ));
         }
 
         // Lifeline
-        let lifeline_start = PADDING + participant_height;
+        let lifeline_start = padding + participant_height;
         let lifeline_end = bottom_box_y;
-        builder.add_element(create_line(
-            p.center_x,
-            lifeline_start,
-            p.center_x,
-            lifeline_end,
-            &colors.line,
-            LineStyle::Solid,
-        ));
-
-        // Bottom participant box
-        builder.add_element(draw_rect(
-            p.left_edge(),
-            bottom_box_y,
-            p.width,
-            participant_height,
-            &colors.participant_bg,
-            &colors.participant_border,
-        ));
+        lifelines.push(
+            Element::new("g")
+                .with_attr("id", ids.lifeline(&p.name))
+                .with_attr("class", "lifeline")
+                .with_attr("data-participant", &p.name)
+                .with_child(line(
+                    p.center_x,
+                    lifeline_start,
+                    p.center_x,
+                    lifeline_end,
+                    palette.paint(ColorRole::Lifeline),
+                    colors.line_width,
+                    LineStyle::Solid,
+                    class::ACTOR_LINE,
+                )),
+        );
 
-        // Bottom participant name (single or multi-line)
-        let bottom_center_y = bottom_box_y + participant_height / 2.0;
-        if p.lines.len() == 1 {
-            builder.add_element(draw_text(
-                p.center_x,
-                bottom_center_y + 5.0, // Baseline adjustment
-                &p.lines[0],
-                &colors.text,
-                options.font_size,
-                "middle",
-            ));
-        } else {
-            builder.add_element(draw_multiline_text(
-                p.center_x,
-                bottom_center_y,
-                &p.lines,
-                &colors.text,
-                options.font_size,
-                LINE_HEIGHT,
-                "middle",
+        if config.mirror_actors {
+            // Bottom participant box
+            group.push(rect(
+                p.left_edge(),
+                bottom_box_y,
+                p.width,
+                participant_height,
+                palette.paint(ColorRole::ParticipantBg),
+                palette.paint(ColorRole::ParticipantBorder),
+                colors.border_width,
+                colors.corner_radius,
+                &format!("{} {}{}", class::ACTOR, class::ACTOR_BOTTOM, user_classes),
             ));
+
+            // Bottom participant name (single or multi-line)
+            let bottom_center_y = bottom_box_y + participant_height / 2.0;
+            if lines.len() == 1 {
+                group.push(rich_text(
+                    p.center_x,
+                    bottom_center_y + 5.0, // Baseline adjustment
+                    &lines[0],
+                    palette.paint(ColorRole::ParticipantText),
+                    &font,
+                    "middle",
+                    &label_class,
+                ));
+            } else {
+                group.extend(multiline_text(
+                    p.center_x,
+                    bottom_center_y,
+                    &lines,
+                    palette.paint(ColorRole::ParticipantText),
+                    &font,
+                    line_height,
+                    "middle",
+                    &label_class,
+                ));
+            }
+        }
+
+        boxes.push(link_participant(group, links));
+    }
+
+    builder.add(lifelines);
+    builder.add(boxes);
+}
+
+/// Attaches a participant's links to its group
+///
+/// A single link wraps the shapes in `<a href>` with the link label as a
+/// tooltip; several links are listed in a `<title>` tooltip.
+fn link_participant(mut group: Element, links: &[Link]) -> Element {
+    match links {
+        [] => group,
+        [link] => {
+            let mut anchor = Element::new("a")
+                .with_attr("href", &link.url)
+                .with_child(Element::new("title").with_text(link.label.as_str()));
+            anchor.extend(std::mem::take(group.children_mut()));
+            group.with_child(anchor)
+        }
+        _ => {
+            let tooltip = links
+                .iter()
+                .map(|link| format!("{}: {}", link.label, link.url))
+                .collect::<Vec<_>>()
+                .join("\n");
+            group
+                .children_mut()
+                .insert(0, Element::new("title").with_text(tooltip).into());
+            group
         }
     }
 }
 
 /// Draw all messages between participants
+///
+/// Each message's arrow and label are grouped in `<g class="messages">`.
+/// Arrowheads reference shared markers unless
+/// [`inline_arrowheads`](RenderOptions::inline_arrowheads) is set.
 pub fn draw_messages(
     builder: &mut SvgBuilder,
-    diagram: &SequenceDiagram,
-    participants: &[ParticipantLayout],
+    messages: &[MessageLayout],
     options: &RenderOptions,
-    participant_height: f64,
-    bottom_box_y: f64,
+    palette: &Palette,
+    ids: &ElementIds,
 ) {
-    let colors = options.colors();
-    let mut message_y = PADDING + participant_height + MESSAGE_SPACING;
-
-    for statement in &diagram.statements {
-        if let SequenceStatement::Message(msg) = statement {
-            let from_x = find_participant_center(participants, &msg.from);
-            let to_x = find_participant_center(participants, &msg.to);
-
-            if let (Some(fx), Some(tx)) = (from_x, to_x) {
-                if msg.from == msg.to {
-                    // Self-message
-                    if message_y + SELF_MESSAGE_HEIGHT <= bottom_box_y {
-                        let line_style = if is_dotted_arrow(&msg.arrow_type) {
-                            LineStyle::Dotted
-                        } else {
-                            LineStyle::Solid
-                        };
-
-                        builder.add_element(create_self_loop(
-                            fx,
-                            message_y,
-                            &colors.line,
-                            line_style,
-                        ));
-
-                        builder.add_element(draw_text(
-                            fx + SELF_LOOP_TEXT_OFFSET,
-                            message_y + SELF_MESSAGE_HEIGHT / 2.0,
-                            &msg.text,
-                            &colors.text,
-                            options.font_size,
-                            "start",
-                        ));
-                    }
-                    message_y += MESSAGE_SPACING + SELF_MESSAGE_HEIGHT;
-                } else {
-                    // Normal message
-                    let (line_style, start_end, end_end) = arrow_type_to_styles(&msg.arrow_type);
-
-                    builder.add_element(create_arrow(
-                        fx,
-                        message_y,
-                        tx,
-                        message_y,
-                        &colors.line,
-                        line_style,
-                        start_end,
-                        end_end,
-                    ));
-
-                    let text_x = (fx + tx) / 2.0;
-                    builder.add_element(draw_text(
-                        text_x,
-                        message_y - 10.0,
-                        &msg.text,
-                        &colors.text,
-                        options.font_size,
-                        "middle",
-                    ));
-
-                    message_y += MESSAGE_SPACING;
-                }
-            }
-        }
+    let colors = palette.colors();
+    let config = &options.sequence;
+    let font = options.resolve_font(&options.message_font);
+    let line_height = line_height(&font);
+
+    let mut layer = Element::new("g").with_attr("class", "messages");
+    let mut markers =
+        Markers::new(ids.marker_prefix()).with_class_color(colors.get(ColorRole::Line));
+
+    for msg in messages {
+        let mut group = Element::new("g")
+            .with_attr("id", ids.message(msg.index))
+            .with_attr("class", "message")
+            .with_attr("data-from", &msg.from)
+            .with_attr("data-to", &msg.to)
+            .with_attr("data-arrow", arrow_type_name(&msg.arrow_type))
+            .with_attr("data-index", msg.index);
+
+        let (shapes, label) = if msg.is_self {
+            // Self-message
+            let line_style = if is_dotted_arrow(&msg.arrow_type) {
+                LineStyle::Dotted
+            } else {
+                LineStyle::Solid
+            };
+
+            let stroke = palette.paint(ColorRole::Line);
+            let class = message_line_class(line_style);
+            let shapes = if options.inline_arrowheads {
+                self_loop(
+                    msg.from_x,
+                    msg.y,
+                    config.self_loop_width,
+                    stroke,
+                    colors.line_width,
+                    line_style,
+                    class,
+                )
+            } else {
+                vec![marker_self_loop(
+                    msg.from_x,
+                    msg.y,
+                    config.self_loop_width,
+                    stroke,
+                    colors.line_width,
+                    line_style,
+                    class,
+                    &mut markers,
+                )]
+            };
+
+            let label = draw_label(
+                msg.from_x + config.self_loop_text_offset,
+                msg.y + config.self_message_height / 2.0,
+                &msg.lines,
+                palette.paint(ColorRole::MessageText),
+                &font,
+                line_height,
+                "start",
+            );
+            (shapes, label)
+        } else {
+            // Normal message
+            let (line_style, start_end, end_end) = arrow_type_to_styles(&msg.arrow_type);
+
+            let stroke = palette.paint(ColorRole::Line);
+            let class = message_line_class(line_style);
+            let shapes = if options.inline_arrowheads {
+                arrow(
+                    msg.from_x,
+                    msg.y,
+                    msg.to_x,
+                    msg.y,
+                    stroke,
+                    colors.line_width,
+                    line_style,
+                    start_end,
+                    end_end,
+                    class,
+                )
+            } else {
+                vec![marker_arrow(
+                    msg.from_x,
+                    msg.y,
+                    msg.to_x,
+                    msg.y,
+                    stroke,
+                    colors.line_width,
+                    line_style,
+                    start_end,
+                    end_end,
+                    class,
+                    &mut markers,
+                )]
+            };
+
+            let text_x = (msg.from_x + msg.to_x) / 2.0;
+            let label = draw_label(
+                text_x,
+                msg.y - 10.0,
+                &msg.lines,
+                palette.paint(ColorRole::MessageText),
+                &font,
+                line_height,
+                "middle",
+            );
+            (shapes, label)
+        };
+
+        group.extend(shapes);
+        group.extend(label);
+        layer.push(group);
+    }
+
+    for marker in markers.definitions() {
+        builder.add_definition(marker);
+    }
+    builder.add(layer);
+}
+
+/// Draw a message label whose last line sits on `baseline_y`
+///
+/// Additional lines stack upward, away from the arrow.
+fn draw_label(
+    x: f64,
+    baseline_y: f64,
+    lines: &[TextLine],
+    fill: Paint,
+    font: &Font,
+    line_height: f64,
+    anchor: &str,
+) -> Vec<Element> {
+    if lines.len() == 1 {
+        return vec![rich_text(
+            x,
+            baseline_y,
+            &lines[0],
+            fill,
+            font,
+            anchor,
+            class::MESSAGE_TEXT,
+        )];
+    }
+
+    // multiline_text centers the block; shift so the last baseline lands on baseline_y
+    let total_height = (lines.len() - 1) as f64 * line_height;
+    let center_y = baseline_y - total_height / 2.0 - font.size as f64 * 0.35;
+    multiline_text(
+        x,
+        center_y,
+        lines,
+        fill,
+        font,
+        line_height,
+        anchor,
+        class::MESSAGE_TEXT,
+    )
+}
+
+/// Mermaid's class for solid (`messageLine0`) and dotted (`messageLine1`) lines
+fn message_line_class(line_style: LineStyle) -> &'static str {
+    match line_style {
+        LineStyle::Solid => class::MESSAGE_LINE_SOLID,
+        LineStyle::Dotted => class::MESSAGE_LINE_DOTTED,
     }
 }
 
@@ -197,6 +398,20 @@ fn arrow_type_to_styles(arrow_type: &ArrowType) -> (LineStyle, EndStyle, EndStyl
     }
 }
 
+/// Name of an arrow type for the `data-arrow` attribute
+fn arrow_type_name(arrow_type: &ArrowType) -> &'static str {
+    match arrow_type {
+        ArrowType::SolidOpen => "solid-open",
+        ArrowType::SolidClosed => "solid-closed",
+        ArrowType::Cross => "cross",
+        ArrowType::Point => "point",
+        ArrowType::BiDirectionalSolid => "bidirectional-solid",
+        ArrowType::DottedOpen => "dotted-open",
+        ArrowType::DottedClosed => "dotted-closed",
+        ArrowType::BiDirectionalDotted => "bidirectional-dotted",
+    }
+}
+
 /// Check if an arrow type uses dotted line style
 fn is_dotted_arrow(arrow_type: &ArrowType) -> bool {
     matches!(
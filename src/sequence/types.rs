@@ -1,6 +1,8 @@
 //! Type definitions for sequence diagram layout
 
-use crate::layout::ContentBounds;
+use mermaid_parser::common::ast::ArrowType;
+
+use crate::layout::{ContentBounds, TextLine};
 
 /// Layout information for a single participant
 #[derive(Debug, Clone)]
@@ -8,7 +10,7 @@ pub struct ParticipantLayout {
     /// Actor name (for lookup)
     pub name: String,
     /// Display lines (split by line breaks)
-    pub lines: Vec<String>,
+    pub lines: Vec<TextLine>,
     /// Center X position
     pub center_x: f64,
     /// Box width (uniform across all participants)
@@ -22,12 +24,37 @@ impl ParticipantLayout {
     }
 }
 
+/// Layout information for a single message row
+#[derive(Debug, Clone)]
+pub struct MessageLayout {
+    /// Index of the message among the diagram's statements
+    pub index: usize,
+    /// Name of the sending participant
+    pub from: String,
+    /// Name of the receiving participant
+    pub to: String,
+    /// Center X of the sending participant
+    pub from_x: f64,
+    /// Center X of the receiving participant
+    pub to_x: f64,
+    /// Y position of the arrow (top of the loop for self-messages)
+    pub y: f64,
+    /// Label lines (at least one)
+    pub lines: Vec<TextLine>,
+    /// Arrow type of the message
+    pub arrow_type: ArrowType,
+    /// Whether the message is sent to the same participant
+    pub is_self: bool,
+}
+
 /// Calculated layout information for rendering
 pub struct Layout {
     /// Content bounds for SVG sizing
     pub bounds: ContentBounds,
     /// Participant layouts
     pub participants: Vec<ParticipantLayout>,
+    /// Message rows in drawing order
+    pub messages: Vec<MessageLayout>,
     /// Uniform height for all participant boxes
     pub participant_height: f64,
     /// Y position of bottom participant boxes
@@ -0,0 +1,201 @@
+//! Statements handled before parsing
+//!
+//! The parser knows nothing of diagram configuration, styling, accessibility
+//! or links, so [`extract_statements`] removes them from the source in a
+//! single scan before it is parsed. Each of these modules only interprets
+//! its own statements:
+//!
+//! - front-matter and `%%{init: ...}%%` directives (see [`config`](crate::config)),
+//! - `classDef`, `class` and `style` (see [`styles`](crate::styles)),
+//! - `accTitle` and `accDescr` (see [`accessibility`](crate::accessibility)),
+//! - `link` and `links` (see [`links`](crate::links)).
+//!
+//! Lines inside a multi-line init directive or `accDescr { ... }` block
+//! belong to it and are never taken for other statements.
+
+use crate::accessibility::{Accessibility, DescriptionBlock};
+use crate::config::{split_front_matter, DiagramConfig};
+use crate::links::ParticipantLinks;
+use crate::styles::DiagramStyles;
+
+/// Everything collected from the statements of a diagram
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Statements {
+    /// Front-matter `config` and init directives
+    pub config: DiagramConfig,
+    /// `classDef`, `class` and `style` statements
+    pub styles: DiagramStyles,
+    /// `accTitle` and `accDescr` statements
+    pub accessibility: Accessibility,
+    /// `link` and `links` statements
+    pub links: ParticipantLinks,
+}
+
+/// A statement spanning several lines
+enum Open {
+    /// Lines of an init directive, up to its closing `}%%`
+    Directive(String),
+    /// An `accDescr { ... }` block
+    Description(DescriptionBlock),
+}
+
+/// Removes the statements the parser does not handle from a diagram source
+///
+/// Returns the remaining source, with the removed lines left blank so line
+/// numbers in parse errors still match, and the collected statements. Other
+/// directives such as `%%{wrap}%%` are left in place.
+pub fn extract_statements(input: &str) -> (String, Statements) {
+    let mut statements = Statements::default();
+    let input = match split_front_matter(input) {
+        Some((yaml, rest)) => {
+            statements.config.add_front_matter(&yaml);
+            rest
+        }
+        None => input.to_string(),
+    };
+
+    let lines: Vec<&str> = input.split_inclusive('\n').collect();
+    let mut source = String::with_capacity(input.len());
+    let mut open: Option<Open> = None;
+
+    for (index, &line) in lines.iter().enumerate() {
+        match &mut open {
+            Some(Open::Directive(directive)) => {
+                directive.push_str(line);
+                if line.contains("}%%") {
+                    source.push_str(&statements.config.strip_directives(directive));
+                    open = None;
+                }
+                continue;
+            }
+            Some(Open::Description(block)) => {
+                let taken = block.push(line);
+                if !taken || block.is_closed() {
+                    statements.accessibility.description = block.text();
+                    open = None;
+                }
+                if taken {
+                    blank(line, &mut source);
+                    continue;
+                }
+            }
+            None => {}
+        }
+
+        if let Some(start) = line.find("%%{") {
+            let closed_later = lines[index + 1..].iter().any(|line| line.contains("}%%"));
+            if line[start..].contains("}%%") || !closed_later {
+                source.push_str(&statements.config.strip_directives(line));
+            } else {
+                open = Some(Open::Directive(line.to_string()));
+            }
+            continue;
+        }
+
+        let statement = line.trim();
+        let (keyword, rest) = split_keyword(statement);
+        let separated = rest.starts_with(char::is_whitespace);
+        let handled = match keyword {
+            "accTitle" => statements.accessibility.add_title(rest),
+            "accDescr" => {
+                if let Some(block) = statements.accessibility.add_description(
+                    rest,
+                    line,
+                    index + 1,
+                    &lines[index + 1..],
+                ) {
+                    open = Some(Open::Description(block));
+                }
+                true
+            }
+            "classDef" | "class" | "style" if separated => {
+                statements.styles.add_statement(keyword, rest.trim())
+            }
+            "link" | "links" if separated => statements.links.add_statement(keyword, rest.trim()),
+            _ => false,
+        };
+
+        if handled {
+            blank(line, &mut source);
+        } else {
+            source.push_str(line);
+        }
+    }
+
+    match open {
+        Some(Open::Directive(directive)) => source.push_str(&directive),
+        // An unterminated block may run to the end of the diagram
+        Some(Open::Description(block)) => statements.accessibility.description = block.text(),
+        None => {}
+    }
+    statements.styles.check_assignments();
+
+    (source, statements)
+}
+
+/// Splits a statement into its leading keyword and the rest, which starts
+/// with whitespace, `:` or `{`
+fn split_keyword(statement: &str) -> (&str, &str) {
+    let end = statement
+        .find(|c: char| c.is_whitespace() || c == ':' || c == '{')
+        .unwrap_or(statement.len());
+    statement.split_at(end)
+}
+
+/// Leaves a removed line blank
+fn blank(line: &str, source: &mut String) {
+    if line.ends_with('\n') {
+        source.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_scan_keeps_line_numbers() {
+        let input = "---\nconfig:\n  theme: forest\n---\n\
+                     %%{init: {\n  'wrap': true\n}}%%\n\
+                     sequenceDiagram\n    accTitle: Flow\n    classDef hot fill:#f00\n\
+                     \x20   class A hot\n    link A: Home @ https://example.com\n    A->>B: Hi\n";
+        let (source, statements) = extract_statements(input);
+
+        assert_eq!(
+            source,
+            format!(
+                "{}sequenceDiagram\n{}    A->>B: Hi\n",
+                "\n".repeat(7),
+                "\n".repeat(4)
+            )
+        );
+        assert!(!statements.config.is_empty());
+        assert_eq!(statements.accessibility.title.as_deref(), Some("Flow"));
+        assert_eq!(statements.styles.classes_of("A", ""), vec!["hot"]);
+        assert_eq!(statements.links.of("A").len(), 1);
+    }
+
+    #[test]
+    fn test_blocks_hide_statements() {
+        let input = "%%{init: {\n  'theme': 'dark',\n  'note': 'a \\\nclass A hot'\n}}%%\n\
+                     accDescr {\n  style Bob bold\n  links are drawn: twice\n}\nA->>B: Hi";
+        let (source, statements) = extract_statements(input);
+
+        assert_eq!(source, "\n\n\n\n\n\n\n\n\nA->>B: Hi");
+        assert!(statements.config.warnings().is_empty());
+        assert_eq!(statements.styles, DiagramStyles::default());
+        assert_eq!(statements.links, ParticipantLinks::default());
+        assert_eq!(
+            statements.accessibility.description.as_deref(),
+            Some("style Bob bold\nlinks are drawn: twice")
+        );
+    }
+
+    #[test]
+    fn test_keywords_must_stand_alone() {
+        let input = "classic A hot\nstyle-guide B x\nlink: A\nA->>B: class A hot";
+        let (source, statements) = extract_statements(input);
+        assert_eq!(source, input);
+        assert_eq!(statements, Statements::default());
+    }
+}
@@ -0,0 +1,369 @@
+//! Mermaid `classDef`, `class` and `style` statements
+//!
+//! ```text
+//! sequenceDiagram
+//!     classDef important fill:#ffe0e0,stroke:#c00,color:#900
+//!     class Alice important
+//!     style Bob stroke-dasharray:5 5
+//!     Alice->>Bob: Hello
+//! ```
+//!
+//! Participants named by `class` get the class added to their boxes and
+//! labels; `style` declarations are given a generated class (`msv-style-<n>`
+//! after the diagram's id prefix). Both are turned into rules of an embedded
+//! `<style>` block, scoped to the diagram, so they override the theme colours
+//! whether those are inline attributes or stylesheet rules.
+//!
+//! `color` and the `font-*` properties apply to the participant's label,
+//! everything else to its box. A `classDef default` applies to every
+//! participant. Invalid names and declarations are reported as warnings.
+
+use std::collections::BTreeMap;
+
+/// A single `property: value` declaration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declaration {
+    /// CSS property name
+    pub property: String,
+    /// CSS value
+    pub value: String,
+}
+
+impl Declaration {
+    /// Returns `true` if the declaration styles text rather than shapes
+    pub fn is_text(&self) -> bool {
+        self.property == "color" || self.property.starts_with("font-")
+    }
+
+    /// Formats the declaration as CSS, mapping `color` to the text fill
+    fn to_css(&self) -> String {
+        let property = if self.property == "color" {
+            "fill"
+        } else {
+            &self.property
+        };
+        format!("{}: {};", property, self.value)
+    }
+}
+
+/// Class definitions and assignments collected from a diagram
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiagramStyles {
+    class_defs: BTreeMap<String, Vec<Declaration>>,
+    assignments: BTreeMap<String, Vec<String>>,
+    styles: BTreeMap<String, Vec<Declaration>>,
+    warnings: Vec<String>,
+}
+
+impl DiagramStyles {
+    /// Returns `true` if the diagram defined no classes or styles
+    pub fn is_empty(&self) -> bool {
+        self.class_defs.is_empty() && self.assignments.is_empty() && self.styles.is_empty()
+    }
+
+    /// Returns the declarations of a class
+    pub fn class_def(&self, name: &str) -> Option<&[Declaration]> {
+        self.class_defs.get(name).map(Vec::as_slice)
+    }
+
+    /// Returns problems found while extracting the statements
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Returns warnings for `class` and `style` statements naming
+    /// participants that are not in `participants`
+    pub fn unknown_participants<'a>(
+        &self,
+        participants: impl IntoIterator<Item = &'a str>,
+    ) -> Vec<String> {
+        let known: Vec<&str> = participants.into_iter().collect();
+        self.assignments
+            .keys()
+            .chain(self.styles.keys())
+            .filter(|name| !known.contains(&name.as_str()))
+            .map(|name| format!("unknown participant '{}' in class or style statement", name))
+            .collect()
+    }
+
+    /// Returns the classes to add to a participant's boxes and labels
+    ///
+    /// Generated class names start with `prefix`.
+    pub fn classes_of(&self, participant: &str, prefix: &str) -> Vec<String> {
+        let mut classes = Vec::new();
+        if self.class_defs.contains_key("default") {
+            classes.push("default".to_string());
+        }
+        if let Some(assigned) = self.assignments.get(participant) {
+            classes.extend(assigned.iter().cloned());
+        }
+        if let Some(index) = self.styles.keys().position(|name| name == participant) {
+            classes.push(style_class(prefix, index));
+        }
+        classes
+    }
+
+    /// Returns the CSS rules for the class definitions and styles
+    ///
+    /// Generated class names start with `prefix`, as in
+    /// [`classes_of`](Self::classes_of). Empty if the diagram has none.
+    pub fn stylesheet(&self, prefix: &str) -> String {
+        let generated = self
+            .styles
+            .values()
+            .enumerate()
+            .map(|(index, declarations)| (style_class(prefix, index), declarations));
+        self.class_defs
+            .iter()
+            .map(|(name, declarations)| (name.clone(), declarations))
+            .chain(generated)
+            .map(|(class, declarations)| class_rules(&class, declarations))
+            .collect()
+    }
+
+    /// Handles a `classDef`, `class` or `style` statement, given the text
+    /// after the keyword
+    pub(crate) fn add_statement(&mut self, keyword: &str, rest: &str) -> bool {
+        match keyword {
+            "classDef" => self.add_class_def(rest),
+            "class" => self.add_assignment(rest),
+            "style" => self.add_style(rest),
+            _ => false,
+        }
+    }
+
+    /// Warns about assigned classes that were never defined
+    pub(crate) fn check_assignments(&mut self) {
+        let undefined: Vec<String> = self
+            .assignments
+            .values()
+            .flatten()
+            .filter(|class| !self.class_defs.contains_key(*class))
+            .map(|class| format!("class '{}' is not defined", class))
+            .collect();
+        for warning in undefined {
+            if !self.warnings.contains(&warning) {
+                self.warnings.push(warning);
+            }
+        }
+    }
+
+    /// Handles `classDef <names> <declarations>`
+    fn add_class_def(&mut self, rest: &str) -> bool {
+        let Some((names, declarations)) = rest.split_once(char::is_whitespace) else {
+            return false;
+        };
+        let declarations = self.parse_declarations(declarations);
+        for name in names.split(',').map(str::trim) {
+            if self.check_name(name, "class") {
+                self.class_defs
+                    .insert(name.to_string(), declarations.clone());
+            }
+        }
+        true
+    }
+
+    /// Handles `class <participants> <class>`
+    fn add_assignment(&mut self, rest: &str) -> bool {
+        let Some((participants, class)) = rest.rsplit_once(char::is_whitespace) else {
+            return false;
+        };
+        if !self.check_name(class, "class") {
+            return true;
+        }
+        for participant in participants.split(',').map(str::trim) {
+            let classes = self.assignments.entry(participant.to_string()).or_default();
+            if !classes.iter().any(|c| c == class) {
+                classes.push(class.to_string());
+            }
+        }
+        true
+    }
+
+    /// Handles `style <participant> <declarations>`
+    fn add_style(&mut self, rest: &str) -> bool {
+        let Some((participant, declarations)) = rest.split_once(char::is_whitespace) else {
+            return false;
+        };
+        let declarations = self.parse_declarations(declarations);
+        self.styles
+            .entry(participant.to_string())
+            .or_default()
+            .extend(declarations);
+        true
+    }
+
+    /// Parses comma separated `property:value` pairs
+    ///
+    /// Commas inside parentheses, as in `rgb(0, 0, 0)`, do not split.
+    fn parse_declarations(&mut self, input: &str) -> Vec<Declaration> {
+        let input = input.trim().trim_end_matches(';');
+        let mut declarations = Vec::new();
+        for part in split_top_level(input) {
+            let part = part.trim();
+            if part.is_empty() {
+                continue;
+            }
+            match part.split_once(':') {
+                Some((property, value))
+                    if is_property(property.trim()) && is_safe_value(value.trim()) =>
+                {
+                    declarations.push(Declaration {
+                        property: property.trim().to_string(),
+                        value: value.trim().to_string(),
+                    });
+                }
+                _ => self
+                    .warnings
+                    .push(format!("invalid style declaration '{}' ignored", part)),
+            }
+        }
+        declarations
+    }
+
+    /// Checks that a class name is a plain CSS identifier
+    fn check_name(&mut self, name: &str, kind: &str) -> bool {
+        let valid = !name.is_empty()
+            && !name.starts_with(|c: char| c.is_ascii_digit() || c == '-')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            self.warnings
+                .push(format!("invalid {} name '{}' ignored", kind, name));
+        }
+        valid
+    }
+}
+
+/// Name of the class generated for the n-th `style` statement target
+fn style_class(prefix: &str, index: usize) -> String {
+    format!("{}msv-style-{}", prefix, index)
+}
+
+/// Formats the box and label rules of one class
+fn class_rules(class: &str, declarations: &[Declaration]) -> String {
+    let (text, shape): (Vec<_>, Vec<_>) = declarations.iter().partition(|d| d.is_text());
+    let mut css = String::new();
+    for (selector, declarations) in [("rect", shape), ("text", text)] {
+        if declarations.is_empty() {
+            continue;
+        }
+        let body: Vec<String> = declarations.iter().map(|d| d.to_css()).collect();
+        css.push_str(&format!(
+            "{}.{} {{ {} }}\n",
+            selector,
+            class,
+            body.join(" ")
+        ));
+    }
+    css
+}
+
+/// Splits on commas outside parentheses
+fn split_top_level(input: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in input.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(&input[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&input[start..]);
+    parts
+}
+
+/// Returns `true` for a lowercase CSS property name
+fn is_property(property: &str) -> bool {
+    !property.is_empty() && property.chars().all(|c| c.is_ascii_lowercase() || c == '-')
+}
+
+/// Rejects values that could escape the declaration or the `<style>`
+/// element, or open a comment swallowing the rules after them
+fn is_safe_value(value: &str) -> bool {
+    !value.is_empty()
+        && !value.contains("/*")
+        && !value.contains("*/")
+        && !value
+            .chars()
+            .any(|c| matches!(c, ';' | '{' | '}' | '<' | '>' | '&' | '"' | '\\') || c.is_control())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::statements::{extract_statements, Statements};
+
+    #[test]
+    fn test_extracts_statements_and_keeps_line_numbers() {
+        let input = "sequenceDiagram\n    classDef hot fill:#f00\n    class A hot\n    style B stroke:#00f\n    A->>B: Hi\n";
+        let (source, Statements { styles, .. }) = extract_statements(input);
+
+        assert_eq!(source, "sequenceDiagram\n\n\n\n    A->>B: Hi\n");
+        assert_eq!(styles.classes_of("A", ""), vec!["hot"]);
+        assert_eq!(styles.classes_of("B", ""), vec!["msv-style-0"]);
+        assert_eq!(styles.classes_of("B", "login-"), vec!["login-msv-style-0"]);
+        assert!(styles.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_stylesheet_splits_box_and_text() {
+        let (_, Statements { styles, .. }) = extract_statements(
+            "classDef hot fill:#f00,color:#fff,font-weight:bold;\nclass A,B hot",
+        );
+        assert_eq!(
+            styles.stylesheet(""),
+            "rect.hot { fill: #f00; }\ntext.hot { fill: #fff; font-weight: bold; }\n"
+        );
+        assert_eq!(styles.classes_of("B", ""), vec!["hot"]);
+    }
+
+    #[test]
+    fn test_parenthesised_values_keep_commas() {
+        let (_, Statements { styles, .. }) =
+            extract_statements("classDef soft fill:rgb(255, 240, 240),stroke:#999");
+        let declarations = styles.class_def("soft").unwrap();
+        assert_eq!(declarations[0].value, "rgb(255, 240, 240)");
+        assert_eq!(declarations.len(), 2);
+    }
+
+    #[test]
+    fn test_default_class_applies_to_everyone() {
+        let (_, Statements { styles, .. }) = extract_statements("classDef default fill:#eee");
+        assert_eq!(styles.classes_of("Anyone", ""), vec!["default"]);
+    }
+
+    #[test]
+    fn test_rejects_unsafe_input() {
+        let (_, Statements { styles, .. }) = extract_statements(
+            "classDef bad fill:red}</style><script>\nclassDef x fill:red/*\n\
+             style A stroke:*/red\nclassDef 1x fill:red\nclass A missing",
+        );
+        assert_eq!(
+            styles.warnings(),
+            [
+                "invalid style declaration 'fill:red}</style><script>' ignored",
+                "invalid style declaration 'fill:red/*' ignored",
+                "invalid style declaration 'stroke:*/red' ignored",
+                "invalid class name '1x' ignored",
+                "class 'missing' is not defined",
+            ]
+        );
+        assert!(styles.stylesheet("").is_empty());
+    }
+
+    #[test]
+    fn test_unknown_participants() {
+        let (_, Statements { styles, .. }) = extract_statements("style Carol fill:#fff");
+        assert_eq!(
+            styles.unknown_participants(["Alice", "Bob"]),
+            vec!["unknown participant 'Carol' in class or style statement"]
+        );
+    }
+}
@@ -1,22 +1,37 @@
 //! SVG document builder
 //!
 //! Provides a builder pattern for constructing SVG documents with
-//! theme-aware styling and optional transparent backgrounds.
+//! theme-aware styling and optional transparent backgrounds. Embedded
+//! stylesheets are scoped to the diagram with a class on the root.
 
 use std::fmt;
 
+use crate::layout::Viewport;
 use crate::options::ThemeColors;
+use crate::theme::ColorRole;
+
+use super::element::{Element, Node};
+use super::number::NumberFormat;
+use super::style::{class, painted, scope_class, scope_stylesheet, Palette, ADAPTIVE_CLASS};
 
 /// Builder for constructing SVG documents
 ///
 /// Collects SVG elements and renders them into a complete SVG document
 /// with proper XML structure, dimensions, and background handling.
+/// [`document`](Self::document) returns the typed tree for post-processing.
 pub struct SvgBuilder {
-    width: u32,
-    height: u32,
-    elements: Vec<String>,
-    colors: ThemeColors,
+    viewport: Viewport,
+    elements: Vec<Node>,
+    definitions: Vec<Element>,
+    title: Option<(String, String)>,
+    description: Option<(String, String)>,
+    role_description: Option<String>,
+    metadata: Option<Element>,
+    palette: Palette,
+    number_format: NumberFormat,
+    style_rules: String,
     transparent: bool,
+    responsive: bool,
 }
 
 impl SvgBuilder {
@@ -30,58 +45,215 @@ impl SvgBuilder {
     /// * `transparent` - If `true`, omits the background rectangle
     pub fn new(width: u32, height: u32, colors: ThemeColors, transparent: bool) -> Self {
         Self {
-            width,
-            height,
+            viewport: Viewport::unscaled(width, height),
             elements: Vec::new(),
-            colors,
+            definitions: Vec::new(),
+            title: None,
+            description: None,
+            role_description: None,
+            metadata: None,
+            palette: Palette::fixed(colors),
+            number_format: NumberFormat::default(),
+            style_rules: String::new(),
             transparent,
+            responsive: false,
         }
     }
 
+    /// Places the content within a (possibly scaled) canvas (builder pattern)
+    ///
+    /// The canvas takes the viewport's dimensions and all elements are
+    /// wrapped in a group carrying the viewport's scale and offset.
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    /// Paints the background and embeds the stylesheet of `palette` (builder pattern)
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Sets how numbers in geometric attributes are written (builder pattern)
+    ///
+    /// Applied to every element when the document is built.
+    pub fn with_number_format(mut self, number_format: NumberFormat) -> Self {
+        self.number_format = number_format;
+        self
+    }
+
+    /// Appends CSS rules to the embedded stylesheet (builder pattern)
+    ///
+    /// They follow the palette's rules, so they win at equal specificity,
+    /// and are scoped to the diagram like them.
+    pub fn with_style_rules(mut self, css: impl Into<String>) -> Self {
+        self.style_rules.push_str(&css.into());
+        self
+    }
+
+    /// Emits `width="100%"` with a `max-width` style instead of a fixed size (builder pattern)
+    pub fn responsive(mut self, responsive: bool) -> Self {
+        self.responsive = responsive;
+        self
+    }
+
+    /// Adds a `<title>` with `id`, referenced by `aria-labelledby` (builder pattern)
+    pub fn with_title(mut self, id: impl Into<String>, title: impl Into<String>) -> Self {
+        self.title = Some((id.into(), title.into()));
+        self
+    }
+
+    /// Adds a `<desc>` with `id`, referenced by `aria-describedby` (builder pattern)
+    pub fn with_description(
+        mut self,
+        id: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        self.description = Some((id.into(), description.into()));
+        self
+    }
+
+    /// Names the kind of graphic for assistive technology (builder pattern)
+    ///
+    /// Emitted as `aria-roledescription`, for example `"sequence"`.
+    pub fn with_role_description(mut self, description: impl Into<String>) -> Self {
+        self.role_description = Some(description.into());
+        self
+    }
+
+    /// Adds an element to the document's `<metadata>` (builder pattern)
+    pub fn with_metadata(mut self, metadata: Element) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
     /// Returns a reference to the theme colors
     #[allow(dead_code)]
     pub fn colors(&self) -> &ThemeColors {
-        &self.colors
+        self.palette.colors()
     }
 
     /// Adds an SVG element string to the document
     ///
-    /// Elements are rendered in the order they are added.
+    /// Elements are rendered in the order they are added. Prefer
+    /// [`add`](Self::add), which keeps the element inspectable.
     pub fn add_element(&mut self, element: String) {
-        self.elements.push(element);
+        self.elements.push(Node::Raw(element));
     }
-}
 
-impl fmt::Display for SvgBuilder {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let elements_str = self.elements.join("\n  ");
+    /// Adds a typed element to the document
+    pub fn add(&mut self, element: impl Into<Node>) {
+        self.elements.push(element.into());
+    }
 
-        let background = if self.transparent {
-            String::new()
+    /// Adds several typed elements to the document
+    pub fn extend(&mut self, elements: impl IntoIterator<Item = Element>) {
+        self.elements.extend(elements.into_iter().map(Node::from));
+    }
+
+    /// Adds a reusable element (marker, gradient, ...) to the `<defs>` section
+    pub fn add_definition(&mut self, element: Element) {
+        self.definitions.push(element);
+    }
+
+    /// Builds the document tree
+    ///
+    /// The root `<svg>` holds the title and description, the metadata, the
+    /// stylesheet, the definitions, the background and the elements, wrapped
+    /// in a transform group when the viewport scales. Numbers in geometric
+    /// attributes are written with the builder's [`NumberFormat`].
+    ///
+    /// The stylesheet's rules only apply below the root, which carries the
+    /// class from [`scope_class`].
+    pub fn document(&self) -> Element {
+        let Viewport { width, height, .. } = self.viewport;
+        let css = self.palette.stylesheet().unwrap_or_default() + &self.style_rules;
+        let scope = (!css.is_empty()).then(|| scope_class(&css));
+
+        let mut svg = Element::new("svg").with_attr("xmlns", "http://www.w3.org/2000/svg");
+        if self.palette.is_adaptive() {
+            svg.add_class(ADAPTIVE_CLASS);
+        }
+        if let Some(scope) = &scope {
+            svg.add_class(scope);
+        }
+        if self.responsive {
+            svg.set_attr("width", "100%");
+            svg.set_attr("style", format!("max-width: {}px;", width));
         } else {
-            format!(
-                r#"<rect width="100%" height="100%" fill="{}"/>"#,
-                self.colors.background
-            )
-        };
-
-        if background.is_empty() {
-            write!(
-                f,
-                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">
-  {}
-</svg>"#,
-                self.width, self.height, self.width, self.height, elements_str
-            )
+            svg.set_attr("width", width);
+            svg.set_attr("height", height);
+        }
+        svg.set_attr("viewBox", format!("0 0 {} {}", width, height));
+        svg.set_attr("role", "graphics-document document");
+        if let Some(description) = &self.role_description {
+            svg.set_attr("aria-roledescription", description);
+        }
+
+        for (name, aria, text) in [
+            ("title", "aria-labelledby", &self.title),
+            ("desc", "aria-describedby", &self.description),
+        ] {
+            if let Some((id, text)) = text {
+                svg.set_attr(aria, id);
+                svg.push(
+                    Element::new(name)
+                        .with_attr("id", id)
+                        .with_text(text.as_str()),
+                );
+            }
+        }
+
+        if let Some(metadata) = &self.metadata {
+            svg.push(Element::new("metadata").with_child(metadata.clone()));
+        }
+
+        if let Some(scope) = &scope {
+            let css = scope_stylesheet(&css, &format!(".{}", scope));
+            svg.push(Element::new("style").with_text(format!("\n{}", css)));
+        }
+
+        if !self.definitions.is_empty() {
+            let mut defs = Element::new("defs");
+            defs.extend(self.definitions.iter().cloned());
+            svg.push(defs);
+        }
+
+        if !self.transparent {
+            let background = Element::new("rect")
+                .with_attr("width", "100%")
+                .with_attr("height", "100%");
+            svg.push(painted(
+                background,
+                class::BACKGROUND,
+                Some(self.palette.paint(ColorRole::Background)),
+                None,
+            ));
+        }
+
+        if self.viewport.is_identity() {
+            svg.children_mut().extend(self.elements.iter().cloned());
         } else {
-            write!(
-                f,
-                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">
-  {}
-  {}
-</svg>"#,
-                self.width, self.height, self.width, self.height, background, elements_str
-            )
+            let mut group = Element::new("g").with_attr(
+                "transform",
+                format!(
+                    "translate({} {}) scale({})",
+                    self.number_format.format(self.viewport.offset_x),
+                    self.number_format.format(self.viewport.offset_y),
+                    self.number_format.format_scale(self.viewport.scale)
+                ),
+            );
+            group.children_mut().extend(self.elements.iter().cloned());
+            svg.push(group);
         }
+        self.number_format.apply(&mut svg);
+        svg
+    }
+}
+
+impl fmt::Display for SvgBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.document())
     }
 }
@@ -0,0 +1,416 @@
+//! Typed SVG element tree
+//!
+//! Shapes are built as [`Element`]s, so they can be inspected, restyled or
+//! transformed before the document is serialized. Attributes keep their
+//! insertion order, which keeps the output deterministic.
+//!
+//! Serialization puts each child of a container (`svg`, `g`, `defs`, ...)
+//! on its own line, indented two spaces per level. Elements containing
+//! text are written on one line, so no whitespace leaks into labels.
+//! [`Element::to_compact_string`] writes the whole tree on one line and
+//! [`Element::to_pretty_string`] indents preformatted markup as well.
+
+use std::fmt;
+
+/// A node of an SVG document
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    /// Nested element
+    Element(Element),
+    /// Character data, escaped when serialized
+    Text(String),
+    /// Preformatted markup, written verbatim
+    ///
+    /// Used for elements created through the string API.
+    Raw(String),
+}
+
+impl From<Element> for Node {
+    fn from(element: Element) -> Self {
+        Node::Element(element)
+    }
+}
+
+/// An SVG element with ordered attributes and child nodes
+#[derive(Debug, Clone, PartialEq)]
+pub struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Node>,
+}
+
+impl Element {
+    /// Creates an element without attributes or children
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            attributes: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Returns the tag name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Sets an attribute (builder pattern)
+    pub fn with_attr(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.set_attr(name, value);
+        self
+    }
+
+    /// Sets an attribute, replacing its value in place if already present
+    pub fn set_attr(&mut self, name: impl Into<String>, value: impl ToString) {
+        let name = name.into();
+        let value = value.to_string();
+        match self.attributes.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, existing)) => *existing = value,
+            None => self.attributes.push((name, value)),
+        }
+    }
+
+    /// Returns the value of an attribute
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Removes an attribute, returning its value
+    pub fn remove_attr(&mut self, name: &str) -> Option<String> {
+        let index = self.attributes.iter().position(|(n, _)| n == name)?;
+        Some(self.attributes.remove(index).1)
+    }
+
+    /// Reorders the attributes by a key; equal keys keep their order
+    pub fn sort_attributes_by_key<K: Ord>(&mut self, mut key: impl FnMut(&str) -> K) {
+        self.attributes.sort_by_key(|(name, _)| key(name));
+    }
+
+    /// Returns the attributes in order
+    pub fn attributes(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.attributes
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Returns the classes of the `class` attribute
+    pub fn classes(&self) -> impl Iterator<Item = &str> {
+        self.attr("class").unwrap_or("").split_whitespace()
+    }
+
+    /// Returns `true` if the element has `class`
+    pub fn has_class(&self, class: &str) -> bool {
+        self.classes().any(|c| c == class)
+    }
+
+    /// Appends a class to the `class` attribute unless already present
+    pub fn add_class(&mut self, class: &str) {
+        if class.is_empty() || self.has_class(class) {
+            return;
+        }
+        let classes = match self.attr("class") {
+            Some(existing) if !existing.is_empty() => format!("{} {}", existing, class),
+            _ => class.to_string(),
+        };
+        self.set_attr("class", classes);
+    }
+
+    /// Appends a child (builder pattern)
+    pub fn with_child(mut self, child: impl Into<Node>) -> Self {
+        self.push(child);
+        self
+    }
+
+    /// Appends a text child (builder pattern)
+    pub fn with_text(mut self, text: impl Into<String>) -> Self {
+        self.children.push(Node::Text(text.into()));
+        self
+    }
+
+    /// Appends a child
+    pub fn push(&mut self, child: impl Into<Node>) {
+        self.children.push(child.into());
+    }
+
+    /// Appends several children
+    pub fn extend<N: Into<Node>>(&mut self, children: impl IntoIterator<Item = N>) {
+        self.children.extend(children.into_iter().map(Into::into));
+    }
+
+    /// Returns the child nodes
+    pub fn children(&self) -> &[Node] {
+        &self.children
+    }
+
+    /// Returns the child nodes for modification
+    pub fn children_mut(&mut self) -> &mut Vec<Node> {
+        &mut self.children
+    }
+
+    /// Returns this element and all descendant elements, depth first
+    pub fn descendants(&self) -> Vec<&Element> {
+        let mut elements = vec![self];
+        for child in &self.children {
+            if let Node::Element(element) = child {
+                elements.extend(element.descendants());
+            }
+        }
+        elements
+    }
+
+    /// Calls `f` on this element and every descendant element, depth first
+    ///
+    /// Parents are visited before their children, so `f` may add children
+    /// that are then visited too.
+    pub fn visit_mut(&mut self, f: &mut dyn FnMut(&mut Element)) {
+        f(self);
+        for child in &mut self.children {
+            if let Node::Element(element) = child {
+                element.visit_mut(f);
+            }
+        }
+    }
+
+    /// Serializes the element without whitespace between elements
+    ///
+    /// Text content is kept as is. See [`minify`](super::minify::minify).
+    pub fn to_compact_string(&self) -> String {
+        struct Compact<'a>(&'a Element);
+
+        impl fmt::Display for Compact<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.write(f, 0, true, false)
+            }
+        }
+
+        Compact(self).to_string()
+    }
+
+    /// Serializes the element with preformatted markup indented too
+    ///
+    /// Each line of a [`Node::Raw`] child is indented like an element at
+    /// its position, and the output ends with a newline. See
+    /// [`canonicalize`](super::pretty::canonicalize).
+    pub fn to_pretty_string(&self) -> String {
+        struct Pretty<'a>(&'a Element);
+
+        impl fmt::Display for Pretty<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.write(f, 0, false, true)?;
+                writeln!(f)
+            }
+        }
+
+        Pretty(self).to_string()
+    }
+
+    /// Returns `true` if the children are written on one line
+    ///
+    /// A `<text>` is always inline, since whitespace between its runs
+    /// would be drawn as spaces.
+    fn is_inline(&self) -> bool {
+        self.name == "text"
+            || self
+                .children
+                .iter()
+                .any(|child| matches!(child, Node::Text(_)))
+    }
+
+    fn write(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        depth: usize,
+        inline: bool,
+        indent_raw: bool,
+    ) -> fmt::Result {
+        write!(f, "<{}", self.name)?;
+        for (name, value) in &self.attributes {
+            write!(f, r#" {}="{}""#, name, escape_xml(value))?;
+        }
+        if self.children.is_empty() {
+            return write!(f, "/>");
+        }
+        write!(f, ">")?;
+
+        let inline = inline || self.is_inline();
+        for child in &self.children {
+            if !inline {
+                write!(f, "\n{}", indent(depth + 1))?;
+            }
+            match child {
+                Node::Element(element) => element.write(f, depth + 1, inline, indent_raw)?,
+                Node::Text(text) => write!(f, "{}", escape_xml(text))?,
+                Node::Raw(markup) if indent_raw && !inline => {
+                    let separator = format!("\n{}", indent(depth + 1));
+                    write!(
+                        f,
+                        "{}",
+                        markup
+                            .trim_end()
+                            .lines()
+                            .collect::<Vec<_>>()
+                            .join(&separator)
+                    )?
+                }
+                Node::Raw(markup) => write!(f, "{}", markup)?,
+            }
+        }
+        if !inline {
+            write!(f, "\n{}", indent(depth))?;
+        }
+        write!(f, "</{}>", self.name)
+    }
+}
+
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, 0, false, false)
+    }
+}
+
+/// Transforms a rendered document before it is serialized
+///
+/// Processors registered with
+/// [`RenderOptions::with_post_processor`](crate::RenderOptions::with_post_processor)
+/// run in order on the root `<svg>` element. Closures taking
+/// `&mut Element` implement this trait.
+///
+/// ```rust,ignore
+/// let options = RenderOptions::default().with_post_processor(|svg: &mut Element| {
+///     svg.visit_mut(&mut |element| {
+///         if element.has_class("actor") {
+///             element.set_attr("data-kind", "participant");
+///         }
+///     });
+/// });
+/// ```
+pub trait PostProcessor: Send + Sync {
+    /// Modifies the document in place
+    fn process(&self, document: &mut Element);
+}
+
+impl<F> PostProcessor for F
+where
+    F: Fn(&mut Element) + Send + Sync,
+{
+    fn process(&self, document: &mut Element) {
+        self(document)
+    }
+}
+
+impl fmt::Debug for dyn PostProcessor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PostProcessor")
+    }
+}
+
+/// Joins serialized elements with newlines
+///
+/// This is the format of multi-element shapes in the string API.
+pub fn join_elements(elements: &[Element]) -> String {
+    elements
+        .iter()
+        .map(Element::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn indent(depth: usize) -> String {
+    "  ".repeat(depth)
+}
+
+/// Escape XML special characters
+///
+/// Carriage returns become character references, since XML parsers
+/// normalize literal ones away.
+pub(crate) fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+        .replace('\r', "&#13;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attributes_keep_order_and_escape() {
+        let mut rect = Element::new("rect").with_attr("x", 1.5).with_attr("y", 2);
+        rect.set_attr("x", 3);
+        rect.set_attr("data-label", "a \"b\" & c");
+        assert_eq!(
+            rect.to_string(),
+            r#"<rect x="3" y="2" data-label="a &quot;b&quot; &amp; c"/>"#
+        );
+        assert_eq!(rect.remove_attr("y").as_deref(), Some("2"));
+        assert_eq!(rect.attr("y"), None);
+    }
+
+    #[test]
+    fn test_classes() {
+        let mut line = Element::new("line");
+        line.add_class("messageLine0");
+        line.add_class("hot");
+        line.add_class("hot");
+        assert_eq!(line.attr("class"), Some("messageLine0 hot"));
+        assert!(line.has_class("hot"));
+    }
+
+    #[test]
+    fn test_containers_indent_and_text_stays_inline() {
+        let text = Element::new("text").with_text("a < b ").with_child(
+            Element::new("a")
+                .with_attr("href", "https://example.com")
+                .with_child(Element::new("tspan").with_text("link")),
+        );
+        let group = Element::new("g")
+            .with_child(Element::new("rect"))
+            .with_child(Element::new("g").with_child(text));
+        assert_eq!(
+            group.to_string(),
+            "<g>\n  <rect/>\n  <g>\n    <text>a &lt; b <a href=\"https://example.com\"><tspan>link</tspan></a></text>\n  </g>\n</g>"
+        );
+    }
+
+    #[test]
+    fn test_text_of_styled_runs_stays_inline() {
+        let text = Element::new("text")
+            .with_attr("x", 1)
+            .with_child(
+                Element::new("tspan")
+                    .with_attr("font-weight", "bold")
+                    .with_text("a"),
+            )
+            .with_child(
+                Element::new("tspan")
+                    .with_attr("font-style", "italic")
+                    .with_text("b"),
+            );
+        let group = Element::new("g").with_child(text);
+        let expected = "<text x=\"1\"><tspan font-weight=\"bold\">a</tspan><tspan font-style=\"italic\">b</tspan></text>";
+        assert_eq!(group.to_string(), format!("<g>\n  {}\n</g>", expected));
+        assert_eq!(
+            group.to_pretty_string(),
+            format!("<g>\n  {}\n</g>\n", expected)
+        );
+    }
+
+    #[test]
+    fn test_visit_mut() {
+        let mut group = Element::new("g")
+            .with_child(Element::new("rect"))
+            .with_child(Element::new("line"));
+        group.visit_mut(&mut |element| element.set_attr("data-seen", true));
+        assert_eq!(group.descendants().len(), 3);
+        assert!(group
+            .descendants()
+            .iter()
+            .all(|element| element.attr("data-seen") == Some("true")));
+    }
+}
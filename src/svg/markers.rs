@@ -0,0 +1,242 @@
+//! Arrowheads as reusable `<marker>` definitions
+//!
+//! Instead of drawing a polygon or pair of lines at every arrow end, each
+//! end style is defined once in `<defs>` and referenced from message lines
+//! with `marker-start`/`marker-end`. Markers use `userSpaceOnUse` units, so
+//! heads keep their size when a stylesheet changes the line's stroke width,
+//! and are drawn undashed on dotted lines.
+//!
+//! Marker ids include the arrow colour, so diagrams with different themes
+//! can share a page. Markers painted by a stylesheet class are named after
+//! the colour the class resolves to (see [`Markers::with_class_color`]).
+//! Consumers without marker support (some PDF converters) can use the inline
+//! shapes of [`arrow`](super::arrow) instead.
+
+use super::element::Element;
+use super::shapes::{end_closed, end_cross, end_open, line, loop_path, EndStyle, LineStyle};
+use super::style::Paint;
+
+/// Size of the marker viewport, with room for the stroke around the head
+const MARKER_SIZE: f64 = 16.0;
+
+/// Collects the markers referenced by a diagram
+#[derive(Debug, Clone)]
+pub struct Markers<'a> {
+    prefix: String,
+    class_color: Option<String>,
+    used: Vec<(String, EndStyle, Paint<'a>, f64)>,
+}
+
+impl<'a> Markers<'a> {
+    /// Creates an empty set whose ids start with `prefix`
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            class_color: None,
+            used: Vec::new(),
+        }
+    }
+
+    /// Sets the colour that [`Paint::Class`] resolves to (builder pattern)
+    ///
+    /// It names class-painted markers, so that diagrams whose stylesheets
+    /// colour arrows differently do not share a definition.
+    pub fn with_class_color(mut self, color: impl Into<String>) -> Self {
+        self.class_color = Some(color.into());
+        self
+    }
+
+    /// Returns a `url(#id)` reference to the marker of an end style
+    ///
+    /// Returns `None` for [`EndStyle::None`].
+    pub fn reference(&mut self, style: EndStyle, stroke: Paint<'a>, width: f64) -> Option<String> {
+        let id = self.id(style, stroke, width)?;
+        let reference = format!("url(#{})", id);
+        if !self.used.iter().any(|(used, ..)| *used == id) {
+            self.used.push((id, style, stroke, width));
+        }
+        Some(reference)
+    }
+
+    /// Returns `true` if no marker was referenced
+    pub fn is_empty(&self) -> bool {
+        self.used.is_empty()
+    }
+
+    /// Returns the `<marker>` elements of the referenced markers
+    ///
+    /// Markers are defined in order of first use; add them to the document
+    /// with [`SvgBuilder::add_definition`](super::SvgBuilder::add_definition).
+    pub fn definitions(&self) -> Vec<Element> {
+        let mut definitions = Vec::new();
+        for (id, style, stroke, width) in &self.used {
+            let (style, stroke, width) = (*style, *stroke, *width);
+            let (ref_x, shapes) = match style {
+                EndStyle::None => continue,
+                EndStyle::Closed => (14.0, vec![end_closed(14.0, 8.0, 0.0, stroke)]),
+                EndStyle::Open => (14.0, end_open(14.0, 8.0, 0.0, stroke, width)),
+                EndStyle::Cross => (8.0, end_cross(8.0, 8.0, stroke, width)),
+            };
+            let mut marker = Element::new("marker")
+                .with_attr("id", id)
+                .with_attr("markerWidth", MARKER_SIZE)
+                .with_attr("markerHeight", MARKER_SIZE)
+                .with_attr("refX", ref_x)
+                .with_attr("refY", MARKER_SIZE / 2.0)
+                .with_attr("markerUnits", "userSpaceOnUse")
+                .with_attr("orient", "auto-start-reverse");
+            marker.extend(shapes);
+            definitions.push(marker);
+        }
+        definitions
+    }
+
+    /// Id of the marker for an end style, colour and stroke width
+    fn id(&self, style: EndStyle, stroke: Paint, width: f64) -> Option<String> {
+        let kind = match style {
+            EndStyle::None => return None,
+            EndStyle::Closed => "arrowhead",
+            EndStyle::Open => "openhead",
+            EndStyle::Cross => "crosshead",
+        };
+        let colour = match stroke {
+            Paint::Color(color) => color_slug(color),
+            Paint::Role(role) => role.name().to_string(),
+            Paint::Class => match &self.class_color {
+                Some(color) => format!("styled-{}", color_slug(color)),
+                None => "styled".to_string(),
+            },
+        };
+        let mut id = format!("{}{}-{}", self.prefix, kind, colour);
+        // Only line-based heads depend on the stroke width
+        if style != EndStyle::Closed && width != 1.0 {
+            id.push_str(&format!("-w{}", width.to_string().replace('.', "_")));
+        }
+        Some(id)
+    }
+}
+
+/// Lowercases a colour's letters and digits and joins the runs between them
+/// with `-` (`rgb(0, 0, 0)` to `rgb-0-0-0`)
+fn color_slug(color: &str) -> String {
+    color
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Create an arrow whose ends reference shared markers
+///
+/// Same geometry as [`arrow`](super::arrow), as a single line.
+#[allow(clippy::too_many_arguments)]
+pub fn marker_arrow<'a>(
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+    stroke: Paint<'a>,
+    width: f64,
+    line_style: LineStyle,
+    start_end: EndStyle,
+    end_end: EndStyle,
+    class: &str,
+    markers: &mut Markers<'a>,
+) -> Element {
+    let mut line = line(x1, y1, x2, y2, stroke, width, line_style, class);
+    if let Some(reference) = markers.reference(start_end, stroke, width) {
+        line.set_attr("marker-start", reference);
+    }
+    if let Some(reference) = markers.reference(end_end, stroke, width) {
+        line.set_attr("marker-end", reference);
+    }
+    line
+}
+
+/// Create a self-message loop whose arrowhead references a shared marker
+///
+/// Same curve as [`self_loop`](super::self_loop).
+#[allow(clippy::too_many_arguments)]
+pub fn marker_self_loop<'a>(
+    x: f64,
+    y: f64,
+    loop_width: f64,
+    stroke: Paint<'a>,
+    width: f64,
+    line_style: LineStyle,
+    class: &str,
+    markers: &mut Markers<'a>,
+) -> Element {
+    let mut path = loop_path(x, y, loop_width, stroke, width, line_style, class);
+    if let Some(reference) = markers.reference(EndStyle::Closed, stroke, width) {
+        path.set_attr("marker-end", reference);
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markers_are_defined_once() {
+        let mut markers = Markers::new("marker-");
+        let stroke = Paint::Color("#333333");
+        let first = marker_arrow(
+            0.0,
+            0.0,
+            100.0,
+            0.0,
+            stroke,
+            1.0,
+            LineStyle::Solid,
+            EndStyle::Closed,
+            EndStyle::Closed,
+            "messageLine0",
+            &mut markers,
+        );
+        marker_self_loop(
+            0.0,
+            0.0,
+            40.0,
+            stroke,
+            1.0,
+            LineStyle::Dotted,
+            "",
+            &mut markers,
+        );
+
+        assert_eq!(
+            first.attr("marker-start"),
+            Some("url(#marker-arrowhead-333333)")
+        );
+        assert_eq!(first.attr("marker-end"), first.attr("marker-start"));
+
+        let definitions = markers.definitions();
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(definitions[0].attr("orient"), Some("auto-start-reverse"));
+        assert_eq!(definitions[0].children().len(), 1);
+    }
+
+    #[test]
+    fn test_marker_ids_depend_on_colour_and_width() {
+        let mut markers = Markers::new("");
+        assert_eq!(
+            markers.reference(EndStyle::Cross, Paint::Color("rgb(0, 0, 0)"), 1.5),
+            Some("url(#crosshead-rgb-0-0-0-w1_5)".to_string())
+        );
+        assert_eq!(markers.reference(EndStyle::None, Paint::Class, 1.0), None);
+        assert_eq!(
+            markers.reference(EndStyle::Open, Paint::Class, 1.0),
+            Some("url(#openhead-styled)".to_string())
+        );
+        assert_eq!(markers.definitions().len(), 2);
+
+        let mut styled = Markers::new("").with_class_color("#333333");
+        assert_eq!(
+            styled.reference(EndStyle::Closed, Paint::Class, 1.0),
+            Some("url(#arrowhead-styled-333333)".to_string())
+        );
+    }
+}
@@ -0,0 +1,422 @@
+//! SVG minification
+//!
+//! [`minify`] rewrites a rendered document into a smaller one that renders
+//! the same:
+//!
+//! - presentation attributes repeated on several elements (`fill`, `stroke`,
+//!   `font-family`, ...) move into a shared class rule,
+//! - runs of identical sibling `<line>`s become one `<path>`,
+//! - leading zeros are dropped from numbers (`0.5` becomes `.5`) and the
+//!   spaces around path commands from `d`,
+//! - the stylesheet loses its whitespace.
+//!
+//! Serialize the result with [`Element::to_compact_string`] to drop the
+//! whitespace between elements as well.
+//!
+//! Shared classes are named after a hash of their declarations, so
+//! diagrams inlined in the same page never disagree about a class.
+//! Their rules come first in the stylesheet with single-class selectors;
+//! theme and `classDef` rules still override them as they overrode the
+//! attributes.
+
+use std::collections::BTreeMap;
+
+use super::element::{Element, Node};
+use super::number::{shorten_numbers, NUMERIC_ATTRIBUTES};
+use super::style::fnv1a;
+
+/// Presentation attributes that may move into a shared class
+const SHAREABLE_ATTRIBUTES: &[&str] = &[
+    "fill",
+    "stroke",
+    "stroke-width",
+    "font-family",
+    "font-size",
+    "font-weight",
+    "font-style",
+    "text-anchor",
+];
+
+/// Properties that need a unit in CSS but not as attributes
+const LENGTH_PROPERTIES: &[&str] = &["stroke-width", "font-size"];
+
+/// Elements whose content is not graphics
+const NON_GRAPHIC_ELEMENTS: &[&str] = &["svg", "style", "metadata", "title", "desc"];
+
+/// Minifies a document in place
+pub fn minify(svg: &mut Element) {
+    let css = stylesheet(svg).unwrap_or_default();
+    if !targets_element(&css, "line") {
+        svg.visit_mut(&mut merge_lines);
+    }
+
+    let rules = share_classes(svg);
+    let css = minify_css(&(rules + &css));
+    set_stylesheet(svg, css);
+
+    svg.visit_mut(&mut |element| {
+        for &name in NUMERIC_ATTRIBUTES {
+            if let Some(value) = element.attr(name) {
+                let mut shortened = shorten_numbers(value);
+                if name == "d" {
+                    shortened = compact_path(&shortened);
+                }
+                if shortened != value {
+                    element.set_attr(name, shortened);
+                }
+            }
+        }
+    });
+}
+
+/// Removes the spaces around path commands (`M 10 20 Q` to `M10 20Q`)
+fn compact_path(d: &str) -> String {
+    let mut compact = String::with_capacity(d.len());
+    let mut chars = d.trim().chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == ' ' {
+            while chars.peek() == Some(&' ') {
+                chars.next();
+            }
+            let next = chars.peek().copied();
+            let after_command = compact.ends_with(|c: char| c.is_ascii_alphabetic());
+            if after_command || next.map_or(true, |c| c.is_ascii_alphabetic()) {
+                continue;
+            }
+        }
+        compact.push(c);
+    }
+    compact
+}
+
+/// Replaces runs of two or more mergeable `<line>` children with a `<path>`
+fn merge_lines(element: &mut Element) {
+    let children = std::mem::take(element.children_mut());
+    let mut merged: Vec<Node> = Vec::with_capacity(children.len());
+    let mut run: Vec<Element> = Vec::new();
+
+    for child in children {
+        match child {
+            Node::Element(line) if is_mergeable(&line) => {
+                if run
+                    .first()
+                    .is_some_and(|first| style_attributes(first) != style_attributes(&line))
+                {
+                    merged.push(lines_to_path(std::mem::take(&mut run)));
+                }
+                run.push(line);
+            }
+            other => {
+                if !run.is_empty() {
+                    merged.push(lines_to_path(std::mem::take(&mut run)));
+                }
+                merged.push(other);
+            }
+        }
+    }
+    if !run.is_empty() {
+        merged.push(lines_to_path(run));
+    }
+    *element.children_mut() = merged;
+}
+
+/// A plain line: no id, markers or children
+fn is_mergeable(element: &Element) -> bool {
+    element.name() == "line"
+        && element.children().is_empty()
+        && element.attr("id").is_none()
+        && ["x1", "y1", "x2", "y2"]
+            .iter()
+            .all(|name| element.attr(name).is_some())
+        && !element
+            .attributes()
+            .any(|(name, _)| name.starts_with("marker"))
+}
+
+/// The attributes of a line other than its coordinates
+fn style_attributes(line: &Element) -> Vec<(&str, &str)> {
+    line.attributes()
+        .filter(|(name, _)| !matches!(*name, "x1" | "y1" | "x2" | "y2"))
+        .collect()
+}
+
+/// Turns a run of lines into one path, or returns a single line unchanged
+fn lines_to_path(mut lines: Vec<Element>) -> Node {
+    if lines.len() == 1 {
+        return lines.remove(0).into();
+    }
+    let d: String = lines
+        .iter()
+        .map(|line| {
+            let coord = |name| line.attr(name).unwrap_or("0");
+            format!(
+                "M{} {}L{} {}",
+                coord("x1"),
+                coord("y1"),
+                coord("x2"),
+                coord("y2")
+            )
+        })
+        .collect();
+    let mut path = Element::new("path").with_attr("d", d);
+    for (name, value) in style_attributes(&lines[0]) {
+        path.set_attr(name, value);
+    }
+    path.into()
+}
+
+/// Moves repeated presentation attributes into classes and returns their
+/// rules
+fn share_classes(svg: &mut Element) -> String {
+    // Total bytes saved by each set of declarations
+    let mut savings: BTreeMap<String, isize> = BTreeMap::new();
+    for element in svg.descendants() {
+        if let Some(declarations) = declarations(element) {
+            let name = class_name(&declarations);
+            *savings.entry(declarations).or_default() += saving(element, &name);
+        }
+    }
+    let shared: BTreeMap<String, String> = savings
+        .into_iter()
+        .filter(|(declarations, saving)| {
+            *saving > (class_name(declarations).len() + declarations.len() + 3) as isize
+        })
+        .map(|(declarations, _)| {
+            let name = class_name(&declarations);
+            (declarations, name)
+        })
+        .collect();
+
+    svg.visit_mut(&mut |element| {
+        let Some(name) = declarations(element).and_then(|d| shared.get(&d)) else {
+            return;
+        };
+        for &attribute in SHAREABLE_ATTRIBUTES {
+            element.remove_attr(attribute);
+        }
+        element.add_class(name);
+    });
+
+    shared
+        .iter()
+        .map(|(declarations, name)| format!(".{}{{{}}}", name, declarations))
+        .collect()
+}
+
+/// The shareable attributes of a graphic element as CSS declarations
+fn declarations(element: &Element) -> Option<String> {
+    if NON_GRAPHIC_ELEMENTS.contains(&element.name()) || element.name().contains(':') {
+        return None;
+    }
+    let mut declarations = Vec::new();
+    for (name, value) in element.attributes() {
+        if !SHAREABLE_ATTRIBUTES.contains(&name) {
+            continue;
+        }
+        if value.contains([';', '{', '}', '\\', '<']) || value.contains("/*") {
+            return None;
+        }
+        let unit = if LENGTH_PROPERTIES.contains(&name) && value.parse::<f64>().is_ok() {
+            "px"
+        } else {
+            ""
+        };
+        declarations.push(format!("{}:{}{}", name, value, unit));
+    }
+    (!declarations.is_empty()).then(|| declarations.join(";"))
+}
+
+/// Bytes saved by replacing the shareable attributes of `element` with a
+/// class
+fn saving(element: &Element, class: &str) -> isize {
+    let attributes: usize = element
+        .attributes()
+        .filter(|(name, _)| SHAREABLE_ATTRIBUTES.contains(name))
+        .map(|(name, value)| name.len() + value.len() + 4)
+        .sum();
+    let class_cost = match element.attr("class") {
+        Some(_) => class.len() + 1,
+        None => class.len() + 9,
+    };
+    attributes as isize - class_cost as isize
+}
+
+/// Class named after an FNV-1a hash of the declarations
+fn class_name(declarations: &str) -> String {
+    format!("msv-{:08x}", fnv1a(declarations))
+}
+
+/// Returns the text of the document's `<style>` element
+fn stylesheet(svg: &Element) -> Option<String> {
+    let style = svg.children().iter().find_map(|child| match child {
+        Node::Element(element) if element.name() == "style" => Some(element),
+        _ => None,
+    })?;
+    Some(
+        style
+            .children()
+            .iter()
+            .filter_map(|child| match child {
+                Node::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect(),
+    )
+}
+
+/// Replaces the document's stylesheet, adding a `<style>` element after the
+/// title, description and metadata if there was none
+fn set_stylesheet(svg: &mut Element, css: String) {
+    let children = svg.children_mut();
+    let existing = children
+        .iter()
+        .position(|child| matches!(child, Node::Element(e) if e.name() == "style"));
+    match existing {
+        Some(index) => children[index] = Element::new("style").with_text(css).into(),
+        None if css.is_empty() => {}
+        None => {
+            let index = children
+                .iter()
+                .position(|child| {
+                    !matches!(child, Node::Element(e) if ["title", "desc", "metadata"].contains(&e.name()))
+                })
+                .unwrap_or(children.len());
+            children.insert(index, Element::new("style").with_text(css).into());
+        }
+    }
+}
+
+/// Returns `true` if a selector in `css` names the element type `name`
+fn targets_element(css: &str, name: &str) -> bool {
+    css.match_indices(name).any(|(start, _)| {
+        let before = css[..start].chars().next_back();
+        let after = css[start + name.len()..].chars().next();
+        !before.is_some_and(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '#'))
+            && after.map_or(true, |c| {
+                matches!(c, '.' | ' ' | ',' | '{' | ':' | '[' | '>')
+            })
+    })
+}
+
+/// Removes insignificant whitespace and final semicolons from CSS
+fn minify_css(css: &str) -> String {
+    let mut minified = String::with_capacity(css.len());
+    let mut quote = None;
+    let mut pending_space = false;
+    for c in css.chars() {
+        if let Some(q) = quote {
+            minified.push(c);
+            if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        if c.is_whitespace() {
+            pending_space = true;
+            continue;
+        }
+        let tight = |c: char| matches!(c, '{' | '}' | ';' | ':' | ',');
+        if pending_space && !minified.is_empty() && !tight(c) && !minified.ends_with(tight) {
+            minified.push(' ');
+        }
+        pending_space = false;
+        if c == '}' && minified.ends_with(';') {
+            minified.pop();
+        }
+        if c == '"' || c == '\'' {
+            quote = Some(c);
+        }
+        minified.push(c);
+    }
+    minified
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(x1: u32, y1: u32, x2: u32, y2: u32) -> Element {
+        Element::new("line")
+            .with_attr("x1", x1)
+            .with_attr("y1", y1)
+            .with_attr("x2", x2)
+            .with_attr("y2", y2)
+            .with_attr("stroke", "#333")
+    }
+
+    #[test]
+    fn test_merges_adjacent_lines() {
+        let mut group = Element::new("g")
+            .with_child(line(2, 2, 14, 14))
+            .with_child(line(2, 14, 14, 2))
+            .with_child(line(0, 0, 1, 1).with_attr("stroke", "red"))
+            .with_child(line(0, 0, 1, 1).with_attr("marker-end", "url(#m)"));
+        merge_lines(&mut group);
+        assert_eq!(
+            group.to_compact_string(),
+            "<g><path d=\"M2 2L14 14M2 14L14 2\" stroke=\"#333\"/>\
+             <line x1=\"0\" y1=\"0\" x2=\"1\" y2=\"1\" stroke=\"red\"/>\
+             <line x1=\"0\" y1=\"0\" x2=\"1\" y2=\"1\" stroke=\"#333\" marker-end=\"url(#m)\"/></g>"
+        );
+    }
+
+    #[test]
+    fn test_shares_repeated_attributes() {
+        let text = |y: u32| {
+            Element::new("text")
+                .with_attr("y", y)
+                .with_attr("fill", "#333333")
+                .with_attr("class", "actor")
+                .with_attr("font-size", 14)
+                .with_attr("font-family", "Arial, sans-serif")
+                .with_text("A")
+        };
+        let mut svg = Element::new("svg")
+            .with_child(Element::new("style").with_text("\n.hot { fill: red; }\n"))
+            .with_child(text(1))
+            .with_child(text(2))
+            .with_child(text(3))
+            .with_child(Element::new("rect").with_attr("fill", "#fff"));
+        minify(&mut svg);
+
+        let declarations = "fill:#333333;font-size:14px;font-family:Arial,sans-serif";
+        let class = class_name("fill:#333333;font-size:14px;font-family:Arial, sans-serif");
+        assert_eq!(
+            svg.to_compact_string(),
+            format!(
+                "<svg><style>.{class}{{{declarations}}}.hot{{fill:red}}</style>\
+                 <text y=\"1\" class=\"actor {class}\">A</text>\
+                 <text y=\"2\" class=\"actor {class}\">A</text>\
+                 <text y=\"3\" class=\"actor {class}\">A</text>\
+                 <rect fill=\"#fff\"/></svg>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_compact_path() {
+        assert_eq!(
+            compact_path("M 250 260 Q 290 260 290 275 Q 290 290 250 290"),
+            "M250 260Q290 260 290 275Q290 290 250 290"
+        );
+        assert_eq!(compact_path("M2 2L14 14"), "M2 2L14 14");
+    }
+
+    #[test]
+    fn test_lines_kept_when_styled_by_type() {
+        assert!(targets_element(
+            "line.arrowhead, .crosshead { stroke: red; }",
+            "line"
+        ));
+        assert!(!targets_element(".actor-line { stroke: red; }", "line"));
+        assert!(!targets_element(".messageLine0 { stroke: red; }", "line"));
+    }
+
+    #[test]
+    fn test_minify_css() {
+        assert_eq!(
+            minify_css("\n@media (prefers-color-scheme: dark) { svg.a .b { fill: #fff; } }\ntext.x { font-family: \"trebuchet ms\", verdana; }\n"),
+            "@media (prefers-color-scheme:dark){svg.a .b{fill:#fff}}text.x{font-family:\"trebuchet ms\",verdana}"
+        );
+    }
+}
@@ -1,7 +1,17 @@
 //! SVG generation utilities
 
 pub mod builder;
+pub mod element;
+pub mod markers;
+pub mod minify;
+pub mod number;
+pub mod pretty;
 pub mod shapes;
+pub mod style;
 
 pub use builder::SvgBuilder;
+pub use element::{Element, Node, PostProcessor};
+pub use markers::{marker_arrow, marker_self_loop, Markers};
+pub use number::NumberFormat;
 pub use shapes::*;
+pub use style::{class, Paint, Palette};
@@ -0,0 +1,246 @@
+//! Number formatting policy
+//!
+//! Coordinates come out of layout arithmetic with as many digits as `f64`
+//! needs (`93.33333333333333`), and trigonometry may differ in the last bit
+//! between platforms. Every number written to a geometric attribute is
+//! therefore rounded to a fixed number of decimals, trailing zeros are
+//! trimmed and negative zero is written as `0`. Identical input and options
+//! then produce byte-identical SVG everywhere.
+
+use super::element::Element;
+
+/// Attributes whose numbers are formatted by the policy
+///
+/// Other attributes (ids, classes, URLs, text) are left untouched, and so is
+/// `transform`: its scale factor multiplies every coordinate, so it is
+/// written with [`NumberFormat::format_scale`] where the transform is built.
+pub const NUMERIC_ATTRIBUTES: &[&str] = &[
+    "x",
+    "y",
+    "x1",
+    "y1",
+    "x2",
+    "y2",
+    "cx",
+    "cy",
+    "r",
+    "rx",
+    "ry",
+    "width",
+    "height",
+    "points",
+    "d",
+    "viewBox",
+    "stroke-width",
+    "stroke-dasharray",
+    "font-size",
+    "refX",
+    "refY",
+    "markerWidth",
+    "markerHeight",
+];
+
+/// Largest supported number of decimals
+pub const MAX_PRECISION: usize = 6;
+
+/// Rounds and prints numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    precision: usize,
+}
+
+impl Default for NumberFormat {
+    /// Two decimals, enough for sub-pixel positioning
+    fn default() -> Self {
+        Self { precision: 2 }
+    }
+}
+
+impl NumberFormat {
+    /// Creates a format with `precision` decimals, at most [`MAX_PRECISION`]
+    pub fn new(precision: usize) -> Self {
+        Self {
+            precision: precision.min(MAX_PRECISION),
+        }
+    }
+
+    /// Returns the number of decimals
+    pub fn precision(&self) -> usize {
+        self.precision
+    }
+
+    /// Formats a number
+    ///
+    /// Non-finite values are written as `0`.
+    pub fn format(&self, value: f64) -> String {
+        if !value.is_finite() {
+            return "0".to_string();
+        }
+        let mut text = format!("{:.*}", self.precision, value);
+        if text.contains('.') {
+            text.truncate(text.trim_end_matches('0').trim_end_matches('.').len());
+        }
+        if text == "-0" {
+            text.remove(0);
+        }
+        text
+    }
+
+    /// Formats a scale factor with [`MAX_PRECISION`] decimals
+    ///
+    /// Rounding a scale to two decimals would shift content far from the
+    /// origin by several pixels.
+    pub fn format_scale(&self, value: f64) -> String {
+        Self::new(MAX_PRECISION).format(value)
+    }
+
+    /// Reformats every number in an attribute value
+    ///
+    /// Separators and units (`,`, spaces, `%`, `px`, path commands) are kept.
+    pub fn format_numbers(&self, value: &str) -> String {
+        let mut formatted = String::with_capacity(value.len());
+        let mut rest = value;
+        while let Some(start) = rest.find(|c: char| c.is_ascii_digit() || c == '-' || c == '.') {
+            formatted.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let len = number_len(rest);
+            match rest[..len].parse::<f64>() {
+                Ok(number) if len > 0 => formatted.push_str(&self.format(number)),
+                _ => formatted.push_str(&rest[..len.max(1)]),
+            }
+            rest = &rest[len.max(1)..];
+        }
+        formatted.push_str(rest);
+        formatted
+    }
+
+    /// Applies the policy to the numeric attributes of an element and its
+    /// descendants
+    pub fn apply(&self, element: &mut Element) {
+        element.visit_mut(&mut |element| {
+            for &name in NUMERIC_ATTRIBUTES {
+                if let Some(value) = element.attr(name) {
+                    let formatted = self.format_numbers(value);
+                    if formatted != value {
+                        element.set_attr(name, formatted);
+                    }
+                }
+            }
+        });
+    }
+
+    /// Applies the policy and serializes an element
+    pub fn serialize(&self, mut element: Element) -> String {
+        self.apply(&mut element);
+        element.to_string()
+    }
+}
+
+/// Drops the leading zero of fractions (`0.5` to `.5`, `-0.5` to `-.5`)
+pub fn shorten_numbers(value: &str) -> String {
+    let mut shortened = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_digit() || c == '-' || c == '.') {
+        shortened.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let len = number_len(rest).max(1);
+        let number = &rest[..len];
+        match number.strip_prefix("-0.") {
+            Some(fraction) => shortened.push_str(&format!("-.{}", fraction)),
+            None => shortened.push_str(
+                number
+                    .strip_prefix('0')
+                    .filter(|n| n.starts_with('.'))
+                    .unwrap_or(number),
+            ),
+        }
+        rest = &rest[len..];
+    }
+    shortened.push_str(rest);
+    shortened
+}
+
+/// Length of the decimal number (`-12.5`, `3`, `.5`) at the start of `text`
+fn number_len(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let mut len = usize::from(bytes.first() == Some(&b'-'));
+    let digits_start = len;
+    while len < bytes.len() && bytes[len].is_ascii_digit() {
+        len += 1;
+    }
+    if len < bytes.len() && bytes[len] == b'.' {
+        let fraction = len + 1;
+        let mut end = fraction;
+        while end < bytes.len() && bytes[end].is_ascii_digit() {
+            end += 1;
+        }
+        if end > fraction {
+            len = end;
+        }
+    }
+    if len == digits_start {
+        0
+    } else {
+        len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let format = NumberFormat::default();
+        assert_eq!(format.format(93.33333333333333), "93.33");
+        assert_eq!(format.format(12.5), "12.5");
+        assert_eq!(format.format(100.0), "100");
+        assert_eq!(format.format(0.1 + 0.2), "0.3");
+        assert_eq!(format.format(-0.001), "0");
+        assert_eq!(format.format(-0.0), "0");
+        assert_eq!(format.format(-4.999), "-5");
+        assert_eq!(format.format(f64::NAN), "0");
+
+        assert_eq!(NumberFormat::new(0).format(2.4), "2");
+        assert_eq!(NumberFormat::new(4).format(1.0 / 3.0), "0.3333");
+        assert_eq!(NumberFormat::new(99).precision(), MAX_PRECISION);
+        assert_eq!(format.format_scale(1.0 / 3.0), "0.333333");
+    }
+
+    #[test]
+    fn test_format_numbers() {
+        let format = NumberFormat::default();
+        assert_eq!(
+            format.format_numbers("14,8 5.224174381096272,12.79425538604203"),
+            "14,8 5.22,12.79"
+        );
+        assert_eq!(
+            format.format_numbers("M 78 122 Q 118.5 122 118.5 137.00000001"),
+            "M 78 122 Q 118.5 122 118.5 137"
+        );
+        assert_eq!(
+            format.format_numbers("translate(-0.0001 20) scale(0.6666666)"),
+            "translate(0 20) scale(0.67)"
+        );
+        assert_eq!(format.format_numbers("100%"), "100%");
+        assert_eq!(format.format_numbers("- ."), "- .");
+    }
+
+    #[test]
+    fn test_shorten_numbers() {
+        assert_eq!(shorten_numbers("M0.5 -0.25L10.05 0"), "M.5 -.25L10.05 0");
+        assert_eq!(shorten_numbers("0,0.75 100%"), "0,.75 100%");
+    }
+
+    #[test]
+    fn test_apply_skips_other_attributes() {
+        let mut group = Element::new("g")
+            .with_attr("id", "message-1.123")
+            .with_child(Element::new("line").with_attr("x1", 1.0 / 3.0));
+        NumberFormat::default().apply(&mut group);
+        assert_eq!(
+            group.to_string(),
+            "<g id=\"message-1.123\">\n  <line x1=\"0.33\"/>\n</g>"
+        );
+    }
+}
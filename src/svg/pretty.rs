@@ -0,0 +1,175 @@
+//! Canonical pretty-printing
+//!
+//! [`canonicalize`] puts a rendered document into one canonical form, so
+//! that committed diagrams diff well in review:
+//!
+//! - attributes follow one order on every element: identity (`id`,
+//!   `class`), geometry, paint, markers, then `data-*` and anything else
+//!   alphabetically,
+//! - `<defs>` entries are sorted by id, so a new marker does not move the
+//!   others,
+//! - the stylesheet has one indented rule per line.
+//!
+//! [`Element::to_pretty_string`] then writes one element per line, indenting
+//! preformatted markup from the string API like any other child.
+
+use super::element::{Element, Node};
+
+/// Canonical attribute order; unlisted attributes follow alphabetically
+pub const ATTRIBUTE_ORDER: &[&str] = &[
+    "xmlns",
+    "xmlns:msv",
+    "version",
+    "id",
+    "class",
+    "role",
+    "aria-roledescription",
+    "aria-labelledby",
+    "aria-describedby",
+    "href",
+    "x",
+    "y",
+    "width",
+    "height",
+    "x1",
+    "y1",
+    "x2",
+    "y2",
+    "cx",
+    "cy",
+    "r",
+    "rx",
+    "ry",
+    "viewBox",
+    "points",
+    "d",
+    "transform",
+    "markerWidth",
+    "markerHeight",
+    "refX",
+    "refY",
+    "markerUnits",
+    "orient",
+    "fill",
+    "stroke",
+    "stroke-width",
+    "stroke-dasharray",
+    "font-family",
+    "font-size",
+    "font-weight",
+    "font-style",
+    "text-anchor",
+    "dominant-baseline",
+    "marker-start",
+    "marker-end",
+    "style",
+];
+
+/// Puts a document into canonical form in place
+pub fn canonicalize(svg: &mut Element) {
+    canonicalize_at(svg, 0);
+}
+
+fn canonicalize_at(element: &mut Element, depth: usize) {
+    element.sort_attributes_by_key(attribute_rank);
+    match element.name() {
+        "defs" => element
+            .children_mut()
+            .sort_by(|a, b| definition_id(a).cmp(definition_id(b))),
+        "style" => indent_stylesheet(element, depth),
+        _ => {}
+    }
+    for child in element.children_mut() {
+        if let Node::Element(child) = child {
+            canonicalize_at(child, depth + 1);
+        }
+    }
+}
+
+/// Sort key placing listed attributes first, then `data-*`, then the rest
+fn attribute_rank(name: &str) -> (usize, String) {
+    let rank = match ATTRIBUTE_ORDER.iter().position(|&listed| listed == name) {
+        Some(position) => position,
+        None if name.starts_with("data-") => ATTRIBUTE_ORDER.len(),
+        None => ATTRIBUTE_ORDER.len() + 1,
+    };
+    (rank, name.to_string())
+}
+
+fn definition_id(node: &Node) -> &str {
+    match node {
+        Node::Element(element) => element.attr("id").unwrap_or_default(),
+        _ => "",
+    }
+}
+
+/// Rewrites the stylesheet with one rule per line, indented below `<style>`
+fn indent_stylesheet(style: &mut Element, depth: usize) {
+    let css: String = style
+        .children()
+        .iter()
+        .filter_map(|child| match child {
+            Node::Text(text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    let rules: Vec<&str> = css
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    if rules.is_empty() {
+        return;
+    }
+    let inner = "  ".repeat(depth + 1);
+    let text = format!(
+        "\n{}{}\n{}",
+        inner,
+        rules.join(&format!("\n{}", inner)),
+        "  ".repeat(depth)
+    );
+    *style.children_mut() = vec![Node::Text(text)];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attribute_order() {
+        let mut text = Element::new("text")
+            .with_attr("data-z", 1)
+            .with_attr("font-size", 14)
+            .with_attr("zoom", 1)
+            .with_attr("fill", "#333")
+            .with_attr("class", "actor")
+            .with_attr("data-a", 2)
+            .with_attr("y", 2)
+            .with_attr("x", 1)
+            .with_text("A");
+        canonicalize(&mut text);
+        assert_eq!(
+            text.to_string(),
+            r##"<text class="actor" x="1" y="2" fill="#333" font-size="14" data-a="2" data-z="1" zoom="1">A</text>"##
+        );
+    }
+
+    #[test]
+    fn test_definitions_sorted_and_stylesheet_indented() {
+        let mut svg = Element::new("svg")
+            .with_child(
+                Element::new("style").with_text("\n.b { fill: red; }\n\n.a { fill: blue; }\n"),
+            )
+            .with_child(
+                Element::new("defs")
+                    .with_child(Element::new("marker").with_attr("id", "m-open"))
+                    .with_child(Element::new("marker").with_attr("id", "m-closed")),
+            )
+            .with_child(Node::Raw("<line/>\n<line/>\n".to_string()));
+        canonicalize(&mut svg);
+        assert_eq!(
+            svg.to_pretty_string(),
+            "<svg>\n  <style>\n    .b { fill: red; }\n    .a { fill: blue; }\n  </style>\n  <defs>\n    <marker id=\"m-closed\"/>\n    <marker id=\"m-open\"/>\n  </defs>\n  <line/>\n  <line/>\n</svg>\n"
+        );
+    }
+}
@@ -1,4 +1,16 @@
 //! SVG shape primitives with composable arrow rendering
+//!
+//! Shapes are built as typed [`Element`]s. The string functions at the end
+//! of this module (`create_*`, `draw_*`) serialize the same shapes, with
+//! numbers written by the default [`NumberFormat`], and are kept for
+//! compatibility.
+
+use crate::layout::{BaselineShift, TextLine, TextRun, CODE_FONT_FAMILY};
+use crate::options::Font;
+
+use super::element::{join_elements, Element, Node};
+use super::number::NumberFormat;
+use super::style::{class, painted, Paint};
 
 /// Line style for arrows
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -26,36 +38,69 @@ pub enum EndStyle {
 // Core Composable Functions
 // =============================================================================
 
-/// Create a line segment with specified style
-pub fn create_line(x1: f64, y1: f64, x2: f64, y2: f64, stroke: &str, style: LineStyle) -> String {
-    let dash = match style {
-        LineStyle::Dotted => r#" stroke-dasharray="5,5""#,
-        LineStyle::Solid => "",
-    };
-    format!(
-        r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="1"{}/>"#,
-        x1, y1, x2, y2, stroke, dash
-    )
+/// Serializes a shape with the default number format
+fn serialize(element: Element) -> String {
+    NumberFormat::default().serialize(element)
+}
+
+/// Serializes a multi-element shape with the default number format
+fn serialize_all(mut elements: Vec<Element>) -> String {
+    let format = NumberFormat::default();
+    for element in &mut elements {
+        format.apply(element);
+    }
+    join_elements(&elements)
+}
+
+/// Create a line segment with specified style and semantic class
+#[allow(clippy::too_many_arguments)]
+pub fn line(
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+    stroke: Paint,
+    width: f64,
+    style: LineStyle,
+    class: &str,
+) -> Element {
+    let line = Element::new("line")
+        .with_attr("x1", x1)
+        .with_attr("y1", y1)
+        .with_attr("x2", x2)
+        .with_attr("y2", y2);
+    let line = painted(line, class, None, Some(stroke)).with_attr("stroke-width", width);
+    with_dash(line, style)
 }
 
 /// Create an arrow end/head at a specific point with direction
 ///
+/// Arrowheads get the `arrowhead` class and crosses `crosshead`.
+///
 /// # Arguments
 /// * `x`, `y` - The tip position of the arrowhead
 /// * `angle` - Direction the arrow is pointing (in radians)
 /// * `stroke` - Color/stroke style
+/// * `width` - Stroke width of line-based markers
 /// * `style` - Type of end marker to draw
-pub fn create_end(x: f64, y: f64, angle: f64, stroke: &str, style: EndStyle) -> String {
+pub fn arrow_end(
+    x: f64,
+    y: f64,
+    angle: f64,
+    stroke: Paint,
+    width: f64,
+    style: EndStyle,
+) -> Vec<Element> {
     match style {
-        EndStyle::None => String::new(),
-        EndStyle::Closed => create_end_closed(x, y, angle, stroke),
-        EndStyle::Open => create_end_open(x, y, angle, stroke),
-        EndStyle::Cross => create_end_cross(x, y, stroke),
+        EndStyle::None => Vec::new(),
+        EndStyle::Closed => vec![end_closed(x, y, angle, stroke)],
+        EndStyle::Open => end_open(x, y, angle, stroke, width),
+        EndStyle::Cross => end_cross(x, y, stroke, width),
     }
 }
 
 /// Create a filled triangle arrowhead
-fn create_end_closed(x: f64, y: f64, angle: f64, stroke: &str) -> String {
+pub(super) fn end_closed(x: f64, y: f64, angle: f64, stroke: Paint) -> Element {
     let arrow_length = 10.0;
     let arrow_angle = 0.5; // ~30 degrees
 
@@ -64,14 +109,15 @@ fn create_end_closed(x: f64, y: f64, angle: f64, stroke: &str) -> String {
     let ax2 = x - arrow_length * (angle + arrow_angle).cos();
     let ay2 = y - arrow_length * (angle + arrow_angle).sin();
 
-    format!(
-        r#"<polygon points="{},{} {},{} {},{}" fill="{}"/>"#,
-        x, y, ax1, ay1, ax2, ay2, stroke
-    )
+    let polygon = Element::new("polygon").with_attr(
+        "points",
+        format!("{},{} {},{} {},{}", x, y, ax1, ay1, ax2, ay2),
+    );
+    painted(polygon, class::ARROWHEAD, Some(stroke), None)
 }
 
 /// Create a V-shape open arrowhead (async style)
-fn create_end_open(x: f64, y: f64, angle: f64, stroke: &str) -> String {
+pub(super) fn end_open(x: f64, y: f64, angle: f64, stroke: Paint, width: f64) -> Vec<Element> {
     let arrow_length = 10.0;
     let arrow_angle = 0.5;
 
@@ -80,31 +126,57 @@ fn create_end_open(x: f64, y: f64, angle: f64, stroke: &str) -> String {
     let ax2 = x - arrow_length * (angle + arrow_angle).cos();
     let ay2 = y - arrow_length * (angle + arrow_angle).sin();
 
-    format!(
-        r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="1"/>
-<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="1"/>"#,
-        ax1, ay1, x, y, stroke, ax2, ay2, x, y, stroke
-    )
+    vec![
+        marker_line(ax1, ay1, x, y, stroke, width, class::ARROWHEAD),
+        marker_line(ax2, ay2, x, y, stroke, width, class::ARROWHEAD),
+    ]
 }
 
 /// Create an X-shape cross marker
-fn create_end_cross(x: f64, y: f64, stroke: &str) -> String {
+pub(super) fn end_cross(x: f64, y: f64, stroke: Paint, width: f64) -> Vec<Element> {
     let cross_size = 6.0;
 
-    format!(
-        r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="1"/>
-<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="1"/>"#,
-        x - cross_size,
-        y - cross_size,
-        x + cross_size,
-        y + cross_size,
-        stroke,
-        x - cross_size,
-        y + cross_size,
-        x + cross_size,
-        y - cross_size,
-        stroke
-    )
+    vec![
+        marker_line(
+            x - cross_size,
+            y - cross_size,
+            x + cross_size,
+            y + cross_size,
+            stroke,
+            width,
+            class::CROSSHEAD,
+        ),
+        marker_line(
+            x - cross_size,
+            y + cross_size,
+            x + cross_size,
+            y - cross_size,
+            stroke,
+            width,
+            class::CROSSHEAD,
+        ),
+    ]
+}
+
+/// Create one stroke of a line-based end marker
+fn marker_line(
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+    stroke: Paint,
+    width: f64,
+    class: &str,
+) -> Element {
+    line(x1, y1, x2, y2, stroke, width, LineStyle::Solid, class)
+}
+
+/// Adds the dash pattern of dotted lines
+pub(super) fn with_dash(element: Element, style: LineStyle) -> Element {
+    match style {
+        LineStyle::Dotted => element.with_attr("stroke-dasharray", "5,5"),
+        LineStyle::Solid => element,
+    }
 }
 
 // =============================================================================
@@ -117,35 +189,33 @@ fn create_end_cross(x: f64, y: f64, stroke: &str) -> String {
 /// * `x1`, `y1` - Start point
 /// * `x2`, `y2` - End point
 /// * `stroke` - Color/stroke style
+/// * `width` - Stroke width
 /// * `line_style` - Solid or dotted line
 /// * `start_end` - End marker at start point
 /// * `end_end` - End marker at end point
+/// * `class` - Semantic class of the line
 #[allow(clippy::too_many_arguments)]
-pub fn create_arrow(
+pub fn arrow(
     x1: f64,
     y1: f64,
     x2: f64,
     y2: f64,
-    stroke: &str,
+    stroke: Paint,
+    width: f64,
     line_style: LineStyle,
     start_end: EndStyle,
     end_end: EndStyle,
-) -> String {
-    let line = create_line(x1, y1, x2, y2, stroke, line_style);
+    class: &str,
+) -> Vec<Element> {
+    let mut elements = vec![line(x1, y1, x2, y2, stroke, width, line_style, class)];
 
     // Calculate angle from start to end
     let angle = (y2 - y1).atan2(x2 - x1);
     let reverse_angle = angle + std::f64::consts::PI;
 
-    let end_marker = create_end(x2, y2, angle, stroke, end_end);
-    let start_marker = create_end(x1, y1, reverse_angle, stroke, start_end);
-
-    // Combine parts, filtering empty strings
-    [line, end_marker, start_marker]
-        .into_iter()
-        .filter(|s| !s.is_empty())
-        .collect::<Vec<_>>()
-        .join("\n")
+    elements.extend(arrow_end(x2, y2, angle, stroke, width, end_end));
+    elements.extend(arrow_end(x1, y1, reverse_angle, stroke, width, start_end));
+    elements
 }
 
 /// Create a self-referencing loop arrow (for self-messages)
@@ -153,92 +223,173 @@ pub fn create_arrow(
 /// # Arguments
 /// * `x` - X position (participant center)
 /// * `y` - Y position (message row)
+/// * `loop_width` - Horizontal extent of the loop
 /// * `stroke` - Color/stroke style
+/// * `width` - Stroke width
 /// * `line_style` - Solid or dotted line
-pub fn create_self_loop(x: f64, y: f64, stroke: &str, line_style: LineStyle) -> String {
-    let dash = match line_style {
-        LineStyle::Dotted => r#" stroke-dasharray="5,5""#,
-        LineStyle::Solid => "",
-    };
+/// * `class` - Semantic class of the loop
+pub fn self_loop(
+    x: f64,
+    y: f64,
+    loop_width: f64,
+    stroke: Paint,
+    width: f64,
+    line_style: LineStyle,
+    class: &str,
+) -> Vec<Element> {
+    let path = loop_path(x, y, loop_width, stroke, width, line_style, class);
+
+    // Arrowhead pointing left at the end
+    let head = Element::new("polygon").with_attr(
+        "points",
+        format!(
+            "{},{} {},{} {},{}",
+            x,
+            y + LOOP_HEIGHT,
+            x + 8.0,
+            y + LOOP_HEIGHT - 5.0,
+            x + 8.0,
+            y + LOOP_HEIGHT + 5.0
+        ),
+    );
+
+    vec![path, painted(head, class::ARROWHEAD, Some(stroke), None)]
+}
 
-    let loop_width = 40.0;
-    let loop_height = 30.0;
+/// Height of self-message loops
+const LOOP_HEIGHT: f64 = 30.0;
 
-    // Quadratic bezier curves for oval shape
-    format!(
-        r#"<path d="M {} {} Q {} {} {} {} Q {} {} {} {}" fill="none" stroke="{}" stroke-width="1"{}/>
-<polygon points="{},{} {},{} {},{}" fill="{}"/>"#,
-        // Start point
-        x,
-        y,
-        // First control point (top-right), end at right-middle
-        x + loop_width,
-        y,
-        x + loop_width,
-        y + loop_height / 2.0,
-        // Second control point (bottom-right), end at left-bottom
-        x + loop_width,
-        y + loop_height,
-        x,
-        y + loop_height,
-        stroke,
-        dash,
-        // Arrowhead pointing left at the end
-        x,
-        y + loop_height,
-        x + 8.0,
-        y + loop_height - 5.0,
-        x + 8.0,
-        y + loop_height + 5.0,
-        stroke
-    )
+/// Create the curve of a self-message loop, without arrowhead
+pub(super) fn loop_path(
+    x: f64,
+    y: f64,
+    loop_width: f64,
+    stroke: Paint,
+    width: f64,
+    line_style: LineStyle,
+    class: &str,
+) -> Element {
+    // Quadratic bezier curves for oval shape: the first control point
+    // (top-right) ends at right-middle, the second (bottom-right) at left-bottom
+    let path = Element::new("path").with_attr(
+        "d",
+        format!(
+            "M {} {} Q {} {} {} {} Q {} {} {} {}",
+            x,
+            y,
+            x + loop_width,
+            y,
+            x + loop_width,
+            y + LOOP_HEIGHT / 2.0,
+            x + loop_width,
+            y + LOOP_HEIGHT,
+            x,
+            y + LOOP_HEIGHT
+        ),
+    );
+    let path = painted(path, class, Some(Paint::Color("none")), Some(stroke))
+        .with_attr("stroke-width", width);
+    with_dash(path, line_style)
 }
 
 // =============================================================================
 // Basic Shape Primitives
 // =============================================================================
 
-/// Draw a rectangle
-pub fn draw_rect(x: f64, y: f64, width: f64, height: f64, fill: &str, stroke: &str) -> String {
-    format!(
-        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" stroke="{}" stroke-width="1" rx="4"/>"#,
-        x, y, width, height, fill, stroke
-    )
+/// Create a rectangle with rounded corners
+#[allow(clippy::too_many_arguments)]
+pub fn rect(
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    fill: Paint,
+    stroke: Paint,
+    stroke_width: f64,
+    radius: f64,
+    class: &str,
+) -> Element {
+    let rect = Element::new("rect")
+        .with_attr("x", x)
+        .with_attr("y", y)
+        .with_attr("width", width)
+        .with_attr("height", height);
+    painted(rect, class, Some(fill), Some(stroke))
+        .with_attr("stroke-width", stroke_width)
+        .with_attr("rx", radius)
 }
 
-/// Draw text
-pub fn draw_text(x: f64, y: f64, text: &str, fill: &str, font_size: u32, anchor: &str) -> String {
-    format!(
-        r#"<text x="{}" y="{}" fill="{}" font-size="{}" font-family="Arial, sans-serif" text-anchor="{}">{}</text>"#,
-        x,
-        y,
-        fill,
-        font_size,
-        anchor,
-        escape_xml(text)
-    )
+/// Create a `<text>` element without content
+fn text_element(x: f64, y: f64, fill: Paint, font: &Font, anchor: &str, class: &str) -> Element {
+    let text = Element::new("text").with_attr("x", x).with_attr("y", y);
+    let mut text = painted(text, class, Some(fill), None)
+        .with_attr("font-size", font.size)
+        .with_attr("font-family", &font.family);
+    if let Some(weight) = &font.weight {
+        text.set_attr("font-weight", weight);
+    }
+    text.with_attr("text-anchor", anchor)
 }
 
-/// Draw multi-line text centered vertically
+/// Create text
+pub fn text(
+    x: f64,
+    y: f64,
+    content: &str,
+    fill: Paint,
+    font: &Font,
+    anchor: &str,
+    class: &str,
+) -> Element {
+    text_element(x, y, fill, font, anchor, class).with_text(content)
+}
+
+/// Create a line of styled text
+///
+/// Plain lines are created exactly like [`text`]; formatted runs become
+/// `<tspan>` elements, and links are wrapped in `<a>`.
+pub fn rich_text(
+    x: f64,
+    y: f64,
+    line: &TextLine,
+    fill: Paint,
+    font: &Font,
+    anchor: &str,
+    class: &str,
+) -> Element {
+    if line.is_plain() {
+        return text(x, y, &line.text(), fill, font, anchor, class);
+    }
+
+    let mut text = text_element(x, y, fill, font, anchor, class);
+    for run in &line.runs {
+        text.push(run_node(run, font));
+    }
+    text
+}
+
+/// Create multi-line text centered vertically
 ///
 /// SVG text y coordinate is the baseline, so we need to adjust for visual centering.
 /// The baseline adjustment accounts for the fact that text renders above its y coordinate.
-pub fn draw_multiline_text(
+#[allow(clippy::too_many_arguments)]
+pub fn multiline_text(
     x: f64,
     center_y: f64,
-    lines: &[String],
-    fill: &str,
-    font_size: u32,
+    lines: &[TextLine],
+    fill: Paint,
+    font: &Font,
     line_height: f64,
     anchor: &str,
-) -> String {
+    class: &str,
+) -> Vec<Element> {
     if lines.is_empty() {
-        return String::new();
+        return Vec::new();
     }
 
     // Baseline adjustment: text renders above y, so shift down to visually center
     // Approximately 0.35 * font_size works well for most fonts
-    let baseline_adjustment = font_size as f64 * 0.35;
+    let baseline_adjustment = font.size as f64 * 0.35;
 
     // Total height from first baseline to last baseline
     let total_height = (lines.len() - 1) as f64 * line_height;
@@ -251,22 +402,205 @@ pub fn draw_multiline_text(
         .enumerate()
         .map(|(i, line)| {
             let y = start_y + i as f64 * line_height;
-            format!(
-                r#"<text x="{}" y="{}" fill="{}" font-size="{}" font-family="Arial, sans-serif" text-anchor="{}">{}</text>"#,
-                x, y, fill, font_size, anchor, escape_xml(line)
-            )
+            rich_text(x, y, line, fill, font, anchor, class)
         })
-        .collect::<Vec<_>>()
-        .join("\n")
+        .collect()
+}
+
+/// Create one run of a styled line as text or a `<tspan>`
+fn run_node(run: &TextRun, font: &Font) -> Node {
+    let style = &run.style;
+    if style.is_plain() {
+        return Node::Text(run.text.clone());
+    }
+
+    let mut span = Element::new("tspan");
+    if style.bold {
+        span.set_attr("font-weight", "bold");
+    }
+    if style.italic {
+        span.set_attr("font-style", "italic");
+    }
+    if style.code {
+        span.set_attr("font-family", CODE_FONT_FAMILY);
+    }
+    if style.href.is_some() {
+        span.set_attr("text-decoration", "underline");
+    }
+    if let Some(shift) = style.shift {
+        let shift = match shift {
+            BaselineShift::Sub => "sub",
+            BaselineShift::Super => "super",
+        };
+        span.set_attr("font-size", style.font(font).size);
+        span.set_attr("baseline-shift", shift);
+    }
+
+    let span = span.with_text(run.text.as_str());
+    match &style.href {
+        Some(href) => Element::new("a")
+            .with_attr("href", href)
+            .with_child(span)
+            .into(),
+        None => span.into(),
+    }
+}
+
+// =============================================================================
+// String API (compatibility layer over the typed shapes)
+// =============================================================================
+
+/// Create a line segment with specified style and semantic class
+///
+/// See [`line`].
+#[allow(clippy::too_many_arguments)]
+pub fn create_line(
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+    stroke: Paint,
+    width: f64,
+    style: LineStyle,
+    class: &str,
+) -> String {
+    serialize(line(x1, y1, x2, y2, stroke, width, style, class))
+}
+
+/// Create an arrow end/head at a specific point with direction
+///
+/// See [`arrow_end`].
+pub fn create_end(
+    x: f64,
+    y: f64,
+    angle: f64,
+    stroke: Paint,
+    width: f64,
+    style: EndStyle,
+) -> String {
+    serialize_all(arrow_end(x, y, angle, stroke, width, style))
 }
 
-/// Escape XML special characters
-fn escape_xml(s: &str) -> String {
-    s.replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        .replace('"', "&quot;")
-        .replace('\'', "&apos;")
+/// Create a complete arrow with line and optional end markers
+///
+/// See [`arrow`].
+#[allow(clippy::too_many_arguments)]
+pub fn create_arrow(
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+    stroke: Paint,
+    width: f64,
+    line_style: LineStyle,
+    start_end: EndStyle,
+    end_end: EndStyle,
+    class: &str,
+) -> String {
+    serialize_all(arrow(
+        x1, y1, x2, y2, stroke, width, line_style, start_end, end_end, class,
+    ))
+}
+
+/// Create a self-referencing loop arrow (for self-messages)
+///
+/// See [`self_loop`].
+pub fn create_self_loop(
+    x: f64,
+    y: f64,
+    loop_width: f64,
+    stroke: Paint,
+    width: f64,
+    line_style: LineStyle,
+    class: &str,
+) -> String {
+    serialize_all(self_loop(
+        x, y, loop_width, stroke, width, line_style, class,
+    ))
+}
+
+/// Draw a rectangle with rounded corners
+///
+/// See [`rect`].
+#[allow(clippy::too_many_arguments)]
+pub fn draw_rect(
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    fill: Paint,
+    stroke: Paint,
+    stroke_width: f64,
+    radius: f64,
+    class: &str,
+) -> String {
+    serialize(rect(
+        x,
+        y,
+        width,
+        height,
+        fill,
+        stroke,
+        stroke_width,
+        radius,
+        class,
+    ))
+}
+
+/// Draw text
+///
+/// See [`text`].
+pub fn draw_text(
+    x: f64,
+    y: f64,
+    content: &str,
+    fill: Paint,
+    font: &Font,
+    anchor: &str,
+    class: &str,
+) -> String {
+    serialize(text(x, y, content, fill, font, anchor, class))
+}
+
+/// Draw a line of styled text
+///
+/// See [`rich_text`].
+pub fn draw_rich_text(
+    x: f64,
+    y: f64,
+    line: &TextLine,
+    fill: Paint,
+    font: &Font,
+    anchor: &str,
+    class: &str,
+) -> String {
+    serialize(rich_text(x, y, line, fill, font, anchor, class))
+}
+
+/// Draw multi-line text centered vertically
+///
+/// See [`multiline_text`].
+#[allow(clippy::too_many_arguments)]
+pub fn draw_multiline_text(
+    x: f64,
+    center_y: f64,
+    lines: &[TextLine],
+    fill: Paint,
+    font: &Font,
+    line_height: f64,
+    anchor: &str,
+    class: &str,
+) -> String {
+    serialize_all(multiline_text(
+        x,
+        center_y,
+        lines,
+        fill,
+        font,
+        line_height,
+        anchor,
+        class,
+    ))
 }
 
 // =============================================================================
@@ -280,5 +614,5 @@ pub fn draw_line(x1: f64, y1: f64, x2: f64, y2: f64, stroke: &str, dashed: bool)
     } else {
         LineStyle::Solid
     };
-    create_line(x1, y1, x2, y2, stroke, style)
+    create_line(x1, y1, x2, y2, Paint::Color(stroke), 1.0, style, "")
 }
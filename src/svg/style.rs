@@ -0,0 +1,406 @@
+//! Colour paints and adaptive stylesheets
+//!
+//! Every shape carries a semantic class named after Mermaid's (see
+//! [`class`]). Colours are painted either with inline `fill`/`stroke`
+//! attributes, with rules keyed by the semantic classes in an embedded
+//! stylesheet ([`Palette::with_style_block`]) or, for [`Theme::Auto`], with
+//! role classes. Adaptive stylesheets define one custom property per
+//! [`ColorRole`] and swap them inside `@media (prefers-color-scheme: dark)`.
+//!
+//! Stylesheets are scoped to their diagram with [`scope_stylesheet`], so
+//! diagrams inlined into the same page keep their own colours.
+
+use crate::theme::{ColorRole, Theme, ThemeColors};
+
+use super::element::Element;
+
+/// Semantic class names
+pub mod class {
+    /// Background rectangle
+    pub const BACKGROUND: &str = "background";
+    /// Participant boxes and labels
+    pub const ACTOR: &str = "actor";
+    /// Participant boxes at the top
+    pub const ACTOR_TOP: &str = "actor-top";
+    /// Participant boxes at the bottom
+    pub const ACTOR_BOTTOM: &str = "actor-bottom";
+    /// Lifelines
+    pub const ACTOR_LINE: &str = "actor-line";
+    /// Solid message lines
+    pub const MESSAGE_LINE_SOLID: &str = "messageLine0";
+    /// Dotted message lines
+    pub const MESSAGE_LINE_DOTTED: &str = "messageLine1";
+    /// Message labels
+    pub const MESSAGE_TEXT: &str = "messageText";
+    /// Filled and open arrowheads
+    pub const ARROWHEAD: &str = "arrowhead";
+    /// Cross arrow ends
+    pub const CROSSHEAD: &str = "crosshead";
+}
+
+/// Selectors of the semantic classes with the roles of their fill and stroke
+const SEMANTIC_RULES: &[(&str, Option<ColorRole>, Option<ColorRole>)] = &[
+    ("rect.background", Some(ColorRole::Background), None),
+    (
+        "rect.actor",
+        Some(ColorRole::ParticipantBg),
+        Some(ColorRole::ParticipantBorder),
+    ),
+    ("text.actor", Some(ColorRole::ParticipantText), None),
+    (".actor-line", None, Some(ColorRole::Lifeline)),
+    (".messageLine0, .messageLine1", None, Some(ColorRole::Line)),
+    ("polygon.arrowhead", Some(ColorRole::Line), None),
+    ("line.arrowhead, .crosshead", None, Some(ColorRole::Line)),
+    ("text.messageText", Some(ColorRole::MessageText), None),
+];
+
+/// Class set on the root `<svg>` of adaptive diagrams
+pub const ADAPTIVE_CLASS: &str = "msv-auto";
+
+/// How a shape's fill or stroke is coloured
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Paint<'a> {
+    /// Inline colour value
+    Color(&'a str),
+    /// Colour role resolved by the embedded stylesheet
+    Role(ColorRole),
+    /// Colour set by the stylesheet rule of the element's semantic class
+    Class,
+}
+
+impl<'a> From<&'a str> for Paint<'a> {
+    fn from(color: &'a str) -> Self {
+        Paint::Color(color)
+    }
+}
+
+impl<'a> From<&'a String> for Paint<'a> {
+    fn from(color: &'a String) -> Self {
+        Paint::Color(color)
+    }
+}
+
+/// Class applying a role as a fill (`msv-fill-<role>`)
+pub fn fill_class(role: ColorRole) -> String {
+    format!("msv-fill-{}", role.name())
+}
+
+/// Class applying a role as a stroke (`msv-stroke-<role>`)
+pub fn stroke_class(role: ColorRole) -> String {
+    format!("msv-stroke-{}", role.name())
+}
+
+/// Sets the fill, stroke and class attributes of an element
+///
+/// Inline colours become `fill`/`stroke` attributes; the semantic `class`
+/// (which may be empty) and any role classes are collected into a single
+/// `class` attribute placed after them.
+pub fn painted(
+    mut element: Element,
+    class: &str,
+    fill: Option<Paint>,
+    stroke: Option<Paint>,
+) -> Element {
+    let mut classes = Vec::new();
+    if !class.is_empty() {
+        classes.push(class.to_string());
+    }
+
+    for (attribute, paint) in [("fill", fill), ("stroke", stroke)] {
+        match paint {
+            Some(Paint::Color(color)) => element.set_attr(attribute, color),
+            Some(Paint::Role(role)) if attribute == "fill" => classes.push(fill_class(role)),
+            Some(Paint::Role(role)) => classes.push(stroke_class(role)),
+            Some(Paint::Class) | None => {}
+        }
+    }
+    if !classes.is_empty() {
+        element.set_attr("class", classes.join(" "));
+    }
+    element
+}
+
+/// Formats the class, fill and stroke of an element as attribute text
+///
+/// See [`painted`] for the attributes produced.
+pub fn paint_attributes(class: &str, fill: Option<Paint>, stroke: Option<Paint>) -> String {
+    painted(Element::new(""), class, fill, stroke)
+        .attributes()
+        .map(|(name, value)| format!(r#"{}="{}""#, name, value))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Resolves colour roles for a theme
+#[derive(Debug, Clone)]
+pub struct Palette {
+    colors: ThemeColors,
+    dark: Option<ThemeColors>,
+    style_block: bool,
+}
+
+impl Palette {
+    /// Creates the palette of a theme
+    ///
+    /// [`Theme::Auto`] yields an adaptive palette that paints with roles.
+    pub fn new(theme: &Theme) -> Self {
+        let dark = match theme {
+            Theme::Auto => Some(ThemeColors::dark()),
+            _ => None,
+        };
+        Self {
+            colors: theme.colors(),
+            dark,
+            style_block: false,
+        }
+    }
+
+    /// Creates a palette that paints with fixed colours
+    pub fn fixed(colors: ThemeColors) -> Self {
+        Self {
+            colors,
+            dark: None,
+            style_block: false,
+        }
+    }
+
+    /// Paints through rules for the semantic classes instead of per-element
+    /// colours (builder pattern)
+    pub fn with_style_block(mut self, style_block: bool) -> Self {
+        self.style_block = style_block;
+        self
+    }
+
+    /// Returns `true` if colours are resolved by a stylesheet
+    pub fn is_adaptive(&self) -> bool {
+        self.dark.is_some()
+    }
+
+    /// Returns the (light) colours, stroke widths and corner radius
+    pub fn colors(&self) -> &ThemeColors {
+        &self.colors
+    }
+
+    /// Returns the paint for a colour role
+    pub fn paint(&self, role: ColorRole) -> Paint<'_> {
+        if self.style_block {
+            Paint::Class
+        } else if self.is_adaptive() {
+            Paint::Role(role)
+        } else {
+            Paint::Color(self.colors.get(role))
+        }
+    }
+
+    /// Returns the stylesheet of an adaptive or style block palette
+    ///
+    /// The custom properties are declared on `&`, the diagram's root; see
+    /// [`scope_stylesheet`].
+    pub fn stylesheet(&self) -> Option<String> {
+        let mut css = String::new();
+        if let Some(dark) = &self.dark {
+            css.push_str(&custom_properties("&", &self.colors));
+            css.push_str(&format!(
+                "@media (prefers-color-scheme: dark) {{ {} }}\n",
+                custom_properties("&", dark).trim_end()
+            ));
+        }
+
+        if self.style_block {
+            for &(selector, fill, stroke) in SEMANTIC_RULES {
+                let mut declarations = Vec::new();
+                if let Some(role) = fill {
+                    declarations.push(format!("fill: {};", self.value(role)));
+                }
+                if let Some(role) = stroke {
+                    declarations.push(format!("stroke: {};", self.value(role)));
+                }
+                css.push_str(&format!("{} {{ {} }}\n", selector, declarations.join(" ")));
+            }
+            return Some(css);
+        }
+        if !self.is_adaptive() {
+            return None;
+        }
+
+        for role in ColorRole::ALL {
+            css.push_str(&format!(
+                ".{} {{ fill: var(--msv-{}); }}\n.{} {{ stroke: var(--msv-{}); }}\n",
+                fill_class(role),
+                role.name(),
+                stroke_class(role),
+                role.name()
+            ));
+        }
+        Some(css)
+    }
+
+    /// Returns the CSS value of a role: its custom property when adaptive
+    fn value(&self, role: ColorRole) -> String {
+        if self.is_adaptive() {
+            format!("var(--msv-{})", role.name())
+        } else {
+            self.colors.get(role).to_string()
+        }
+    }
+}
+
+/// Class scoping a stylesheet to its diagram, named after a hash of the rules
+///
+/// Diagrams on one page share the class only if their rules are identical.
+pub fn scope_class(css: &str) -> String {
+    format!("msv-diagram-{:08x}", fnv1a(css))
+}
+
+/// Scopes every rule of a stylesheet to the descendants of `root`
+///
+/// Each selector `S` becomes `root S` and `&` stands for the root itself.
+/// At-rules such as `@media` keep their condition and have their rules
+/// scoped.
+pub fn scope_stylesheet(css: &str, root: &str) -> String {
+    let mut scoped = String::with_capacity(css.len() + css.len() / 2);
+    let mut rest = css;
+    while let Some(open) = rest.find('{') {
+        let (prelude, block) = (&rest[..open], &rest[open + 1..]);
+        let close = matching_brace(block);
+        let body = &block[..close];
+
+        let selector = prelude.trim();
+        scoped.push_str(&prelude[..prelude.len() - prelude.trim_start().len()]);
+        if selector.starts_with('@') {
+            scoped.push_str(&format!(
+                "{} {{{}}}",
+                selector,
+                scope_stylesheet(body, root)
+            ));
+        } else {
+            let selectors: Vec<String> = selector
+                .split(',')
+                .map(|selector| match selector.trim() {
+                    "&" => root.to_string(),
+                    selector => format!("{} {}", root, selector),
+                })
+                .collect();
+            scoped.push_str(&format!("{} {{{}}}", selectors.join(", "), body));
+        }
+        rest = block.get(close + 1..).unwrap_or_default();
+    }
+    scoped.push_str(rest);
+    scoped
+}
+
+/// Index of the `}` closing a block whose `{` was just consumed, or the
+/// block's length if it is unterminated
+fn matching_brace(block: &str) -> usize {
+    let mut depth = 0usize;
+    for (index, c) in block.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return index,
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    block.len()
+}
+
+/// FNV-1a hash, used for stable generated class names
+pub(crate) fn fnv1a(text: &str) -> u32 {
+    text.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// Declares one custom property per colour role
+fn custom_properties(scope: &str, colors: &ThemeColors) -> String {
+    let properties: Vec<String> = ColorRole::ALL
+        .iter()
+        .map(|&role| format!("--msv-{}: {};", role.name(), colors.get(role)))
+        .collect();
+    format!("{} {{ {} }}\n", scope, properties.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paint_attributes() {
+        assert_eq!(
+            paint_attributes("", Some("#fff".into()), Some("#000".into())),
+            r##"fill="#fff" stroke="#000""##
+        );
+        assert_eq!(
+            paint_attributes(
+                "actor",
+                Some(Paint::Role(ColorRole::ParticipantBg)),
+                Some(Paint::Role(ColorRole::ParticipantBorder))
+            ),
+            r#"class="actor msv-fill-participant-bg msv-stroke-participant-border""#
+        );
+        assert_eq!(
+            paint_attributes("", Some("none".into()), Some(Paint::Role(ColorRole::Line))),
+            r#"fill="none" class="msv-stroke-line""#
+        );
+    }
+
+    #[test]
+    fn test_static_palette_paints_inline() {
+        let palette = Palette::new(&Theme::Dark);
+        assert!(!palette.is_adaptive());
+        assert_eq!(
+            palette.paint(ColorRole::Background),
+            Paint::Color("#1a1a2e")
+        );
+        assert_eq!(palette.stylesheet(), None);
+    }
+
+    #[test]
+    fn test_adaptive_stylesheet() {
+        let palette = Palette::new(&Theme::Auto);
+        assert_eq!(palette.paint(ColorRole::Line), Paint::Role(ColorRole::Line));
+
+        let css = palette.stylesheet().unwrap();
+        assert!(css.contains("& { --msv-background: #ffffff;"));
+        assert!(
+            css.contains("@media (prefers-color-scheme: dark) { & { --msv-background: #1a1a2e;")
+        );
+        assert!(css.contains(".msv-stroke-line { stroke: var(--msv-line); }"));
+    }
+
+    #[test]
+    fn test_style_block_rules() {
+        let palette = Palette::new(&Theme::Light).with_style_block(true);
+        assert_eq!(palette.paint(ColorRole::Line), Paint::Class);
+        assert_eq!(
+            paint_attributes(class::ACTOR_LINE, Some(Paint::Class), None),
+            r#"class="actor-line""#
+        );
+
+        let css = palette.stylesheet().unwrap();
+        assert!(css.contains("rect.actor { fill: #ecf0f1; stroke: #333333; }"));
+        assert!(!css.contains("--msv-"));
+
+        let adaptive = Palette::new(&Theme::Auto)
+            .with_style_block(true)
+            .stylesheet()
+            .unwrap();
+        assert!(adaptive.contains("text.messageText { fill: var(--msv-message-text); }"));
+        assert!(!adaptive.contains(".msv-fill-"));
+    }
+
+    #[test]
+    fn test_scope_stylesheet() {
+        let css = "& { --msv-line: #333; }\n\
+                   @media (prefers-color-scheme: dark) { & { --msv-line: #ccc; } }\n\
+                   .messageLine0, .messageLine1 { stroke: var(--msv-line); }\n";
+        assert_eq!(
+            scope_stylesheet(css, ".d"),
+            ".d { --msv-line: #333; }\n\
+             @media (prefers-color-scheme: dark) { .d { --msv-line: #ccc; } }\n\
+             .d .messageLine0, .d .messageLine1 { stroke: var(--msv-line); }\n"
+        );
+        assert_eq!(scope_class(css), scope_class(css));
+        assert_ne!(scope_class(css), scope_class("rect.actor { fill: red; }"));
+    }
+}
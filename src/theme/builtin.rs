@@ -0,0 +1,276 @@
+//! Mermaid's built-in themes
+//!
+//! Each theme is expressed as the `themeVariables` Mermaid assigns for
+//! sequence diagrams; [`ThemeVariables::to_colors`] fills in the rest. The
+//! `base` theme sets nothing, so every colour follows the base derivation
+//! rules.
+
+use super::color::Color;
+use super::{Theme, ThemeColors, ThemeVariables};
+
+impl Theme {
+    /// Returns the Mermaid `themeVariables` behind a Mermaid theme
+    ///
+    /// [`Theme::Light`] and [`Theme::Dark`] (and [`Theme::Auto`], which
+    /// renders light) are given variables that reproduce their palettes, so
+    /// overrides start from them. Returns `None` for custom palettes.
+    pub fn variables(&self) -> Option<ThemeVariables> {
+        match self {
+            Theme::Default => Some(default_variables()),
+            Theme::Neutral => Some(neutral_variables()),
+            Theme::Forest => Some(forest_variables()),
+            Theme::Base => Some(ThemeVariables::new()),
+            Theme::Light | Theme::Auto => Some(palette_variables(&ThemeColors::light(), false)),
+            Theme::Dark => Some(palette_variables(&ThemeColors::dark(), true)),
+            Theme::Custom(_) => None,
+        }
+    }
+}
+
+impl ThemeColors {
+    /// Returns the palette of Mermaid's `default` theme
+    pub fn mermaid_default() -> Self {
+        resolve(default_variables())
+    }
+
+    /// Returns the palette of Mermaid's `neutral` theme
+    pub fn neutral() -> Self {
+        resolve(neutral_variables())
+    }
+
+    /// Returns the palette of Mermaid's `forest` theme
+    pub fn forest() -> Self {
+        resolve(forest_variables())
+    }
+
+    /// Returns the palette of Mermaid's `base` theme
+    ///
+    /// This is the palette [`ThemeVariables`] derives from when no
+    /// variables are set.
+    pub fn base() -> Self {
+        resolve(ThemeVariables::new())
+    }
+}
+
+/// Variables of Mermaid's `default` theme (lavender actors, yellow notes)
+fn default_variables() -> ThemeVariables {
+    ThemeVariables::new()
+        .with("background", "#ffffff")
+        .with("primaryColor", "#ECECFF")
+        .with("secondaryColor", "#ffffde")
+        .with("tertiaryColor", "hsl(80, 100%, 96.2745098039%)")
+        .with("primaryBorderColor", "hsl(240, 60%, 86.2745098039%)")
+        .with("primaryTextColor", "#131300")
+        .with("textColor", "#333")
+        .with("lineColor", "#333333")
+        .with("actorBkg", "#ECECFF")
+        .with(
+            "actorBorder",
+            "hsl(259.6261682243, 59.7765363128%, 87.9019607843%)",
+        )
+        .with("actorTextColor", "black")
+        .with("actorLineColor", "grey")
+        .with("signalColor", "#333")
+        .with("signalTextColor", "#333")
+        .with("labelBoxBkgColor", "#ECECFF")
+        .with(
+            "labelBoxBorderColor",
+            "hsl(259.6261682243, 59.7765363128%, 87.9019607843%)",
+        )
+        .with("labelTextColor", "black")
+        .with("loopTextColor", "black")
+        .with("noteBkgColor", "#fff5ad")
+        .with("noteBorderColor", "#aaaa33")
+        .with("noteTextColor", "black")
+        .with("activationBkgColor", "#f4f4f4")
+        .with("activationBorderColor", "#666")
+        .with("sequenceNumberColor", "white")
+}
+
+/// Variables of Mermaid's `neutral` theme (greyscale, for printing)
+fn neutral_variables() -> ThemeVariables {
+    let contrast = hex("#707070");
+    let border = hex("#999999");
+    let actor_border = border.lighten(23.0).to_string();
+    let secondary = contrast.lighten(55.0).to_string();
+
+    ThemeVariables::new()
+        .with("background", "#ffffff")
+        .with("primaryColor", "#eee")
+        .with("secondaryColor", &secondary)
+        .with("textColor", "#333")
+        .with("primaryTextColor", "#333")
+        .with("lineColor", "#666")
+        .with("actorBkg", "#eee")
+        .with("actorBorder", &actor_border)
+        .with("actorTextColor", "#333")
+        .with("actorLineColor", &actor_border)
+        .with("signalColor", "#333")
+        .with("signalTextColor", "#333")
+        .with("labelBoxBkgColor", "#eee")
+        .with("labelBoxBorderColor", &actor_border)
+        .with("labelTextColor", "#333")
+        .with("loopTextColor", "#333")
+        .with("noteBkgColor", "#666")
+        .with("noteBorderColor", "#999")
+        .with("noteTextColor", "#fff")
+        .with("activationBkgColor", &secondary)
+        .with("activationBorderColor", "#666")
+        .with("sequenceNumberColor", "white")
+}
+
+/// Variables of Mermaid's `forest` theme (greens)
+fn forest_variables() -> ThemeVariables {
+    let actor_border = hex("#cde498").darken(20.0).to_string();
+
+    ThemeVariables::new()
+        .with("background", "#ffffff")
+        .with("primaryColor", "#cde498")
+        .with("secondaryColor", "#cdffb2")
+        .with("primaryBorderColor", "#13540c")
+        .with("textColor", "#333")
+        .with("primaryTextColor", "#333")
+        .with("lineColor", "green")
+        .with("actorBkg", "#cde498")
+        .with("actorBorder", &actor_border)
+        .with("actorTextColor", "black")
+        .with("actorLineColor", &actor_border)
+        .with("signalColor", "#333")
+        .with("signalTextColor", "#333")
+        .with("labelBoxBkgColor", "#cde498")
+        .with("labelBoxBorderColor", "#326932")
+        .with("labelTextColor", "black")
+        .with("loopTextColor", "black")
+        .with("noteBkgColor", "#fff5ad")
+        .with("noteBorderColor", "#6eaa49")
+        .with("noteTextColor", "black")
+        .with("activationBkgColor", "#f4f4f4")
+        .with("activationBorderColor", "#666")
+        .with("sequenceNumberColor", "white")
+}
+
+/// Variables that resolve to the colours of a fixed palette
+fn palette_variables(colors: &ThemeColors, dark: bool) -> ThemeVariables {
+    let mut variables = ThemeVariables::new()
+        .with("darkMode", dark.to_string())
+        .with("background", &colors.background)
+        .with("primaryColor", &colors.participant_bg)
+        .with("secondaryColor", &colors.activation_bg)
+        .with("primaryBorderColor", &colors.participant_border)
+        .with("textColor", &colors.text)
+        .with("primaryTextColor", &colors.participant_text)
+        .with("lineColor", &colors.line)
+        .with("actorBkg", &colors.participant_bg)
+        .with("actorBorder", &colors.participant_border)
+        .with("actorTextColor", &colors.participant_text)
+        .with("actorLineColor", &colors.lifeline)
+        .with("signalColor", &colors.line)
+        .with("signalTextColor", &colors.message_text)
+        .with("labelBoxBkgColor", &colors.label_bg)
+        .with("labelBoxBorderColor", &colors.frame_border)
+        .with("labelTextColor", &colors.label_text)
+        .with("loopTextColor", &colors.frame_text)
+        .with("noteBkgColor", &colors.note_bg)
+        .with("noteBorderColor", &colors.note_border)
+        .with("noteTextColor", &colors.note_text)
+        .with("activationBkgColor", &colors.activation_bg)
+        .with("activationBorderColor", &colors.activation_border)
+        .with("sequenceNumberColor", &colors.sequence_number)
+        .with("strokeWidth", colors.line_width.to_string());
+    for (index, color) in colors.palette.iter().enumerate() {
+        variables.set(format!("cScale{}", index), color);
+    }
+    variables
+}
+
+/// Resolves built-in variables, which are known to be valid
+fn resolve(variables: ThemeVariables) -> ThemeColors {
+    variables
+        .to_colors()
+        .expect("built-in theme variables are valid")
+}
+
+/// Parses a hex literal from the tables above
+fn hex(value: &str) -> Color {
+    Color::parse(value).expect("valid colour literal")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_theme_matches_mermaid() {
+        let colors = ThemeColors::mermaid_default();
+        assert_eq!(colors.participant_bg, "#ececff");
+        assert_eq!(colors.participant_text, "#000000");
+        assert_eq!(colors.lifeline, "#808080");
+        assert_eq!(colors.note_bg, "#fff5ad");
+        assert_eq!(colors.note_border, "#aaaa33");
+        assert_eq!(colors.line, "#333333");
+        assert_eq!(colors.corner_radius, 3.0);
+    }
+
+    #[test]
+    fn test_light_and_dark_variables_reproduce_their_palettes() {
+        for (theme, colors) in [
+            (Theme::Light, ThemeColors::light()),
+            (Theme::Dark, ThemeColors::dark()),
+        ] {
+            let resolved = resolve(theme.variables().unwrap());
+            assert_eq!(
+                ThemeColors {
+                    corner_radius: colors.corner_radius,
+                    ..resolved
+                },
+                colors
+            );
+        }
+    }
+
+    #[test]
+    fn test_forest_theme_matches_mermaid() {
+        let colors = ThemeColors::forest();
+        assert_eq!(colors.participant_bg, "#cde498");
+        assert_eq!(colors.frame_border, "#326932");
+        assert_eq!(colors.note_border, "#6eaa49");
+    }
+
+    #[test]
+    fn test_neutral_theme_is_greyscale() {
+        let colors = ThemeColors::neutral();
+        for color in [
+            &colors.participant_bg,
+            &colors.participant_border,
+            &colors.note_bg,
+            &colors.activation_bg,
+        ] {
+            let color = Color::parse(color).unwrap();
+            assert!(color.r == color.g && color.g == color.b, "{}", color);
+        }
+        assert_eq!(colors.note_text, "#ffffff");
+    }
+
+    #[test]
+    fn test_base_theme_uses_derivation_rules() {
+        let colors = ThemeColors::base();
+        assert_eq!(colors.participant_bg, "#fff4dd");
+        assert_eq!(
+            colors.participant_border,
+            hex("#fff4dd").adjust(0.0, -40.0, -10.0).to_string()
+        );
+        assert_eq!(colors, ThemeVariables::new().to_colors().unwrap());
+    }
+
+    #[test]
+    fn test_builtin_themes_are_valid() {
+        for colors in [
+            ThemeColors::mermaid_default(),
+            ThemeColors::neutral(),
+            ThemeColors::forest(),
+            ThemeColors::base(),
+        ] {
+            assert!(colors.validate().is_ok());
+        }
+    }
+}
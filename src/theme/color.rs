@@ -0,0 +1,352 @@
+//! Colour parsing and adjustment
+//!
+//! Mermaid derives many theme colours from a few base colours by shifting
+//! hue, saturation and lightness (via the `khroma` library). [`Color`]
+//! implements the same operations so derived palettes match.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// CSS named colours accepted by [`Color::parse`]
+const NAMED_COLORS: &[(&str, (u8, u8, u8))] = &[
+    ("black", (0, 0, 0)),
+    ("white", (255, 255, 255)),
+    ("red", (255, 0, 0)),
+    ("green", (0, 128, 0)),
+    ("blue", (0, 0, 255)),
+    ("yellow", (255, 255, 0)),
+    ("orange", (255, 165, 0)),
+    ("purple", (128, 0, 128)),
+    ("gray", (128, 128, 128)),
+    ("grey", (128, 128, 128)),
+    ("lightgray", (211, 211, 211)),
+    ("lightgrey", (211, 211, 211)),
+    ("darkgray", (169, 169, 169)),
+    ("darkgrey", (169, 169, 169)),
+    ("silver", (192, 192, 192)),
+    ("navy", (0, 0, 128)),
+    ("teal", (0, 128, 128)),
+    ("maroon", (128, 0, 0)),
+    ("olive", (128, 128, 0)),
+    ("lime", (0, 255, 0)),
+    ("aqua", (0, 255, 255)),
+    ("cyan", (0, 255, 255)),
+    ("fuchsia", (255, 0, 255)),
+    ("magenta", (255, 0, 255)),
+    ("pink", (255, 192, 203)),
+    ("brown", (165, 42, 42)),
+    ("gold", (255, 215, 0)),
+];
+
+/// An sRGB colour with alpha
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    /// Red channel (0-255)
+    pub r: u8,
+    /// Green channel (0-255)
+    pub g: u8,
+    /// Blue channel (0-255)
+    pub b: u8,
+    /// Opacity (0.0-1.0)
+    pub a: f64,
+}
+
+impl Color {
+    /// Creates an opaque colour
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 1.0 }
+    }
+
+    /// Parses a CSS colour
+    ///
+    /// Accepts `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb()`/`rgba()`,
+    /// `hsl()`/`hsla()`, `transparent` and common named colours.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use mermaid_svg_render::theme::Color;
+    ///
+    /// assert_eq!(Color::parse("#f80"), Some(Color::rgb(255, 136, 0)));
+    /// assert_eq!(Color::parse("hsl(0, 100%, 50%)"), Some(Color::rgb(255, 0, 0)));
+    /// ```
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let lower = value.to_ascii_lowercase();
+
+        if let Some(hex) = lower.strip_prefix('#') {
+            return parse_hex(hex);
+        }
+        if lower == "transparent" {
+            return Some(Self {
+                a: 0.0,
+                ..Self::rgb(0, 0, 0)
+            });
+        }
+        if let Some((name, args)) = lower.strip_suffix(')').and_then(|f| f.split_once('(')) {
+            return parse_function(name.trim(), args);
+        }
+        NAMED_COLORS
+            .iter()
+            .find(|(name, _)| *name == lower)
+            .map(|(_, (r, g, b))| Self::rgb(*r, *g, *b))
+    }
+
+    /// Converts to hue (degrees), saturation and lightness (percent)
+    pub fn to_hsl(&self) -> (f64, f64, f64) {
+        let r = self.r as f64 / 255.0;
+        let g = self.g as f64 / 255.0;
+        let b = self.b as f64 / 255.0;
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let l = (max + min) / 2.0;
+
+        if max == min {
+            return (0.0, 0.0, l * 100.0);
+        }
+
+        let d = max - min;
+        let s = if l > 0.5 {
+            d / (2.0 - max - min)
+        } else {
+            d / (max + min)
+        };
+        let h = if max == r {
+            (g - b) / d + if g < b { 6.0 } else { 0.0 }
+        } else if max == g {
+            (b - r) / d + 2.0
+        } else {
+            (r - g) / d + 4.0
+        };
+
+        (h * 60.0, s * 100.0, l * 100.0)
+    }
+
+    /// Creates a colour from hue (degrees), saturation and lightness (percent)
+    pub fn from_hsl(h: f64, s: f64, l: f64, a: f64) -> Self {
+        let h = h.rem_euclid(360.0) / 360.0;
+        let s = (s / 100.0).clamp(0.0, 1.0);
+        let l = (l / 100.0).clamp(0.0, 1.0);
+
+        let channel = |t: f64| {
+            let q = if l < 0.5 {
+                l * (1.0 + s)
+            } else {
+                l + s - l * s
+            };
+            let p = 2.0 * l - q;
+            let t = t.rem_euclid(1.0);
+            let v = if t < 1.0 / 6.0 {
+                p + (q - p) * 6.0 * t
+            } else if t < 0.5 {
+                q
+            } else if t < 2.0 / 3.0 {
+                p + (q - p) * (2.0 / 3.0 - t) * 6.0
+            } else {
+                p
+            };
+            (v * 255.0).round() as u8
+        };
+
+        Self {
+            r: channel(h + 1.0 / 3.0),
+            g: channel(h),
+            b: channel(h - 1.0 / 3.0),
+            a,
+        }
+    }
+
+    /// Shifts hue, saturation and lightness (like khroma's `adjust`)
+    ///
+    /// Saturation and lightness are clamped to 0-100 and hue wraps around.
+    pub fn adjust(&self, hue: f64, saturation: f64, lightness: f64) -> Self {
+        let (h, s, l) = self.to_hsl();
+        Self::from_hsl(
+            h + hue,
+            (s + saturation).clamp(0.0, 100.0),
+            (l + lightness).clamp(0.0, 100.0),
+            self.a,
+        )
+    }
+
+    /// Increases lightness by `amount` percentage points
+    pub fn lighten(&self, amount: f64) -> Self {
+        self.adjust(0.0, 0.0, amount)
+    }
+
+    /// Decreases lightness by `amount` percentage points
+    pub fn darken(&self, amount: f64) -> Self {
+        self.adjust(0.0, 0.0, -amount)
+    }
+
+    /// Inverts each RGB channel
+    pub fn invert(&self) -> Self {
+        Self {
+            r: 255 - self.r,
+            g: 255 - self.g,
+            b: 255 - self.b,
+            a: self.a,
+        }
+    }
+
+    /// Returns `true` if the colour is perceived as dark
+    pub fn is_dark(&self) -> bool {
+        let luminance = 0.299 * self.r as f64 + 0.587 * self.g as f64 + 0.114 * self.b as f64;
+        luminance < 128.0
+    }
+}
+
+impl fmt::Display for Color {
+    /// Formats as `#rrggbb`, or `rgba()` when not fully opaque
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.a >= 1.0 {
+            write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+        } else {
+            write!(f, "rgba({}, {}, {}, {})", self.r, self.g, self.b, self.a)
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s).ok_or_else(|| format!("invalid colour '{}'", s))
+    }
+}
+
+/// Parses the digits of a hex colour
+fn parse_hex(hex: &str) -> Option<Color> {
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok().map(|d| d * 17);
+    let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+
+    let (r, g, b, a) = match hex.len() {
+        3 => (digit(0)?, digit(1)?, digit(2)?, 255),
+        4 => (digit(0)?, digit(1)?, digit(2)?, digit(3)?),
+        6 => (pair(0)?, pair(2)?, pair(4)?, 255),
+        8 => (pair(0)?, pair(2)?, pair(4)?, pair(6)?),
+        _ => return None,
+    };
+    Some(Color {
+        a: a as f64 / 255.0,
+        ..Color::rgb(r, g, b)
+    })
+}
+
+/// Parses `rgb()`, `rgba()`, `hsl()` and `hsla()` arguments
+fn parse_function(name: &str, args: &str) -> Option<Color> {
+    let args: Vec<&str> = args
+        .split([',', ' ', '/'])
+        .map(str::trim)
+        .filter(|arg| !arg.is_empty())
+        .collect();
+    if args.len() != 3 && args.len() != 4 {
+        return None;
+    }
+
+    let number = |arg: &str| arg.trim_end_matches('%').parse::<f64>().ok();
+    let alpha = match args.get(3) {
+        Some(arg) if arg.ends_with('%') => number(arg)? / 100.0,
+        Some(arg) => number(arg)?,
+        None => 1.0,
+    }
+    .clamp(0.0, 1.0);
+
+    match name {
+        "rgb" | "rgba" => {
+            let channel = |arg: &str| -> Option<u8> {
+                let value = number(arg)?;
+                let value = if arg.ends_with('%') {
+                    value * 2.55
+                } else {
+                    value
+                };
+                Some(value.round().clamp(0.0, 255.0) as u8)
+            };
+            Some(Color {
+                a: alpha,
+                ..Color::rgb(channel(args[0])?, channel(args[1])?, channel(args[2])?)
+            })
+        }
+        "hsl" | "hsla" => {
+            let hue = number(args[0].trim_end_matches("deg"))?;
+            Some(Color::from_hsl(
+                hue,
+                number(args[1])?,
+                number(args[2])?,
+                alpha,
+            ))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(Color::parse("#ECECFF"), Some(Color::rgb(236, 236, 255)));
+        assert_eq!(Color::parse("#fff"), Some(Color::rgb(255, 255, 255)));
+        assert_eq!(Color::parse("#00000080").map(|c| c.a), Some(128.0 / 255.0));
+        assert_eq!(Color::parse("#12345"), None);
+        assert_eq!(Color::parse("#ggg"), None);
+    }
+
+    #[test]
+    fn test_parse_functions_and_names() {
+        assert_eq!(
+            Color::parse("rgb(10, 20, 30)"),
+            Some(Color::rgb(10, 20, 30))
+        );
+        assert_eq!(Color::parse("rgba(10,20,30,0.5)").map(|c| c.a), Some(0.5));
+        assert_eq!(
+            Color::parse("hsl(240, 100%, 50%)"),
+            Some(Color::rgb(0, 0, 255))
+        );
+        assert_eq!(Color::parse("White"), Some(Color::rgb(255, 255, 255)));
+        assert_eq!(Color::parse("transparent").map(|c| c.a), Some(0.0));
+        assert_eq!(Color::parse("not-a-colour"), None);
+        assert_eq!(Color::parse("\"/><script>"), None);
+    }
+
+    #[test]
+    fn test_hsl_round_trip() {
+        let color = Color::rgb(236, 236, 255);
+        let (h, s, l) = color.to_hsl();
+        assert_eq!(Color::from_hsl(h, s, l, 1.0), color);
+    }
+
+    #[test]
+    fn test_adjust() {
+        let red = Color::rgb(255, 0, 0);
+        assert_eq!(red.adjust(120.0, 0.0, 0.0), Color::rgb(0, 255, 0));
+        assert_eq!(red.adjust(-120.0, 0.0, 0.0), Color::rgb(0, 0, 255));
+        // Mermaid's light-mode border rule: { s: -40, l: -10 }
+        let white = Color::rgb(255, 255, 255);
+        assert_eq!(white.adjust(0.0, -40.0, -10.0).to_string(), "#e6e6e6");
+    }
+
+    #[test]
+    fn test_lighten_darken_invert() {
+        let gray = Color::rgb(128, 128, 128);
+        assert!(gray.lighten(10.0).r > gray.r);
+        assert!(gray.darken(10.0).r < gray.r);
+        assert_eq!(Color::rgb(255, 255, 255).invert(), Color::rgb(0, 0, 0));
+        assert!(Color::rgb(0, 0, 0).is_dark());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Color::rgb(255, 0, 16).to_string(), "#ff0010");
+        let half = Color {
+            a: 0.5,
+            ..Color::rgb(0, 0, 0)
+        };
+        assert_eq!(half.to_string(), "rgba(0, 0, 0, 0.5)");
+    }
+}
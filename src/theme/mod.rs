@@ -0,0 +1,598 @@
+//! Themes and colour palettes
+//!
+//! A [`Theme`] selects the [`ThemeColors`] used to draw a diagram. Besides
+//! the light and dark palettes and Mermaid's `default`, `neutral`, `forest`
+//! and `base` themes, [`Theme::Custom`] accepts any palette, built
+//! either directly with the `with_*` methods or from Mermaid
+//! `themeVariables` via [`ThemeVariables`].
+
+mod builtin;
+mod color;
+mod variables;
+
+pub use color::Color;
+pub use variables::{ThemeVariables, THEME_VARIABLE_KEYS};
+
+use crate::error::{RenderError, RenderResult};
+
+/// Theme for rendering diagrams
+///
+/// Controls the overall color scheme of the rendered SVG.
+// Themes are built once per render, so boxing the custom palette buys nothing
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Theme {
+    /// Light theme with white background and dark text
+    #[default]
+    Light,
+    /// Dark theme with dark background and light text
+    Dark,
+    /// Mermaid's `default` theme
+    Default,
+    /// Mermaid's `neutral` theme
+    Neutral,
+    /// Mermaid's `forest` theme
+    Forest,
+    /// Mermaid's `base` theme
+    Base,
+    /// Light and dark palettes switched by the viewer's colour scheme
+    ///
+    /// Colours are emitted as CSS classes backed by custom properties, with
+    /// a `@media (prefers-color-scheme: dark)` block selecting the dark
+    /// palette.
+    Auto,
+    /// User-supplied palette
+    Custom(ThemeColors),
+}
+
+impl Theme {
+    /// Returns the color palette of the theme
+    ///
+    /// For [`Theme::Auto`] this is the light palette.
+    pub fn colors(&self) -> ThemeColors {
+        match self {
+            Theme::Light | Theme::Auto => ThemeColors::light(),
+            Theme::Dark => ThemeColors::dark(),
+            Theme::Default => ThemeColors::mermaid_default(),
+            Theme::Neutral => ThemeColors::neutral(),
+            Theme::Forest => ThemeColors::forest(),
+            Theme::Base => ThemeColors::base(),
+            Theme::Custom(colors) => colors.clone(),
+        }
+    }
+
+    /// Looks up a built-in theme by name (case-insensitive)
+    ///
+    /// Accepts `light`, `dark`, `auto` and Mermaid's `default`,
+    /// `neutral`, `forest` and `base`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "light" => Some(Theme::Light),
+            "dark" => Some(Theme::Dark),
+            "default" => Some(Theme::Default),
+            "neutral" => Some(Theme::Neutral),
+            "forest" => Some(Theme::Forest),
+            "base" => Some(Theme::Base),
+            "auto" => Some(Theme::Auto),
+            _ => None,
+        }
+    }
+
+    /// Returns the theme name accepted by [`Theme::from_name`]
+    ///
+    /// Custom palettes are reported as `custom`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+            Theme::Default => "default",
+            Theme::Neutral => "neutral",
+            Theme::Forest => "forest",
+            Theme::Base => "base",
+            Theme::Auto => "auto",
+            Theme::Custom(_) => "custom",
+        }
+    }
+}
+
+/// A colour slot of [`ThemeColors`]
+///
+/// Roles name colours independently of their value, which lets adaptive
+/// themes refer to them through CSS classes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorRole {
+    /// [`ThemeColors::background`]
+    Background,
+    /// [`ThemeColors::text`]
+    Text,
+    /// [`ThemeColors::line`]
+    Line,
+    /// [`ThemeColors::participant_bg`]
+    ParticipantBg,
+    /// [`ThemeColors::participant_border`]
+    ParticipantBorder,
+    /// [`ThemeColors::participant_text`]
+    ParticipantText,
+    /// [`ThemeColors::lifeline`]
+    Lifeline,
+    /// [`ThemeColors::message_text`]
+    MessageText,
+    /// [`ThemeColors::note_bg`]
+    NoteBg,
+    /// [`ThemeColors::note_border`]
+    NoteBorder,
+    /// [`ThemeColors::note_text`]
+    NoteText,
+    /// [`ThemeColors::activation_bg`]
+    ActivationBg,
+    /// [`ThemeColors::activation_border`]
+    ActivationBorder,
+    /// [`ThemeColors::frame_border`]
+    FrameBorder,
+    /// [`ThemeColors::label_bg`]
+    LabelBg,
+    /// [`ThemeColors::label_text`]
+    LabelText,
+    /// [`ThemeColors::frame_text`]
+    FrameText,
+    /// [`ThemeColors::sequence_number`]
+    SequenceNumber,
+}
+
+impl ColorRole {
+    /// Every role, in declaration order
+    pub const ALL: [ColorRole; 18] = [
+        ColorRole::Background,
+        ColorRole::Text,
+        ColorRole::Line,
+        ColorRole::ParticipantBg,
+        ColorRole::ParticipantBorder,
+        ColorRole::ParticipantText,
+        ColorRole::Lifeline,
+        ColorRole::MessageText,
+        ColorRole::NoteBg,
+        ColorRole::NoteBorder,
+        ColorRole::NoteText,
+        ColorRole::ActivationBg,
+        ColorRole::ActivationBorder,
+        ColorRole::FrameBorder,
+        ColorRole::LabelBg,
+        ColorRole::LabelText,
+        ColorRole::FrameText,
+        ColorRole::SequenceNumber,
+    ];
+
+    /// Returns the kebab-case name used in CSS class and property names
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorRole::Background => "background",
+            ColorRole::Text => "text",
+            ColorRole::Line => "line",
+            ColorRole::ParticipantBg => "participant-bg",
+            ColorRole::ParticipantBorder => "participant-border",
+            ColorRole::ParticipantText => "participant-text",
+            ColorRole::Lifeline => "lifeline",
+            ColorRole::MessageText => "message-text",
+            ColorRole::NoteBg => "note-bg",
+            ColorRole::NoteBorder => "note-border",
+            ColorRole::NoteText => "note-text",
+            ColorRole::ActivationBg => "activation-bg",
+            ColorRole::ActivationBorder => "activation-border",
+            ColorRole::FrameBorder => "frame-border",
+            ColorRole::LabelBg => "label-bg",
+            ColorRole::LabelText => "label-text",
+            ColorRole::FrameText => "frame-text",
+            ColorRole::SequenceNumber => "sequence-number",
+        }
+    }
+}
+
+/// Colors used for rendering a specific theme
+///
+/// Contains all color values needed to render diagram elements consistently,
+/// plus the stroke widths and corner radius of shapes.
+///
+/// ```rust
+/// use mermaid_svg_render::{RenderOptions, ThemeColors};
+///
+/// let brand = ThemeColors::light()
+///     .with_participant("#0b3d91", "#072a66", "#ffffff")
+///     .with_line("#072a66")
+///     .with_corner_radius(0.0);
+/// let options = RenderOptions::default().with_colors(brand);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThemeColors {
+    /// Background color of the SVG canvas
+    pub background: String,
+    /// Primary text color
+    pub text: String,
+    /// Color for lines and arrows
+    pub line: String,
+    /// Background color for participant boxes
+    pub participant_bg: String,
+    /// Border color for participant boxes
+    pub participant_border: String,
+    /// Text color for participant names
+    pub participant_text: String,
+    /// Color of participant lifelines
+    pub lifeline: String,
+    /// Text color for message labels
+    pub message_text: String,
+    /// Background color for notes
+    pub note_bg: String,
+    /// Border color for notes
+    pub note_border: String,
+    /// Text color for notes
+    pub note_text: String,
+    /// Background color for activation bars
+    pub activation_bg: String,
+    /// Border color for activation bars
+    pub activation_border: String,
+    /// Border color for block frames (loop, alt, opt, ...)
+    pub frame_border: String,
+    /// Background color of the frame label box
+    pub label_bg: String,
+    /// Text color of the frame label box
+    pub label_text: String,
+    /// Text color for frame conditions
+    pub frame_text: String,
+    /// Text color of autonumber sequence numbers
+    pub sequence_number: String,
+    /// Series colours for elements that cycle through a palette
+    pub palette: Vec<String>,
+    /// Stroke width of lines and arrows
+    pub line_width: f64,
+    /// Stroke width of box borders
+    pub border_width: f64,
+    /// Corner radius of boxes
+    pub corner_radius: f64,
+}
+
+impl ThemeColors {
+    /// Returns the light theme color palette
+    pub fn light() -> Self {
+        Self {
+            background: "#ffffff".to_string(),
+            text: "#333333".to_string(),
+            line: "#333333".to_string(),
+            participant_bg: "#ecf0f1".to_string(),
+            participant_border: "#333333".to_string(),
+            participant_text: "#333333".to_string(),
+            lifeline: "#333333".to_string(),
+            message_text: "#333333".to_string(),
+            note_bg: "#fff5ad".to_string(),
+            note_border: "#aaaa33".to_string(),
+            note_text: "#333333".to_string(),
+            activation_bg: "#f4f4f4".to_string(),
+            activation_border: "#666666".to_string(),
+            frame_border: "#333333".to_string(),
+            label_bg: "#ecf0f1".to_string(),
+            label_text: "#333333".to_string(),
+            frame_text: "#333333".to_string(),
+            sequence_number: "#ffffff".to_string(),
+            palette: palette(&[
+                "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1",
+                "#ff9da7", "#9c755f", "#bab0ac", "#86bcb6", "#d4a6c8",
+            ]),
+            line_width: 1.0,
+            border_width: 1.0,
+            corner_radius: 4.0,
+        }
+    }
+
+    /// Returns the dark theme color palette
+    pub fn dark() -> Self {
+        Self {
+            background: "#1a1a2e".to_string(),
+            text: "#eaeaea".to_string(),
+            line: "#eaeaea".to_string(),
+            participant_bg: "#16213e".to_string(),
+            participant_border: "#eaeaea".to_string(),
+            participant_text: "#eaeaea".to_string(),
+            lifeline: "#eaeaea".to_string(),
+            message_text: "#eaeaea".to_string(),
+            note_bg: "#3d3d5c".to_string(),
+            note_border: "#eaeaea".to_string(),
+            note_text: "#eaeaea".to_string(),
+            activation_bg: "#2a2a4a".to_string(),
+            activation_border: "#eaeaea".to_string(),
+            frame_border: "#eaeaea".to_string(),
+            label_bg: "#16213e".to_string(),
+            label_text: "#eaeaea".to_string(),
+            frame_text: "#eaeaea".to_string(),
+            sequence_number: "#1a1a2e".to_string(),
+            palette: palette(&[
+                "#8cb3e0", "#ffb366", "#ff8a8c", "#9fd9d4", "#8fd17f", "#ffe07a", "#d6a6cc",
+                "#ffc2c9", "#c9a58c", "#d9d0cc", "#a9d6d1", "#ebc7e0",
+            ]),
+            line_width: 1.0,
+            border_width: 1.0,
+            corner_radius: 4.0,
+        }
+    }
+
+    /// Sets the canvas background (builder pattern)
+    pub fn with_background(mut self, background: impl Into<String>) -> Self {
+        self.background = background.into();
+        self
+    }
+
+    /// Sets the text color of all labels (builder pattern)
+    pub fn with_text(mut self, text: impl Into<String>) -> Self {
+        let text = text.into();
+        self.participant_text = text.clone();
+        self.message_text = text.clone();
+        self.note_text = text.clone();
+        self.label_text = text.clone();
+        self.frame_text = text.clone();
+        self.text = text;
+        self
+    }
+
+    /// Sets the color of arrows and lifelines (builder pattern)
+    pub fn with_line(mut self, line: impl Into<String>) -> Self {
+        let line = line.into();
+        self.lifeline = line.clone();
+        self.line = line;
+        self
+    }
+
+    /// Sets participant box background, border and text colors (builder pattern)
+    pub fn with_participant(
+        mut self,
+        background: impl Into<String>,
+        border: impl Into<String>,
+        text: impl Into<String>,
+    ) -> Self {
+        self.participant_bg = background.into();
+        self.participant_border = border.into();
+        self.participant_text = text.into();
+        self
+    }
+
+    /// Sets the lifeline color (builder pattern)
+    pub fn with_lifeline(mut self, lifeline: impl Into<String>) -> Self {
+        self.lifeline = lifeline.into();
+        self
+    }
+
+    /// Sets the message label color (builder pattern)
+    pub fn with_message_text(mut self, text: impl Into<String>) -> Self {
+        self.message_text = text.into();
+        self
+    }
+
+    /// Sets note background, border and text colors (builder pattern)
+    pub fn with_note(
+        mut self,
+        background: impl Into<String>,
+        border: impl Into<String>,
+        text: impl Into<String>,
+    ) -> Self {
+        self.note_bg = background.into();
+        self.note_border = border.into();
+        self.note_text = text.into();
+        self
+    }
+
+    /// Sets activation bar background and border colors (builder pattern)
+    pub fn with_activation(
+        mut self,
+        background: impl Into<String>,
+        border: impl Into<String>,
+    ) -> Self {
+        self.activation_bg = background.into();
+        self.activation_border = border.into();
+        self
+    }
+
+    /// Sets frame border, label box and condition text colors (builder pattern)
+    pub fn with_frame(
+        mut self,
+        border: impl Into<String>,
+        label_bg: impl Into<String>,
+        label_text: impl Into<String>,
+        text: impl Into<String>,
+    ) -> Self {
+        self.frame_border = border.into();
+        self.label_bg = label_bg.into();
+        self.label_text = label_text.into();
+        self.frame_text = text.into();
+        self
+    }
+
+    /// Sets the series palette (builder pattern)
+    pub fn with_palette<I, S>(mut self, palette: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.palette = palette.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the stroke width of lines and arrows (builder pattern)
+    pub fn with_line_width(mut self, width: f64) -> Self {
+        self.line_width = width;
+        self
+    }
+
+    /// Sets the stroke width of box borders (builder pattern)
+    pub fn with_border_width(mut self, width: f64) -> Self {
+        self.border_width = width;
+        self
+    }
+
+    /// Sets the corner radius of boxes (builder pattern)
+    pub fn with_corner_radius(mut self, radius: f64) -> Self {
+        self.corner_radius = radius;
+        self
+    }
+
+    /// Returns the colour assigned to a role
+    pub fn get(&self, role: ColorRole) -> &str {
+        match role {
+            ColorRole::Background => &self.background,
+            ColorRole::Text => &self.text,
+            ColorRole::Line => &self.line,
+            ColorRole::ParticipantBg => &self.participant_bg,
+            ColorRole::ParticipantBorder => &self.participant_border,
+            ColorRole::ParticipantText => &self.participant_text,
+            ColorRole::Lifeline => &self.lifeline,
+            ColorRole::MessageText => &self.message_text,
+            ColorRole::NoteBg => &self.note_bg,
+            ColorRole::NoteBorder => &self.note_border,
+            ColorRole::NoteText => &self.note_text,
+            ColorRole::ActivationBg => &self.activation_bg,
+            ColorRole::ActivationBorder => &self.activation_border,
+            ColorRole::FrameBorder => &self.frame_border,
+            ColorRole::LabelBg => &self.label_bg,
+            ColorRole::LabelText => &self.label_text,
+            ColorRole::FrameText => &self.frame_text,
+            ColorRole::SequenceNumber => &self.sequence_number,
+        }
+    }
+
+    /// Returns the palette colour for a series index, cycling through the palette
+    pub fn palette_color(&self, index: usize) -> Option<&str> {
+        if self.palette.is_empty() {
+            return None;
+        }
+        Some(&self.palette[index % self.palette.len()])
+    }
+
+    /// Checks that every color is a valid CSS color and sizes are non-negative
+    ///
+    /// Returns [`RenderError::InvalidOptions`] naming the offending field.
+    pub fn validate(&self) -> RenderResult<()> {
+        let colors = [
+            ("background", &self.background),
+            ("text", &self.text),
+            ("line", &self.line),
+            ("participant_bg", &self.participant_bg),
+            ("participant_border", &self.participant_border),
+            ("participant_text", &self.participant_text),
+            ("lifeline", &self.lifeline),
+            ("message_text", &self.message_text),
+            ("note_bg", &self.note_bg),
+            ("note_border", &self.note_border),
+            ("note_text", &self.note_text),
+            ("activation_bg", &self.activation_bg),
+            ("activation_border", &self.activation_border),
+            ("frame_border", &self.frame_border),
+            ("label_bg", &self.label_bg),
+            ("label_text", &self.label_text),
+            ("frame_text", &self.frame_text),
+            ("sequence_number", &self.sequence_number),
+        ];
+        let palette = self.palette.iter().map(|color| ("palette", color));
+
+        for (name, value) in colors.into_iter().chain(palette) {
+            if !is_css_color(value) {
+                return Err(RenderError::InvalidOptions(format!(
+                    "invalid {} color '{}'",
+                    name, value
+                )));
+            }
+        }
+
+        let sizes = [
+            ("line_width", self.line_width),
+            ("border_width", self.border_width),
+            ("corner_radius", self.corner_radius),
+        ];
+        for (name, value) in sizes {
+            if !value.is_finite() || value < 0.0 {
+                return Err(RenderError::InvalidOptions(format!(
+                    "{} must be a non-negative number",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Accepts parseable colours plus bare CSS keywords (`currentColor`, named colours)
+fn is_css_color(value: &str) -> bool {
+    Color::parse(value).is_some()
+        || (!value.is_empty() && value.bytes().all(|b| b.is_ascii_alphabetic()))
+}
+
+/// Builds an owned palette from string literals
+fn palette(colors: &[&str]) -> Vec<String> {
+    colors.iter().map(|color| color.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_palettes_are_valid() {
+        assert!(ThemeColors::light().validate().is_ok());
+        assert!(ThemeColors::dark().validate().is_ok());
+    }
+
+    #[test]
+    fn test_theme_names_round_trip() {
+        for theme in [
+            Theme::Light,
+            Theme::Dark,
+            Theme::Default,
+            Theme::Neutral,
+            Theme::Forest,
+            Theme::Base,
+            Theme::Auto,
+        ] {
+            assert_eq!(Theme::from_name(theme.name()), Some(theme));
+        }
+        assert_eq!(Theme::from_name("Forest"), Some(Theme::Forest));
+        assert_eq!(Theme::from_name("custom"), None);
+    }
+
+    #[test]
+    fn test_custom_theme_colors() {
+        let colors = ThemeColors::light().with_background("#000000");
+        let theme = Theme::Custom(colors.clone());
+        assert_eq!(theme.colors(), colors);
+    }
+
+    #[test]
+    fn test_with_text_sets_all_labels() {
+        let colors = ThemeColors::light().with_text("#123456");
+        assert_eq!(colors.participant_text, "#123456");
+        assert_eq!(colors.message_text, "#123456");
+        assert_eq!(colors.note_text, "#123456");
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid_values() {
+        let injected = ThemeColors::light().with_line("red\" onload=\"x");
+        assert!(matches!(
+            injected.validate(),
+            Err(RenderError::InvalidOptions(_))
+        ));
+        assert!(ThemeColors::light()
+            .with_line_width(-1.0)
+            .validate()
+            .is_err());
+        assert!(ThemeColors::light()
+            .with_line("currentColor")
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_palette_color_cycles() {
+        let colors = ThemeColors::light().with_palette(["#111111", "#222222"]);
+        assert_eq!(colors.palette_color(3), Some("#222222"));
+        assert_eq!(
+            ThemeColors::light()
+                .with_palette(Vec::<String>::new())
+                .palette_color(0),
+            None
+        );
+    }
+}
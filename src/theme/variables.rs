@@ -0,0 +1,366 @@
+//! Mermaid `themeVariables` support
+//!
+//! Maps Mermaid's theme variable keys onto [`ThemeColors`]. Unset colours
+//! are derived from the ones that are set using the rules of Mermaid's
+//! `base` theme, so setting only `primaryColor` yields a coherent palette.
+
+use std::collections::BTreeMap;
+
+use crate::error::{RenderError, RenderResult};
+
+use super::color::Color;
+use super::ThemeColors;
+
+/// Theme variable keys understood by [`ThemeVariables::to_colors`]
+pub const THEME_VARIABLE_KEYS: &[&str] = &[
+    "darkMode",
+    "background",
+    "fontFamily",
+    "fontSize",
+    "primaryColor",
+    "primaryTextColor",
+    "primaryBorderColor",
+    "secondaryColor",
+    "secondaryTextColor",
+    "secondaryBorderColor",
+    "tertiaryColor",
+    "tertiaryTextColor",
+    "tertiaryBorderColor",
+    "mainBkg",
+    "textColor",
+    "lineColor",
+    "noteBkgColor",
+    "noteTextColor",
+    "noteBorderColor",
+    "actorBkg",
+    "actorBorder",
+    "actorTextColor",
+    "actorLineColor",
+    "signalColor",
+    "signalTextColor",
+    "labelBoxBkgColor",
+    "labelBoxBorderColor",
+    "labelTextColor",
+    "loopTextColor",
+    "activationBkgColor",
+    "activationBorderColor",
+    "sequenceNumberColor",
+    "strokeWidth",
+    "cScale0",
+    "cScale1",
+    "cScale2",
+    "cScale3",
+    "cScale4",
+    "cScale5",
+    "cScale6",
+    "cScale7",
+    "cScale8",
+    "cScale9",
+    "cScale10",
+    "cScale11",
+];
+
+/// Number of series colours (`cScale0` to `cScale11`)
+const PALETTE_SIZE: usize = 12;
+
+/// Hue rotations of the primary colour for `cScale3` onwards
+const PALETTE_HUES: [f64; PALETTE_SIZE - 3] =
+    [30.0, 60.0, 90.0, 120.0, 150.0, 210.0, 270.0, 300.0, 330.0];
+
+/// Mermaid `themeVariables` as key/value pairs
+///
+/// ```rust
+/// use mermaid_svg_render::theme::ThemeVariables;
+/// use mermaid_svg_render::RenderOptions;
+///
+/// let variables = ThemeVariables::new()
+///     .with("primaryColor", "#0b3d91")
+///     .with("primaryTextColor", "#ffffff")
+///     .with("lineColor", "#072a66");
+/// let options = RenderOptions::default().with_theme_variables(&variables)?;
+/// # Ok::<(), mermaid_svg_render::RenderError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThemeVariables {
+    values: BTreeMap<String, String>,
+}
+
+impl ThemeVariables {
+    /// Creates an empty set of variables
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a variable
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.values.insert(key.into(), value.into());
+    }
+
+    /// Sets a variable (builder pattern)
+    pub fn with(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.set(key, value);
+        self
+    }
+
+    /// Returns the value of a variable
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Iterates over all variables in key order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Returns the keys that are not in [`THEME_VARIABLE_KEYS`]
+    pub fn unknown_keys(&self) -> Vec<&str> {
+        self.values
+            .keys()
+            .map(String::as_str)
+            .filter(|key| !THEME_VARIABLE_KEYS.contains(key))
+            .collect()
+    }
+
+    /// Returns `true` if `darkMode` is set to `true`
+    pub fn is_dark_mode(&self) -> bool {
+        self.get("darkMode")
+            .is_some_and(|v| v.trim().eq_ignore_ascii_case("true"))
+    }
+
+    /// Resolves the variables into a palette
+    ///
+    /// Colours that are not set are derived like Mermaid's `base` theme:
+    /// the secondary and tertiary colours rotate the primary hue, borders
+    /// are desaturated and darkened (lightened in dark mode), lines invert
+    /// the background, and sequence diagram colours fall back to the
+    /// primary colours.
+    ///
+    /// Returns [`RenderError::InvalidOptions`] for unparseable colours or
+    /// stroke widths. Keys outside [`THEME_VARIABLE_KEYS`] are ignored.
+    pub fn to_colors(&self) -> RenderResult<ThemeColors> {
+        let dark = self.is_dark_mode();
+        let color = |key: &str| -> RenderResult<Option<Color>> {
+            match self.get(key) {
+                Some(value) => Color::parse(value).map(Some).ok_or_else(|| {
+                    RenderError::InvalidOptions(format!(
+                        "invalid colour '{}' for themeVariables.{}",
+                        value, key
+                    ))
+                }),
+                None => Ok(None),
+            }
+        };
+        let border = |color: Color| {
+            if dark {
+                color.adjust(0.0, -40.0, 10.0)
+            } else {
+                color.adjust(0.0, -40.0, -10.0)
+            }
+        };
+
+        let background = color("background")?.unwrap_or(Color::rgb(255, 255, 255));
+        let primary = color("primaryColor")?.unwrap_or(Color::rgb(0xff, 0xf4, 0xdd));
+        let primary_text = color("primaryTextColor")?.unwrap_or(if dark {
+            Color::rgb(0xee, 0xee, 0xee)
+        } else {
+            Color::rgb(0x33, 0x33, 0x33)
+        });
+        let secondary = color("secondaryColor")?.unwrap_or(primary.adjust(-120.0, 0.0, 0.0));
+        let tertiary = color("tertiaryColor")?.unwrap_or(primary.adjust(180.0, 0.0, 5.0));
+        let primary_border = color("primaryBorderColor")?.unwrap_or(border(primary));
+
+        let note_bg = color("noteBkgColor")?.unwrap_or(Color::rgb(0xff, 0xf5, 0xad));
+        let note_border = color("noteBorderColor")?.unwrap_or(border(note_bg));
+        let note_text = color("noteTextColor")?.unwrap_or(Color::rgb(0x33, 0x33, 0x33));
+
+        let line = color("lineColor")?.unwrap_or(background.invert());
+        let text = color("textColor")?.unwrap_or(primary_text);
+        let main_bkg = color("mainBkg")?.unwrap_or(primary);
+
+        let actor_border = color("actorBorder")?.unwrap_or(primary_border);
+        let actor_bkg = color("actorBkg")?.unwrap_or(main_bkg);
+        let actor_text = color("actorTextColor")?.unwrap_or(primary_text);
+        let actor_line = color("actorLineColor")?.unwrap_or(actor_border);
+        let signal = color("signalColor")?.unwrap_or(text);
+        let signal_text = color("signalTextColor")?.unwrap_or(text);
+        let label_box_bkg = color("labelBoxBkgColor")?.unwrap_or(actor_bkg);
+        let label_box_border = color("labelBoxBorderColor")?.unwrap_or(actor_border);
+        let label_text = color("labelTextColor")?.unwrap_or(actor_text);
+        let loop_text = color("loopTextColor")?.unwrap_or(actor_text);
+        let activation_border = color("activationBorderColor")?.unwrap_or(secondary.darken(10.0));
+        let activation_bkg = color("activationBkgColor")?.unwrap_or(secondary);
+        let sequence_number = color("sequenceNumberColor")?.unwrap_or(line.invert());
+
+        let mut palette = Vec::with_capacity(PALETTE_SIZE);
+        for index in 0..PALETTE_SIZE {
+            let scale = match color(&format!("cScale{}", index))? {
+                Some(scale) => scale,
+                None => {
+                    let derived = match index {
+                        0 => primary,
+                        1 => secondary,
+                        2 => tertiary,
+                        _ => primary.adjust(PALETTE_HUES[index - 3], 0.0, 0.0),
+                    };
+                    derived.darken(if dark { 75.0 } else { 25.0 })
+                }
+            };
+            palette.push(scale.to_string());
+        }
+
+        let stroke_width = match self.get("strokeWidth") {
+            Some(value) => value
+                .trim()
+                .trim_end_matches("px")
+                .parse::<f64>()
+                .ok()
+                .filter(|w| w.is_finite() && *w >= 0.0)
+                .ok_or_else(|| {
+                    RenderError::InvalidOptions(format!(
+                        "invalid themeVariables.strokeWidth '{}'",
+                        value
+                    ))
+                })?,
+            None => 1.0,
+        };
+
+        Ok(ThemeColors {
+            background: background.to_string(),
+            text: text.to_string(),
+            line: signal.to_string(),
+            participant_bg: actor_bkg.to_string(),
+            participant_border: actor_border.to_string(),
+            participant_text: actor_text.to_string(),
+            lifeline: actor_line.to_string(),
+            message_text: signal_text.to_string(),
+            note_bg: note_bg.to_string(),
+            note_border: note_border.to_string(),
+            note_text: note_text.to_string(),
+            activation_bg: activation_bkg.to_string(),
+            activation_border: activation_border.to_string(),
+            frame_border: label_box_border.to_string(),
+            label_bg: label_box_bkg.to_string(),
+            label_text: label_text.to_string(),
+            frame_text: loop_text.to_string(),
+            sequence_number: sequence_number.to_string(),
+            palette,
+            line_width: stroke_width,
+            border_width: stroke_width,
+            corner_radius: 3.0,
+        })
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for ThemeVariables {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut variables = Self::new();
+        for (key, value) in iter {
+            variables.set(key, value);
+        }
+        variables
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explicit_keys_map_to_fields() {
+        let colors = ThemeVariables::new()
+            .with("actorBkg", "#112233")
+            .with("actorBorder", "#445566")
+            .with("noteBkgColor", "#778899")
+            .with("signalColor", "red")
+            .with("strokeWidth", "2px")
+            .to_colors()
+            .unwrap();
+
+        assert_eq!(colors.participant_bg, "#112233");
+        assert_eq!(colors.participant_border, "#445566");
+        assert_eq!(colors.note_bg, "#778899");
+        assert_eq!(colors.line, "#ff0000");
+        assert_eq!(colors.line_width, 2.0);
+    }
+
+    #[test]
+    fn test_sequence_colours_derive_from_primary() {
+        let colors = ThemeVariables::new()
+            .with("primaryColor", "#0b3d91")
+            .with("primaryTextColor", "#ffffff")
+            .to_colors()
+            .unwrap();
+
+        assert_eq!(colors.participant_bg, "#0b3d91");
+        assert_eq!(colors.participant_text, "#ffffff");
+        assert_eq!(colors.label_bg, "#0b3d91");
+        // Border: desaturated and darkened primary
+        let border = Color::parse("#0b3d91").unwrap().adjust(0.0, -40.0, -10.0);
+        assert_eq!(colors.participant_border, border.to_string());
+        assert_eq!(colors.lifeline, colors.participant_border);
+        // Activation uses the secondary colour (primary hue rotated by -120)
+        let secondary = Color::parse("#0b3d91").unwrap().adjust(-120.0, 0.0, 0.0);
+        assert_eq!(colors.activation_bg, secondary.to_string());
+    }
+
+    #[test]
+    fn test_line_inverts_background() {
+        let colors = ThemeVariables::new()
+            .with("background", "#000000")
+            .with("lineColor", "#00ff00")
+            .to_colors()
+            .unwrap();
+        assert_eq!(colors.sequence_number, "#ff00ff");
+
+        let defaults = ThemeVariables::new().to_colors().unwrap();
+        assert_eq!(defaults.background, "#ffffff");
+        assert_eq!(defaults.sequence_number, "#ffffff");
+    }
+
+    #[test]
+    fn test_dark_mode_lightens_borders() {
+        let light = ThemeVariables::new()
+            .with("primaryColor", "#336699")
+            .to_colors()
+            .unwrap();
+        let dark = ThemeVariables::new()
+            .with("primaryColor", "#336699")
+            .with("darkMode", "true")
+            .to_colors()
+            .unwrap();
+
+        let lightness = |c: &str| Color::parse(c).unwrap().to_hsl().2;
+        assert!(lightness(&dark.participant_border) > lightness(&light.participant_border));
+        assert_eq!(dark.participant_text, "#eeeeee");
+    }
+
+    #[test]
+    fn test_palette_has_twelve_entries() {
+        let colors = ThemeVariables::new()
+            .with("cScale4", "#abcdef")
+            .to_colors()
+            .unwrap();
+        assert_eq!(colors.palette.len(), 12);
+        assert_eq!(colors.palette[4], "#abcdef");
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        let result = ThemeVariables::new()
+            .with("primaryColor", "nope!")
+            .to_colors();
+        assert!(matches!(result, Err(RenderError::InvalidOptions(_))));
+        let result = ThemeVariables::new()
+            .with("strokeWidth", "thick")
+            .to_colors();
+        assert!(matches!(result, Err(RenderError::InvalidOptions(_))));
+    }
+
+    #[test]
+    fn test_unknown_keys() {
+        let variables: ThemeVariables = [("primaryColor", "#fff"), ("fancyColor", "#000")]
+            .into_iter()
+            .collect();
+        assert_eq!(variables.unknown_keys(), vec!["fancyColor"]);
+    }
+}
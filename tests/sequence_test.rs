@@ -1,6 +1,12 @@
 //! Integration tests for sequence diagram rendering
 
-use mermaid_svg_render::{render_sequence_diagram, RenderOptions, Theme};
+use mermaid_svg_render::metadata::extract_source;
+use mermaid_svg_render::svg::number::NUMERIC_ATTRIBUTES;
+use mermaid_svg_render::svg::{create_self_loop, Element, LineStyle, Node, Paint};
+use mermaid_svg_render::{
+    render_sequence_diagram, render_sequence_diagram_with_warnings, Align, FontOverride,
+    RenderError, RenderOptions, SequenceConfig, Theme, ThemeColors, ThemeVariables,
+};
 use std::env;
 use std::fs;
 use std::path::Path;
@@ -254,8 +260,15 @@ sequenceDiagram
     maybe_save_svg(&svg, "light", "arrow_bidirectional_solid");
 
     assert!(svg.contains("Bidirectional solid"));
-    // Should have two arrowheads (polygons)
-    let polygon_count = svg.matches("<polygon").count();
+    // Both ends reference the shared arrowhead marker
+    assert!(svg.contains(
+        r##"marker-start="url(#marker-arrowhead-333333)" marker-end="url(#marker-arrowhead-333333)""##
+    ));
+
+    // Inline fallback draws two arrowheads (polygons)
+    let inline =
+        render_sequence_diagram(input, &RenderOptions::default().with_inline_arrowheads()).unwrap();
+    let polygon_count = inline.matches("<polygon").count();
     assert!(
         polygon_count >= 2,
         "Bidirectional arrow should have 2 arrowheads, found {}",
@@ -277,6 +290,90 @@ sequenceDiagram
     assert!(svg.contains("stroke-dasharray")); // dotted line
 }
 
+#[test]
+fn test_arrowheads_use_shared_markers() {
+    let input = r#"
+sequenceDiagram
+    A->>B: One
+    B-->>A: Two
+    A-)B: Async
+    A-xB: Cross
+    B->>B: Self
+    A->B: Open
+"#;
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    maybe_save_svg(&svg, "light", "arrow_markers");
+
+    // One definition per end style, shared by all arrows
+    assert_eq!(svg.matches("<defs>").count(), 1);
+    assert_eq!(svg.matches("<marker ").count(), 3);
+    assert_eq!(svg.matches(r#"id="marker-arrowhead-333333""#).count(), 1);
+    assert_eq!(
+        svg.matches(r##"marker-end="url(#marker-arrowhead-333333)""##)
+            .count(),
+        3
+    );
+    assert!(svg.contains(r##"marker-end="url(#marker-openhead-333333)""##));
+    assert!(svg.contains(r##"marker-end="url(#marker-crosshead-333333)""##));
+    assert!(svg.contains(r#"markerUnits="userSpaceOnUse" orient="auto-start-reverse""#));
+
+    // Heads are only drawn inside the definitions
+    let body = &svg[svg.find("</defs>").unwrap()..];
+    assert!(!body.contains("<polygon"));
+    assert!(!body.contains("arrowhead\""));
+
+    // Open arrows reference no marker
+    let open = &svg[svg.find(r#"data-arrow="solid-open""#).unwrap()..];
+    let open = &open[..open.find("</g>").unwrap()];
+    assert!(!open.contains("marker-"));
+}
+
+#[test]
+fn test_marker_ids_follow_theme_and_prefix() {
+    let input = "sequenceDiagram\n    A->>B: Hi";
+    let dark = render_sequence_diagram(input, &RenderOptions::with_theme(Theme::Dark)).unwrap();
+    let light = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+    let marker_id = |svg: &str| {
+        let start = svg.find(r#"<marker id=""#).unwrap() + 12;
+        svg[start..start + svg[start..].find('"').unwrap()].to_string()
+    };
+
+    // Diagrams with different colours can share a page
+    assert_ne!(marker_id(&dark), marker_id(&light));
+
+    // Also when a style block colours the markers
+    let styled = |theme| {
+        let options = RenderOptions::with_theme(theme).with_style_block();
+        marker_id(&render_sequence_diagram(input, &options).unwrap())
+    };
+    assert_ne!(styled(Theme::Dark), styled(Theme::Light));
+    assert!(styled(Theme::Light).starts_with("marker-arrowhead-styled-"));
+
+    let prefixed =
+        render_sequence_diagram(input, &RenderOptions::default().with_id_prefix("flow")).unwrap();
+    assert!(marker_id(&prefixed).starts_with("flow-marker-arrowhead-"));
+}
+
+#[test]
+fn test_inline_arrowheads_fallback() {
+    let input = r#"
+sequenceDiagram
+    A->>B: One
+    A-xB: Cross
+    B->>B: Self
+"#;
+    let svg =
+        render_sequence_diagram(input, &RenderOptions::default().with_inline_arrowheads()).unwrap();
+
+    maybe_save_svg(&svg, "light", "arrow_inline");
+
+    assert!(!svg.contains("<defs>"));
+    assert!(!svg.contains("marker-"));
+    assert_eq!(svg.matches("<polygon").count(), 2);
+    assert!(svg.contains(r#"class="crosshead""#));
+}
+
 // ============================================
 // Phase 2: Self-Message Tests
 // ============================================
@@ -442,6 +539,137 @@ sequenceDiagram
     assert!(svg.contains("Hi"));
 }
 
+// ============================================
+// Label Wrapping Tests
+// ============================================
+
+/// Extract all text element contents
+fn text_contents(svg: &str) -> Vec<String> {
+    let text_pattern = regex::Regex::new(r"<text[^>]*>([^<]*)</text>").unwrap();
+    text_pattern
+        .captures_iter(svg)
+        .map(|cap| cap[1].to_string())
+        .collect()
+}
+
+const LONG_MESSAGE: &str =
+    "This is a very long message that would otherwise stretch the diagram far too wide";
+
+#[test]
+fn test_wrapping_disabled_by_default() {
+    let input = format!("sequenceDiagram\n    Alice->>Bob: {}\n", LONG_MESSAGE);
+    let svg = render_sequence_diagram(&input, &RenderOptions::default()).unwrap();
+
+    assert!(text_contents(&svg).contains(&LONG_MESSAGE.to_string()));
+}
+
+#[test]
+fn test_wrap_option_wraps_messages() {
+    let input = format!("sequenceDiagram\n    Alice->>Bob: {}\n", LONG_MESSAGE);
+    let unwrapped = render_sequence_diagram(&input, &RenderOptions::default()).unwrap();
+    let svg =
+        render_sequence_diagram(&input, &RenderOptions::default().with_wrapping(150)).unwrap();
+
+    maybe_save_svg(&svg, "light", "wrapped_message");
+
+    let texts = text_contents(&svg);
+    assert!(!texts.contains(&LONG_MESSAGE.to_string()));
+    assert!(texts.iter().any(|t| t.starts_with("This is a very")));
+
+    // Narrower, but taller to make room for the stacked lines
+    let width: u32 = attr_value(&svg, "width").unwrap().parse().unwrap();
+    let height: u32 = attr_value(&svg, "height").unwrap().parse().unwrap();
+    let unwrapped_width: u32 = attr_value(&unwrapped, "width").unwrap().parse().unwrap();
+    let unwrapped_height: u32 = attr_value(&unwrapped, "height").unwrap().parse().unwrap();
+    assert!(width < unwrapped_width);
+    assert!(height > unwrapped_height);
+}
+
+#[test]
+fn test_wrap_prefix() {
+    let input = format!(
+        "sequenceDiagram\n    Alice->>Bob: wrap: {}\n    Bob->>Alice: short\n",
+        LONG_MESSAGE
+    );
+    let svg = render_sequence_diagram(&input, &RenderOptions::default()).unwrap();
+
+    let texts = text_contents(&svg);
+    assert!(!texts.iter().any(|t| t.contains("wrap:")));
+    assert!(!texts.contains(&LONG_MESSAGE.to_string()));
+    assert!(texts.iter().any(|t| t.starts_with("This is a very")));
+}
+
+#[test]
+fn test_nowrap_prefix() {
+    let input = format!(
+        "sequenceDiagram\n    Alice->>Bob: nowrap: {}\n",
+        LONG_MESSAGE
+    );
+    let svg =
+        render_sequence_diagram(&input, &RenderOptions::default().with_wrapping(150)).unwrap();
+
+    assert!(text_contents(&svg).contains(&LONG_MESSAGE.to_string()));
+}
+
+#[test]
+fn test_wrap_directive() {
+    let input = format!(
+        "%%{{wrap}}%%\nsequenceDiagram\n    Alice->>Bob: {}\n",
+        LONG_MESSAGE
+    );
+    let svg = render_sequence_diagram(&input, &RenderOptions::default()).unwrap();
+
+    assert!(!text_contents(&svg).contains(&LONG_MESSAGE.to_string()));
+}
+
+#[test]
+fn test_wrap_participant_names() {
+    let input = r#"
+sequenceDiagram
+    participant A as Authentication and Authorization Gateway Service
+    participant B
+    A->>B: Hello
+"#;
+    let svg = render_sequence_diagram(input, &RenderOptions::default().with_wrapping(120)).unwrap();
+
+    maybe_save_svg(&svg, "light", "wrapped_participant");
+
+    let texts = text_contents(&svg);
+    assert!(!texts.contains(&"Authentication and Authorization Gateway Service".to_string()));
+    // Each wrapped line is drawn in both the top and bottom box
+    assert_eq!(
+        texts
+            .iter()
+            .filter(|t| t.starts_with("Authentication"))
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn test_wrapped_message_lines_do_not_overlap_previous_arrow() {
+    let input = format!(
+        "sequenceDiagram\n    Alice->>Bob: first\n    Bob->>Alice: wrap: {}\n",
+        LONG_MESSAGE
+    );
+    let svg =
+        render_sequence_diagram(&input, &RenderOptions::default().with_wrapping(150)).unwrap();
+
+    let line_pattern =
+        regex::Regex::new(r#"<line x1="[^"]+" y1="([^"]+)" x2="[^"]+" y2="([^"]+)""#).unwrap();
+    // Horizontal message arrows
+    let arrow_ys: Vec<f64> = line_pattern
+        .captures_iter(&svg)
+        .filter(|cap| cap[1] == cap[2])
+        .map(|cap| cap[1].parse().unwrap())
+        .collect();
+    let text_pattern = regex::Regex::new(r#"<text x="[^"]+" y="([^"]+)"[^>]*>This"#).unwrap();
+    let first_line_y: f64 = text_pattern.captures(&svg).unwrap()[1].parse().unwrap();
+
+    // The top line of the wrapped label sits below the first arrow
+    assert!(first_line_y - 14.0 > arrow_ys[0]);
+}
+
 // ============================================
 // Multi-line Participant Name Tests
 // ============================================
@@ -537,6 +765,259 @@ sequenceDiagram
     );
 }
 
+// ============================================
+// Multi-line Message Label Tests
+// ============================================
+
+#[test]
+fn test_multiline_message_labels() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: First line<br>Second line<br/>Third line
+    Bob-->>Alice: Done
+"#;
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    maybe_save_svg(&svg, "light", "multiline_message_labels");
+
+    let texts = text_contents(&svg);
+    for line in ["First line", "Second line", "Third line"] {
+        assert!(texts.contains(&line.to_string()), "missing {:?}", line);
+    }
+    assert!(!svg.contains("&lt;br"));
+}
+
+#[test]
+fn test_multiline_message_lines_stack_upward() {
+    let input = "sequenceDiagram\n    Alice->>Bob: Top<br>Middle<br>Bottom\n";
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    let y_of = |label: &str| -> f64 {
+        let pattern =
+            regex::Regex::new(&format!(r#"<text x="[^"]+" y="([^"]+)"[^>]*>{}<"#, label)).unwrap();
+        pattern.captures(&svg).unwrap()[1].parse().unwrap()
+    };
+    let arrow_y: f64 =
+        regex::Regex::new(r#"<line x1="[^"]+" y1="([^"]+)" x2="[^"]+" y2="([^"]+)""#)
+            .unwrap()
+            .captures_iter(&svg)
+            .find(|cap| cap[1] == cap[2])
+            .map(|cap| cap[1].parse().unwrap())
+            .unwrap();
+
+    assert!(y_of("Top") < y_of("Middle"));
+    assert!(y_of("Middle") < y_of("Bottom"));
+    assert!(y_of("Bottom") < arrow_y);
+}
+
+#[test]
+fn test_multiline_message_grows_row() {
+    let single = "sequenceDiagram\n    Alice->>Bob: One\n    Bob->>Alice: Two\n";
+    let multi = "sequenceDiagram\n    Alice->>Bob: One\n    Bob->>Alice: Two<br>lines\n";
+    let single_svg = render_sequence_diagram(single, &RenderOptions::default()).unwrap();
+    let multi_svg = render_sequence_diagram(multi, &RenderOptions::default()).unwrap();
+
+    let single_height: f64 = attr_value(&single_svg, "height").unwrap().parse().unwrap();
+    let multi_height: f64 = attr_value(&multi_svg, "height").unwrap().parse().unwrap();
+    assert!(multi_height > single_height);
+}
+
+#[test]
+fn test_multiline_self_message_label() {
+    let input = "sequenceDiagram\n    Alice->>Bob: Hello\n    Bob->>Bob: Think<br>hard\n";
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    maybe_save_svg(&svg, "light", "multiline_self_message");
+
+    let texts = text_contents(&svg);
+    assert!(texts.contains(&"Think".to_string()));
+    assert!(texts.contains(&"hard".to_string()));
+    assert!(!svg.contains("&lt;br"));
+}
+
+// ============================================
+// Inline Markdown Tests
+// ============================================
+
+#[test]
+fn test_markdown_bold_italic_code() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: set **user_id** and *retry* with `max_age`
+"#;
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    maybe_save_svg(&svg, "light", "markdown_labels");
+
+    assert!(svg.contains(r#"<tspan font-weight="bold">user_id</tspan>"#));
+    assert!(svg.contains(r#"<tspan font-style="italic">retry</tspan>"#));
+    assert!(svg.contains(r#"<tspan font-family="monospace">max_age</tspan>"#));
+    assert!(!svg.contains("**"));
+    assert!(!svg.contains('`'));
+}
+
+#[test]
+fn test_markdown_in_participant_names() {
+    let input = r#"
+sequenceDiagram
+    participant A as **Gateway**
+    participant B
+    A->>B: Hello
+"#;
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    assert_eq!(
+        svg.matches(r#"<tspan font-weight="bold">Gateway</tspan>"#)
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn test_markdown_link() {
+    let input = "sequenceDiagram\n    Alice->>Bob: see [spec](https://example.com/spec)\n";
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    assert!(svg.contains(
+        r#"<a href="https://example.com/spec"><tspan text-decoration="underline">spec</tspan></a>"#
+    ));
+}
+
+#[test]
+fn test_markdown_unsafe_link_not_rendered() {
+    let input = "sequenceDiagram\n    Alice->>Bob: [x](javascript:alert)\n";
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    assert!(!svg.contains("<a "));
+    assert!(!svg.contains("href"));
+}
+
+#[test]
+fn test_markdown_literal_when_unmatched() {
+    let input = "sequenceDiagram\n    Alice->>Bob: 2 * 3 and snake_case_name\n";
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    assert!(svg.contains("2 * 3 and snake_case_name"));
+    assert!(!svg.contains("<tspan"));
+}
+
+#[test]
+fn test_markdown_bold_label_widens_gap() {
+    let plain = "sequenceDiagram\n    Alice->>Bob: A fairly long message label here\n";
+    let bold = "sequenceDiagram\n    Alice->>Bob: **A fairly long message label here**\n";
+    let plain_svg = render_sequence_diagram(plain, &RenderOptions::default()).unwrap();
+    let bold_svg = render_sequence_diagram(bold, &RenderOptions::default()).unwrap();
+
+    let plain_width: f64 = attr_value(&plain_svg, "width").unwrap().parse().unwrap();
+    let bold_width: f64 = attr_value(&bold_svg, "width").unwrap().parse().unwrap();
+    assert!(bold_width > plain_width);
+}
+
+// ============================================
+// Escape Code and HTML Subset Tests
+// ============================================
+
+#[test]
+fn test_mermaid_escape_codes_decoded() {
+    let input = "sequenceDiagram\n    Alice->>Bob: a #59; b #quot;quoted#quot; #9829;\n";
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    maybe_save_svg(&svg, "light", "escape_codes");
+
+    assert!(svg.contains("a ; b &quot;quoted&quot; \u{2665}"));
+    assert!(!svg.contains("#59;"));
+}
+
+#[test]
+fn test_html_entities_decoded_once() {
+    let input = "sequenceDiagram\n    Alice->>Bob: List&lt;T&gt; &amp;lt;\n";
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    // Decoded, then escaped again exactly once for XML
+    assert!(svg.contains(">List&lt;T&gt; &amp;lt;</text>"));
+}
+
+#[test]
+fn test_html_subset_tags() {
+    let input =
+        "sequenceDiagram\n    Alice->>Bob: <b>H</b><sub>2</sub>O and x<sup>2</sup> <i>ok</i>\n";
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    maybe_save_svg(&svg, "light", "html_subset");
+
+    assert!(svg.contains(r#"<tspan font-weight="bold">H</tspan>"#));
+    assert!(svg.contains(r#"<tspan font-size="10" baseline-shift="sub">2</tspan>"#));
+    assert!(svg.contains(r#"<tspan font-size="10" baseline-shift="super">2</tspan>"#));
+    assert!(svg.contains(r#"<tspan font-style="italic">ok</tspan>"#));
+}
+
+#[test]
+fn test_unsupported_html_is_escaped() {
+    let input = "sequenceDiagram\n    Alice->>Bob: <script>x</script> <u>y</u>\n";
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    assert!(!svg.contains("<script"));
+    assert!(svg.contains("&lt;script&gt;x&lt;/script&gt; &lt;u&gt;y&lt;/u&gt;"));
+}
+
+#[test]
+fn test_line_break_variants() {
+    let input = "sequenceDiagram\n    Alice->>Bob: one<br />two<BR>three\n";
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    assert!(svg.contains(">one</text>"));
+    assert!(svg.contains(">two</text>"));
+    assert!(svg.contains(">three</text>"));
+}
+
+// ============================================
+// Math Label Tests
+// ============================================
+
+#[test]
+fn test_math_label() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: weight $$\sum_i w_i^2$$
+"#;
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    maybe_save_svg(&svg, "light", "math_label");
+
+    assert!(svg.contains("\u{2211}"));
+    assert!(svg.contains(r#"baseline-shift="sub""#));
+    assert!(svg.contains(r#"baseline-shift="super">2</tspan>"#));
+    assert!(!svg.contains("$$"));
+    assert!(!svg.contains("\\sum"));
+}
+
+#[test]
+fn test_math_fraction_and_greek() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: $$\frac{\alpha}{2} \leq \Omega$$
+"#;
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    assert!(svg.contains("baseline-shift=\"super\">\u{3B1}</tspan>"));
+    assert!(svg.contains("\u{2044}"));
+    assert!(svg.contains("\u{2264}"));
+    assert!(svg.contains("\u{3A9}"));
+}
+
+#[test]
+fn test_math_label_sized_by_runs() {
+    let short = "sequenceDiagram\n    Alice->>Bob: $$x$$\n";
+    let long =
+        "sequenceDiagram\n    Alice->>Bob: $$x_1 + x_2 + x_3 + x_4 + x_5 + x_6 + x_7 + x_8$$\n";
+    let short_svg = render_sequence_diagram(short, &RenderOptions::default()).unwrap();
+    let long_svg = render_sequence_diagram(long, &RenderOptions::default()).unwrap();
+
+    let short_width: f64 = attr_value(&short_svg, "width").unwrap().parse().unwrap();
+    let long_width: f64 = attr_value(&long_svg, "width").unwrap().parse().unwrap();
+    assert!(long_width > short_width);
+}
+
 // ============================================
 // Transparent Background Tests
 // ============================================
@@ -617,105 +1098,1616 @@ sequenceDiagram
 }
 
 // ============================================
-// Dark Theme Feature Tests
+// Sizing Options Tests
 // ============================================
 
+/// Extract the value of the first occurrence of an attribute
+fn attr_value<'a>(svg: &'a str, attr: &str) -> Option<&'a str> {
+    svg.split(&format!("{}=\"", attr))
+        .nth(1)
+        .and_then(|s| s.split('"').next())
+}
+
 #[test]
-fn test_self_message_dark() {
+fn test_fixed_size_scales_content() {
     let input = r#"
 sequenceDiagram
-    Alice->>Alice: Self call
-    Bob->>Bob: Internal process
+    Alice->>Bob: Hello
 "#;
-    let options = RenderOptions::with_theme(Theme::Dark);
+    let options = RenderOptions::default().with_size(1000, 1000);
     let svg = render_sequence_diagram(input, &options).unwrap();
 
-    maybe_save_svg(&svg, "dark", "self_message");
+    maybe_save_svg(&svg, "light", "fixed_size");
 
-    assert!(svg.contains("Self call"));
-    assert!(svg.contains("Internal process"));
-    assert!(svg.contains("#1a1a2e")); // dark background
+    assert_eq!(attr_value(&svg, "width"), Some("1000"));
+    assert_eq!(attr_value(&svg, "height"), Some("1000"));
+    assert_eq!(attr_value(&svg, "viewBox"), Some("0 0 1000 1000"));
+    assert!(svg.contains("<g transform=\"translate("));
+    // Background still fills the whole canvas
+    assert!(svg.contains(r#"<rect width="100%" height="100%""#));
 }
 
 #[test]
-fn test_multiline_participant_names_dark() {
+fn test_fixed_size_alignment() {
     let input = r#"
 sequenceDiagram
-    participant A as Client<br>Browser
-    participant B as Backend<br/>Server
-    A->>B: HTTP Request
-    B-->>A: JSON Response
+    Alice->>Bob: Hello
 "#;
-    let options = RenderOptions::with_theme(Theme::Dark);
+    let options = RenderOptions::default()
+        .with_size(1000, 1000)
+        .with_alignment(Align::Start, Align::Start);
     let svg = render_sequence_diagram(input, &options).unwrap();
 
-    maybe_save_svg(&svg, "dark", "multiline_participant_names");
-
-    // Should contain both lines of each participant name
-    assert!(svg.contains("Client"));
-    assert!(svg.contains("Browser"));
-    assert!(svg.contains("Backend"));
-    assert!(svg.contains("Server"));
-    // Should have dark theme colors
-    assert!(svg.contains("#1a1a2e")); // dark background
-    assert!(svg.contains("#16213e")); // dark participant bg
+    assert!(svg.contains("<g transform=\"translate(0 0) scale("));
 }
 
 #[test]
-fn test_all_arrow_types_dark() {
+fn test_max_width_constrains_output() {
     let input = r#"
 sequenceDiagram
-    participant A
-    participant B
-    A->>B: Solid with arrowhead
-    B-->>A: Dotted with arrowhead
-    A->B: Solid without arrowhead
-    B-->A: Dotted without arrowhead
-    A-xB: Solid with cross
-    A-)B: Solid async (open arrow)
+    Alice->>Bob: This is a very long message that should expand the gap between participants
 "#;
-    let options = RenderOptions::with_theme(Theme::Dark);
+    let options = RenderOptions::default().with_max_width(200);
     let svg = render_sequence_diagram(input, &options).unwrap();
 
-    maybe_save_svg(&svg, "dark", "all_arrow_types");
-
-    assert!(svg.contains("<svg"));
-    assert!(svg.contains("Solid with arrowhead"));
-    assert!(svg.contains("Dotted with arrowhead"));
-    assert!(svg.contains("#1a1a2e")); // dark background
+    let width: u32 = attr_value(&svg, "width").unwrap().parse().unwrap();
+    assert_eq!(width, 200);
 }
 
 #[test]
-fn test_many_participants_dark() {
+fn test_padding_option() {
     let input = r#"
 sequenceDiagram
-    participant Client
-    participant Gateway
-    participant AuthService
-    participant UserService
-    participant Database
-    Client->>Gateway: Request
-    Gateway->>AuthService: Validate Token
-    AuthService->>Database: Check User
-    Database-->>AuthService: User Data
-    AuthService-->>Gateway: Valid
-    Gateway->>UserService: Get Profile
-    UserService->>Database: Query
-    Database-->>UserService: Profile Data
-    UserService-->>Gateway: Profile
-    Gateway-->>Client: Response
+    Alice->>Bob: Hello
 "#;
-    let options = RenderOptions::with_theme(Theme::Dark);
-    let svg = render_sequence_diagram(input, &options).unwrap();
-
-    maybe_save_svg(&svg, "dark", "many_participants");
+    let default_svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+    let padded_svg =
+        render_sequence_diagram(input, &RenderOptions::default().with_padding(40)).unwrap();
+
+    let default_width: u32 = attr_value(&default_svg, "width").unwrap().parse().unwrap();
+    let padded_width: u32 = attr_value(&padded_svg, "width").unwrap().parse().unwrap();
+    // 20px extra on each side
+    assert_eq!(padded_width, default_width + 40);
+    assert!(padded_svg.contains(r#"<rect x="40" y="40""#));
+}
 
-    assert!(svg.contains("Client"));
+#[test]
+fn test_responsive_output() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: Hello
+"#;
+    let svg = render_sequence_diagram(input, &RenderOptions::default().responsive()).unwrap();
+
+    assert_eq!(attr_value(&svg, "width"), Some("100%"));
+    assert!(svg.contains("style=\"max-width: "));
+}
+
+#[test]
+fn test_invalid_options() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: Hello
+"#;
+    let zero_width = RenderOptions::default().with_width(0);
+    assert!(matches!(
+        render_sequence_diagram(input, &zero_width),
+        Err(RenderError::InvalidOptions(_))
+    ));
+
+    let over_max = RenderOptions::default().with_width(800).with_max_width(400);
+    assert!(matches!(
+        render_sequence_diagram(input, &over_max),
+        Err(RenderError::InvalidOptions(_))
+    ));
+}
+
+// ============================================
+// Font Configuration Tests
+// ============================================
+
+#[test]
+fn test_font_family_applied_to_all_text() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: Hello
+    Bob->>Bob: Think
+"#;
+    let options = RenderOptions::default().with_font_family("Corporate Sans, sans-serif");
+    let svg = render_sequence_diagram(input, &options).unwrap();
+
+    maybe_save_svg(&svg, "light", "font_family");
+
+    let text_count = svg.matches("<text").count();
+    let family_count = svg
+        .matches(r#"font-family="Corporate Sans, sans-serif""#)
+        .count();
+    assert_eq!(text_count, family_count);
+    assert!(!svg.contains("Arial"));
+}
+
+#[test]
+fn test_font_family_is_escaped() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: Hello
+"#;
+    let options = RenderOptions::default().with_font_family(r#""Segoe UI", sans-serif"#);
+    let svg = render_sequence_diagram(input, &options).unwrap();
+
+    assert!(svg.contains(r#"font-family="&quot;Segoe UI&quot;, sans-serif""#));
+}
+
+#[test]
+fn test_per_category_fonts() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: Hello
+"#;
+    let options = RenderOptions::default()
+        .with_participant_font(FontOverride::default().with_size(20).with_weight("bold"))
+        .with_message_font(FontOverride::default().with_family("Courier New, monospace"));
+    let svg = render_sequence_diagram(input, &options).unwrap();
+
+    maybe_save_svg(&svg, "light", "per_category_fonts");
+
+    // Participant labels (top and bottom)
+    assert_eq!(
+        svg.matches(r#"font-size="20" font-family="Arial, sans-serif" font-weight="bold""#)
+            .count(),
+        4
+    );
+    // Message label
+    assert!(svg.contains(r#"font-size="14" font-family="Courier New, monospace""#));
+}
+
+#[test]
+fn test_monospace_font_widens_layout() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: iiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiiii
+"#;
+    let sans = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+    let mono = render_sequence_diagram(
+        input,
+        &RenderOptions::default().with_font_family("monospace"),
+    )
+    .unwrap();
+
+    let sans_width: u32 = attr_value(&sans, "width").unwrap().parse().unwrap();
+    let mono_width: u32 = attr_value(&mono, "width").unwrap().parse().unwrap();
+    assert!(mono_width > sans_width);
+}
+
+#[test]
+fn test_font_file_measurement() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: WWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWWW
+"#;
+    let heuristic = RenderOptions::default().with_font_family("DejaVu Sans");
+    let measured = heuristic
+        .clone()
+        .with_font_file("assets/fonts/DejaVuSans.ttf")
+        .unwrap();
+
+    let heuristic_svg = render_sequence_diagram(input, &heuristic).unwrap();
+    let measured_svg = render_sequence_diagram(input, &measured).unwrap();
+
+    maybe_save_svg(&measured_svg, "light", "font_file_measurement");
+
+    // DejaVu Sans "W" is wider than the 11px heuristic estimate
+    let heuristic_width: u32 = attr_value(&heuristic_svg, "width")
+        .unwrap()
+        .parse()
+        .unwrap();
+    let measured_width: u32 = attr_value(&measured_svg, "width").unwrap().parse().unwrap();
+    assert!(measured_width > heuristic_width);
+}
+
+#[test]
+fn test_font_file_missing() {
+    let result = RenderOptions::default().with_font_file("does/not/exist.ttf");
+    assert!(matches!(result, Err(RenderError::FontError(_))));
+}
+
+#[test]
+fn test_invalid_font_options() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: Hello
+"#;
+    let options = RenderOptions::default().with_message_font(FontOverride::default().with_size(0));
+    assert!(matches!(
+        render_sequence_diagram(input, &options),
+        Err(RenderError::InvalidOptions(_))
+    ));
+}
+
+// ============================================
+// Dark Theme Feature Tests
+// ============================================
+
+#[test]
+fn test_self_message_dark() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Alice: Self call
+    Bob->>Bob: Internal process
+"#;
+    let options = RenderOptions::with_theme(Theme::Dark);
+    let svg = render_sequence_diagram(input, &options).unwrap();
+
+    maybe_save_svg(&svg, "dark", "self_message");
+
+    assert!(svg.contains("Self call"));
+    assert!(svg.contains("Internal process"));
+    assert!(svg.contains("#1a1a2e")); // dark background
+}
+
+#[test]
+fn test_multiline_participant_names_dark() {
+    let input = r#"
+sequenceDiagram
+    participant A as Client<br>Browser
+    participant B as Backend<br/>Server
+    A->>B: HTTP Request
+    B-->>A: JSON Response
+"#;
+    let options = RenderOptions::with_theme(Theme::Dark);
+    let svg = render_sequence_diagram(input, &options).unwrap();
+
+    maybe_save_svg(&svg, "dark", "multiline_participant_names");
+
+    // Should contain both lines of each participant name
+    assert!(svg.contains("Client"));
+    assert!(svg.contains("Browser"));
+    assert!(svg.contains("Backend"));
+    assert!(svg.contains("Server"));
+    // Should have dark theme colors
+    assert!(svg.contains("#1a1a2e")); // dark background
+    assert!(svg.contains("#16213e")); // dark participant bg
+}
+
+#[test]
+fn test_all_arrow_types_dark() {
+    let input = r#"
+sequenceDiagram
+    participant A
+    participant B
+    A->>B: Solid with arrowhead
+    B-->>A: Dotted with arrowhead
+    A->B: Solid without arrowhead
+    B-->A: Dotted without arrowhead
+    A-xB: Solid with cross
+    A-)B: Solid async (open arrow)
+"#;
+    let options = RenderOptions::with_theme(Theme::Dark);
+    let svg = render_sequence_diagram(input, &options).unwrap();
+
+    maybe_save_svg(&svg, "dark", "all_arrow_types");
+
+    assert!(svg.contains("<svg"));
+    assert!(svg.contains("Solid with arrowhead"));
+    assert!(svg.contains("Dotted with arrowhead"));
+    assert!(svg.contains("#1a1a2e")); // dark background
+}
+
+#[test]
+fn test_many_participants_dark() {
+    let input = r#"
+sequenceDiagram
+    participant Client
+    participant Gateway
+    participant AuthService
+    participant UserService
+    participant Database
+    Client->>Gateway: Request
+    Gateway->>AuthService: Validate Token
+    AuthService->>Database: Check User
+    Database-->>AuthService: User Data
+    AuthService-->>Gateway: Valid
+    Gateway->>UserService: Get Profile
+    UserService->>Database: Query
+    Database-->>UserService: Profile Data
+    UserService-->>Gateway: Profile
+    Gateway-->>Client: Response
+"#;
+    let options = RenderOptions::with_theme(Theme::Dark);
+    let svg = render_sequence_diagram(input, &options).unwrap();
+
+    maybe_save_svg(&svg, "dark", "many_participants");
+
+    assert!(svg.contains("Client"));
     assert!(svg.contains("Gateway"));
     assert!(svg.contains("Database"));
     assert!(svg.contains("#1a1a2e")); // dark background
 }
 
+// ============================================
+// Custom Theme Tests
+// ============================================
+
+#[test]
+fn test_custom_theme_colors() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: Hello
+    Bob->>Bob: Think
+"#;
+    let colors = ThemeColors::light()
+        .with_background("#fafafa")
+        .with_participant("#0b3d91", "#072a66", "#ffffff")
+        .with_line("#ff6600")
+        .with_lifeline("#999999")
+        .with_message_text("#123456");
+    let options = RenderOptions::with_theme(Theme::Custom(colors));
+    let svg = render_sequence_diagram(input, &options).unwrap();
+
+    maybe_save_svg(&svg, "custom", "custom_colors");
+
+    assert!(svg.contains(r##"fill="#fafafa""##));
+    assert!(svg.contains(r##"fill="#0b3d91" stroke="#072a66""##));
+    assert!(svg.contains(r##"fill="#ffffff""##)); // participant text
+    assert!(svg.contains(r##"stroke="#ff6600""##)); // arrows
+    assert!(svg.contains(r##"stroke="#999999""##)); // lifelines
+    assert!(svg.contains(r##"fill="#123456""##)); // message text
+}
+
+#[test]
+fn test_custom_theme_widths_and_radius() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: Hello
+"#;
+    let colors = ThemeColors::light()
+        .with_line_width(2.5)
+        .with_border_width(3.0)
+        .with_corner_radius(0.0);
+    let svg =
+        render_sequence_diagram(input, &RenderOptions::default().with_colors(colors)).unwrap();
+
+    assert!(svg.contains(r#"stroke-width="2.5""#));
+    assert!(svg.contains(r#"stroke-width="3" rx="0""#));
+    assert!(!svg.contains(r#"rx="4""#));
+}
+
+#[test]
+fn test_theme_variables() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: Hello
+"#;
+    let variables = ThemeVariables::new()
+        .with("primaryColor", "#0b3d91")
+        .with("primaryTextColor", "#ffffff")
+        .with("signalColor", "#ff6600")
+        .with("signalTextColor", "#123456");
+    let options = RenderOptions::default()
+        .with_theme_variables(&variables)
+        .unwrap();
+    let svg = render_sequence_diagram(input, &options).unwrap();
+
+    maybe_save_svg(&svg, "custom", "theme_variables");
+
+    assert!(svg.contains(r##"fill="#0b3d91""##)); // actorBkg from primaryColor
+    assert!(svg.contains(r##"stroke="#ff6600""##));
+    assert!(svg.contains(r##"fill="#123456""##));
+}
+
+#[test]
+fn test_theme_variables_invalid_color() {
+    let variables = ThemeVariables::new().with("actorBkg", "not a colour");
+    let result = RenderOptions::default().with_theme_variables(&variables);
+
+    match result {
+        Err(RenderError::InvalidOptions(message)) => assert!(message.contains("actorBkg")),
+        other => panic!("expected InvalidOptions, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_custom_theme_invalid_color_rejected() {
+    let input = "sequenceDiagram\n    A->>B: Hi";
+    let colors = ThemeColors::light().with_line("red\"/><script>");
+    let result = render_sequence_diagram(input, &RenderOptions::default().with_colors(colors));
+
+    assert!(matches!(result, Err(RenderError::InvalidOptions(_))));
+}
+
+#[test]
+fn test_mermaid_builtin_themes() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: Hello
+    Bob-->>Alice: Hi
+"#;
+    let expected = [
+        (Theme::Default, "#ececff"),
+        (Theme::Neutral, "#eeeeee"),
+        (Theme::Forest, "#cde498"),
+        (Theme::Base, "#fff4dd"),
+    ];
+    for (theme, actor_bkg) in expected {
+        let name = theme.name();
+        let svg = render_sequence_diagram(input, &RenderOptions::with_theme(theme)).unwrap();
+
+        maybe_save_svg(&svg, name, "builtin_theme");
+
+        assert!(
+            svg.contains(&format!(r#"fill="{}""#, actor_bkg)),
+            "{} theme should fill actors with {}",
+            name,
+            actor_bkg
+        );
+        assert!(svg.contains(r#"rx="3""#));
+    }
+}
+
+#[test]
+fn test_auto_theme_follows_color_scheme() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: Hello
+    Bob-xAlice: Bye
+    Alice->>Alice: Think
+"#;
+    let svg = render_sequence_diagram(input, &RenderOptions::with_theme(Theme::Auto)).unwrap();
+
+    maybe_save_svg(&svg, "auto", "color_scheme");
+
+    assert!(svg.contains(r#"<svg xmlns="http://www.w3.org/2000/svg" class="msv-auto msv-diagram-"#));
+    assert!(svg.contains("<style>"));
+    assert!(svg.contains("@media (prefers-color-scheme: dark)"));
+    assert!(svg
+        .contains(r#"<rect width="100%" height="100%" class="background msv-fill-background"/>"#));
+    assert!(svg.contains(
+        r#"class="actor actor-top msv-fill-participant-bg msv-stroke-participant-border""#
+    ));
+    assert!(svg.contains(r#"class="messageText msv-fill-message-text""#));
+    assert!(
+        !svg.contains(r##"fill="#"##),
+        "auto theme should not inline fills"
+    );
+    assert!(
+        !svg.contains(r##"stroke="#"##),
+        "auto theme should not inline strokes"
+    );
+}
+
+#[test]
+fn test_static_themes_have_no_stylesheet() {
+    let input = "sequenceDiagram\n    Alice->>Bob: Hello";
+    for theme in [Theme::Light, Theme::Dark, Theme::Default] {
+        let svg = render_sequence_diagram(input, &RenderOptions::with_theme(theme)).unwrap();
+        assert!(!svg.contains("<style>"));
+        assert!(!svg.contains("msv-"));
+    }
+}
+
+#[test]
+fn test_configured_auto_theme_without_adaptive_output() {
+    let body = "sequenceDiagram\n    Alice->>Bob: Hello";
+    let light = render_sequence_diagram(body, &RenderOptions::default()).unwrap();
+    let options = RenderOptions::with_theme(Theme::Dark).without_adaptive_theme();
+
+    for config in [
+        "%%{init: {'theme': 'auto'}}%%\n",
+        "---\nconfig:\n  theme: auto\n---\n",
+    ] {
+        let input = format!("{}{}", config, body);
+        let svg = render_sequence_diagram(&input, &options).unwrap();
+        assert!(!svg.contains("msv-auto"));
+        assert_eq!(svg, light);
+    }
+}
+
+// ============================================
+// Class-Based Styling Tests
+// ============================================
+
+#[test]
+fn test_semantic_classes() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: Hello
+    Bob-->>Alice: Hi
+    Alice-xBob: Bye
+    Bob->>Bob: Think
+"#;
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    maybe_save_svg(&svg, "light", "semantic_classes");
+
+    for class in [
+        r#"class="background""#,
+        r#"class="actor actor-top""#,
+        r#"class="actor actor-bottom""#,
+        r#"class="actor""#,
+        r#"class="actor-line""#,
+        r#"class="messageLine0""#,
+        r#"class="messageLine1""#,
+        r#"class="messageText""#,
+        r#"class="arrowhead""#,
+        r#"class="crosshead""#,
+    ] {
+        assert!(svg.contains(class), "missing {}", class);
+    }
+    // Classes sit alongside the inline colours by default
+    assert!(svg.contains(r##"fill="#ecf0f1" stroke="#333333" class="actor actor-top""##));
+}
+
+#[test]
+fn test_style_block_replaces_inline_colours() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: Hello
+    Bob-->>Alice: Hi
+"#;
+    let options = RenderOptions::with_theme(Theme::Forest).with_style_block();
+    let svg = render_sequence_diagram(input, &options).unwrap();
+
+    maybe_save_svg(&svg, "forest", "style_block");
+
+    assert!(svg.contains("<style>"));
+    assert!(svg.contains(" rect.actor { fill: #cde498; stroke: "));
+    assert!(svg.contains(" .messageLine0, .msv-diagram-"));
+    assert!(!svg.contains(r##"fill="#"##));
+    assert!(!svg.contains(r##"stroke="#"##));
+    assert!(svg.contains(r#"<rect x="20" y="20""#));
+}
+
+#[test]
+fn test_class_def_and_style_statements() {
+    let input = r#"
+sequenceDiagram
+    classDef important fill:#ffe0e0,stroke:#cc0000,color:#990000
+    class Alice important
+    style Bob stroke-dasharray:5 5,font-style:italic
+    Alice->>Bob: Hello
+"#;
+    let output = render_sequence_diagram_with_warnings(input, &RenderOptions::default()).unwrap();
+    let svg = output.svg;
+
+    maybe_save_svg(&svg, "light", "class_def");
+
+    assert!(output.warnings.is_empty(), "{:?}", output.warnings);
+    assert!(svg.contains(" rect.important { fill: #ffe0e0; stroke: #cc0000; }"));
+    assert!(svg.contains(" text.important { fill: #990000; }"));
+    assert!(svg.contains(" rect.msv-style-0 { stroke-dasharray: 5 5; }"));
+    assert!(svg.contains(" text.msv-style-0 { font-style: italic; }"));
+    assert!(svg.contains(r#"class="actor actor-top important""#));
+    assert!(svg.contains(r#"class="actor important""#));
+    assert!(svg.contains(r#"class="actor actor-bottom msv-style-0""#));
+    assert!(!svg.contains("classDef"));
+}
+
+/// Returns the class scoping a diagram's stylesheet
+fn scope_class(svg: &str) -> &str {
+    let start = svg.find("msv-diagram-").expect("no scoped stylesheet");
+    &svg[start..start + "msv-diagram-".len() + 8]
+}
+
+#[test]
+fn test_stylesheets_are_scoped_to_their_diagram() {
+    let first = render_sequence_diagram(
+        "sequenceDiagram\n    style Alice fill:#ff0000\n    Alice->>Bob: Hi",
+        &RenderOptions::with_theme(Theme::Forest)
+            .with_style_block()
+            .with_id_prefix("first"),
+    )
+    .unwrap();
+    let second = render_sequence_diagram(
+        "sequenceDiagram\n    style Carol fill:#0000ff\n    Carol->>Dave: Hi",
+        &RenderOptions::with_theme(Theme::Dark)
+            .with_style_block()
+            .with_id_prefix("second"),
+    )
+    .unwrap();
+
+    // Each root carries its own scope and every rule sits below it
+    let (first_scope, second_scope) = (scope_class(&first), scope_class(&second));
+    assert_ne!(first_scope, second_scope);
+    for (svg, scope) in [(&first, first_scope), (&second, second_scope)] {
+        let css = &svg[svg.find("<style>").unwrap()..svg.find("</style>").unwrap()];
+        for rule in css.lines().skip(1) {
+            assert!(
+                rule.starts_with(&format!(".{} ", scope)),
+                "unscoped rule {}",
+                rule
+            );
+        }
+    }
+    // Generated classes carry the id prefix
+    assert!(first.contains(&format!(
+        ".{} rect.first-msv-style-0 {{ fill: #ff0000; }}",
+        first_scope
+    )));
+    assert!(second.contains(r#"class="actor actor-top second-msv-style-0""#));
+    assert!(!second.contains("first-msv-style-0"));
+
+    // Diagrams with identical rules may share a scope
+    let again = render_sequence_diagram(
+        "sequenceDiagram\n    style Alice fill:#ff0000\n    Alice->>Bob: Hello again",
+        &RenderOptions::with_theme(Theme::Forest)
+            .with_style_block()
+            .with_id_prefix("first"),
+    )
+    .unwrap();
+    assert_eq!(scope_class(&again), first_scope);
+}
+
+#[test]
+fn test_class_statement_warnings() {
+    let input = r#"
+sequenceDiagram
+    class Carol missing
+    style Alice fill:url(x);color:red
+    Alice->>Bob: Hello
+"#;
+    let output = render_sequence_diagram_with_warnings(input, &RenderOptions::default()).unwrap();
+    assert_eq!(
+        output.warnings,
+        vec![
+            "invalid style declaration 'fill:url(x);color:red' ignored",
+            "class 'missing' is not defined",
+            "unknown participant 'Carol' in class or style statement",
+        ]
+    );
+}
+
+// ============================================
+// Sequence Layout Config Tests
+// ============================================
+
+#[test]
+fn test_mirror_actors_disabled() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: Hello
+"#;
+    let mirrored = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+    let options =
+        RenderOptions::default().with_sequence(SequenceConfig::default().with_mirror_actors(false));
+    let svg = render_sequence_diagram(input, &options).unwrap();
+
+    maybe_save_svg(&svg, "light", "no_mirror_actors");
+
+    // Background plus two boxes per participant, or one when not mirrored
+    assert_eq!(mirrored.matches("<rect").count(), 5);
+    assert_eq!(svg.matches("<rect").count(), 3);
+    assert_eq!(svg.matches(">Alice<").count(), 1);
+
+    let mirrored_height: f64 = attr_value(&mirrored, "height").unwrap().parse().unwrap();
+    let height: f64 = attr_value(&svg, "height").unwrap().parse().unwrap();
+    assert!(height < mirrored_height);
+}
+
+#[test]
+fn test_sequence_spacing() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: Hello
+    Bob->>Alice: Hi
+"#;
+    let default_svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+    let compact = RenderOptions::default().with_sequence(
+        SequenceConfig::default()
+            .with_actor_margin(10.0)
+            .with_message_margin(25.0),
+    );
+    let compact_svg = render_sequence_diagram(input, &compact).unwrap();
+    let airy = RenderOptions::default().with_sequence(
+        SequenceConfig::default()
+            .with_actor_size(150.0, 65.0)
+            .with_message_margin(80.0),
+    );
+    let airy_svg = render_sequence_diagram(input, &airy).unwrap();
+
+    maybe_save_svg(&compact_svg, "light", "compact_spacing");
+    maybe_save_svg(&airy_svg, "light", "airy_spacing");
+
+    let size = |svg: &str| -> (f64, f64) {
+        (
+            attr_value(svg, "width").unwrap().parse().unwrap(),
+            attr_value(svg, "height").unwrap().parse().unwrap(),
+        )
+    };
+    let (default_w, default_h) = size(&default_svg);
+    let (compact_w, compact_h) = size(&compact_svg);
+    let (airy_w, airy_h) = size(&airy_svg);
+    assert!(compact_w <= default_w && compact_h < default_h);
+    assert!(airy_w > default_w && airy_h > default_h);
+    assert!(airy_svg.contains(r#"width="150" height="65""#));
+}
+
+#[test]
+fn test_sequence_config_from_directive() {
+    let input = r#"%%{init: {'sequence': {'mirrorActors': false}}}%%
+sequenceDiagram
+    Alice->>Bob: Hello
+"#;
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    assert_eq!(svg.matches(">Alice<").count(), 1);
+}
+
+#[test]
+fn test_sequence_config_rejects_negative_spacing() {
+    let input = "sequenceDiagram\n    A->>B: Hi";
+    let options =
+        RenderOptions::default().with_sequence(SequenceConfig::default().with_message_margin(-5.0));
+
+    assert!(matches!(
+        render_sequence_diagram(input, &options),
+        Err(RenderError::InvalidOptions(_))
+    ));
+}
+
+// ============================================
+// Diagram Config Tests
+// ============================================
+
+#[test]
+fn test_init_directive_sets_theme() {
+    let input = r#"%%{init: {'theme': 'forest'}}%%
+sequenceDiagram
+    Alice->>Bob: Hello
+"#;
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    maybe_save_svg(&svg, "config", "init_directive");
+
+    assert!(svg.contains(r##"fill="#cde498""##));
+    assert!(!svg.contains("%%{"));
+}
+
+#[test]
+fn test_front_matter_config() {
+    let input = r#"---
+config:
+  theme: dark
+  sequence:
+    messageFontSize: 20
+---
+sequenceDiagram
+    Alice->>Bob: Hello
+"#;
+    let output = render_sequence_diagram_with_warnings(input, &RenderOptions::default()).unwrap();
+
+    maybe_save_svg(&output.svg, "config", "front_matter");
+
+    assert!(output.warnings.is_empty(), "{:?}", output.warnings);
+    assert!(output.svg.contains("#1a1a2e")); // dark background
+    assert!(output.svg.contains(r#"font-size="20""#));
+}
+
+#[test]
+fn test_init_directive_overrides_caller_options() {
+    let input = r#"%%{init: {'theme': 'base', 'themeVariables': {'primaryColor': '#0b3d91'}}}%%
+sequenceDiagram
+    Alice->>Bob: Hello
+"#;
+    let options = RenderOptions::with_theme(Theme::Dark);
+    let svg = render_sequence_diagram(input, &options).unwrap();
+    assert!(svg.contains(r##"fill="#0b3d91""##));
+    assert!(!svg.contains("#1a1a2e"));
+
+    let svg = render_sequence_diagram(input, &options.ignore_diagram_config()).unwrap();
+    assert!(svg.contains("#1a1a2e"));
+    assert!(!svg.contains("#0b3d91"));
+}
+
+#[test]
+fn test_config_warnings_do_not_fail() {
+    let input = r#"%%{init: {'theme': 'sparkly', 'sequence': {'wrap': true, 'unknownKey': 1}}}%%
+%%{init: {not json}}%%
+sequenceDiagram
+    Alice->>Bob: Hello
+"#;
+    let output = render_sequence_diagram_with_warnings(input, &RenderOptions::default()).unwrap();
+
+    assert!(output.svg.contains("Hello"));
+    assert_eq!(output.warnings.len(), 3, "{:?}", output.warnings);
+    assert!(output.warnings.iter().any(|w| w.contains("sparkly")));
+    assert!(output
+        .warnings
+        .iter()
+        .any(|w| w.contains("sequence.unknownKey")));
+    assert!(output.warnings.iter().any(|w| w.contains("init directive")));
+}
+
+// ============================================
+// Document Tree Tests
+// ============================================
+
+#[test]
+fn test_post_processors_run_in_order() {
+    let input = r#"
+sequenceDiagram
+    Alice->>Bob: Hello
+    Bob-->>Alice: Hi
+"#;
+    let options = RenderOptions::default()
+        .with_post_processor(|svg: &mut Element| {
+            svg.visit_mut(&mut |element| {
+                if element.has_class("messageLine0") || element.has_class("messageLine1") {
+                    element.set_attr("data-kind", "message");
+                }
+            });
+        })
+        .with_post_processor(|svg: &mut Element| {
+            svg.set_attr(
+                "data-messages",
+                svg.descendants()
+                    .iter()
+                    .filter(|e| e.attr("data-kind").is_some())
+                    .count(),
+            );
+        });
+    let svg = render_sequence_diagram(input, &options).unwrap();
+
+    maybe_save_svg(&svg, "light", "post_processed");
+
+    assert!(svg.contains(r#"viewBox="0 0 "#));
+    assert!(svg.contains(r#" data-messages="2">"#));
+    assert_eq!(svg.matches(r#"data-kind="message""#).count(), 2);
+}
+
+#[test]
+fn test_document_layout() {
+    let input = "sequenceDiagram\n    Alice-xBob: Bye";
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    // One element per line, indented by two spaces per level
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.ends_with("\n</svg>"));
+    for line in svg.lines().skip(1).take_while(|line| *line != "</svg>") {
+        let indent = line.len() - line.trim_start().len();
+        assert!(indent >= 2 && indent % 2 == 0, "unexpected line {:?}", line);
+        assert!(
+            line.trim_start().starts_with('<'),
+            "unexpected line {:?}",
+            line
+        );
+    }
+    assert!(svg.contains("\n  <g class=\"lifelines\">\n    <g id=\"lifeline-alice\""));
+    assert!(svg.contains("\n      <line "));
+
+    let scaled =
+        render_sequence_diagram(input, &RenderOptions::default().with_width(1000)).unwrap();
+    assert!(scaled.contains("\n  <g transform=\"translate("));
+    assert!(scaled.contains("\n        <line "));
+    assert!(scaled.contains("\n  </g>\n</svg>"));
+}
+
+#[test]
+fn test_groups_have_stable_ids() {
+    let input = r#"
+sequenceDiagram
+    participant Web_Server
+    participant web-server
+    participant 用户
+    Web_Server->>web-server: Forward
+    web-server-->>用户: Reply
+    用户-xWeb_Server: Close
+"#;
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    maybe_save_svg(&svg, "light", "grouped");
+
+    // Layers in drawing order
+    let lifelines = svg.find(r#"<g class="lifelines">"#).unwrap();
+    let participants = svg.find(r#"<g class="participants">"#).unwrap();
+    let messages = svg.find(r#"<g class="messages">"#).unwrap();
+    assert!(lifelines < participants && participants < messages);
+
+    // Participants are keyed by name, with suffixes for clashing slugs
+    assert!(svg.contains(
+        r#"<g id="participant-web-server" class="participant" data-participant="Web_Server" data-index="0">"#
+    ));
+    assert!(svg.contains(r#"<g id="participant-web-server-2" class="participant""#));
+    assert!(svg.contains(r#"<g id="lifeline-p2" class="lifeline" data-participant="用户">"#));
+
+    // Messages are keyed by statement index
+    assert!(svg.contains(
+        r#"<g id="message-0" class="message" data-from="Web_Server" data-to="web-server" data-arrow="solid-closed" data-index="0">"#
+    ));
+    assert!(svg.contains(r#"id="message-1" class="message" data-from="web-server" data-to="用户" data-arrow="dotted-closed""#));
+    assert!(svg.contains(
+        r#"id="message-2" class="message" data-from="用户" data-to="Web_Server" data-arrow="cross""#
+    ));
+
+    // Each message group holds its arrow and label
+    let message = &svg[svg.find(r#"id="message-0""#).unwrap()..];
+    let message = &message[..message.find("</g>").unwrap()];
+    assert!(message.contains("messageLine0"));
+    assert!(message.contains(">Forward</text>"));
+
+    // Ids are unique and stable across renders
+    let ids: Vec<&str> = svg
+        .split(" id=\"")
+        .skip(1)
+        .map(|rest| &rest[..rest.find('"').unwrap()])
+        .collect();
+    let mut unique = ids.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(ids.len(), unique.len());
+    assert_eq!(
+        svg,
+        render_sequence_diagram(input, &RenderOptions::default()).unwrap()
+    );
+
+    let prefixed =
+        render_sequence_diagram(input, &RenderOptions::default().with_id_prefix("login")).unwrap();
+    assert!(prefixed.contains(r#"id="login-message-0""#));
+    assert!(prefixed.contains(r#"id="login-participant-web-server""#));
+}
+
+// ============================================
+// Accessibility Tests
+// ============================================
+
+#[test]
+fn test_acc_title_and_description() {
+    let input = r#"
+sequenceDiagram
+    accTitle: Login & session
+    accDescr {
+        The browser posts credentials
+        and the server answers.
+    }
+    Browser->>Server: POST /login
+"#;
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    maybe_save_svg(&svg, "light", "accessibility");
+
+    assert!(svg.contains(
+        r#"role="graphics-document document" aria-roledescription="sequence" aria-labelledby="diagram-title" aria-describedby="diagram-desc">"#
+    ));
+    // Title and description come first so assistive technology finds them
+    assert!(svg.contains(
+        ">\n  <title id=\"diagram-title\">Login &amp; session</title>\n  <desc id=\"diagram-desc\">The browser posts credentials\nand the server answers.</desc>\n"
+    ));
+    // The statements are not rendered as messages
+    assert_eq!(svg.matches(r#"class="message""#).count(), 1);
+    assert!(!svg.contains("accTitle"));
+}
+
+#[test]
+fn test_description_lines_are_not_statements() {
+    let input = r#"
+sequenceDiagram
+    accDescr {
+        class 101 students sign up,
+        style points count
+    }
+    Student->>Registry: Sign up
+"#;
+    let output = render_sequence_diagram_with_warnings(input, &RenderOptions::default()).unwrap();
+
+    assert!(output.warnings.is_empty(), "{:?}", output.warnings);
+    assert!(output.svg.contains(
+        "<desc id=\"diagram-desc\">class 101 students sign up,\nstyle points count</desc>"
+    ));
+}
+
+#[test]
+fn test_unterminated_description_keeps_messages() {
+    let input = r#"
+sequenceDiagram
+    accDescr {
+        The closing brace is missing
+    Alice->>Bob: Hello
+    Bob-->>Alice: Hi
+"#;
+    let output = render_sequence_diagram_with_warnings(input, &RenderOptions::default()).unwrap();
+
+    assert_eq!(
+        output.warnings,
+        vec!["accDescr block on line 3 is missing its closing '}'"]
+    );
+    assert!(output
+        .svg
+        .contains("<desc id=\"diagram-desc\">The closing brace is missing</desc>"));
+    assert_eq!(output.svg.matches(r#"class="message""#).count(), 2);
+}
+
+#[test]
+fn test_accessible_summary() {
+    let input = r#"
+sequenceDiagram
+    accDescr: Greeting
+    participant A as Alice
+    A->>Bob: Hello **Bob**
+    Bob-->>A: Hi
+    Bob->>Bob: Think
+"#;
+    let options = RenderOptions::default()
+        .with_accessible_summary()
+        .with_id_prefix("greet");
+    let svg = render_sequence_diagram(input, &options).unwrap();
+
+    assert!(svg.contains(r#"aria-describedby="greet-diagram-desc""#));
+    assert!(!svg.contains("aria-labelledby"));
+    assert!(svg.contains(
+        "<desc id=\"greet-diagram-desc\">Greeting\nSequence diagram with 2 participants: Alice, Bob.\nAlice sends &apos;Hello Bob&apos; to Bob.\nBob sends &apos;Hi&apos; to Alice.\nBob sends &apos;Think&apos; to itself.</desc>"
+    ));
+}
+
+#[test]
+fn test_no_title_or_description_by_default() {
+    let svg = render_sequence_diagram("sequenceDiagram\n    A->>B: Hi", &RenderOptions::default())
+        .unwrap();
+    assert!(svg.contains(r#"role="graphics-document document""#));
+    assert!(!svg.contains("<title"));
+    assert!(!svg.contains("<desc"));
+    assert!(!svg.contains("aria-labelledby"));
+}
+
+// ============================================
+// Participant Link Tests
+// ============================================
+
+#[test]
+fn test_participant_links() {
+    let input = r#"
+sequenceDiagram
+    participant Alice
+    participant Bob
+    link Alice: Dashboard @ https://dashboard.example.com/alice
+    links Bob: {"Wiki": "https://wiki.example.com/bob", "Logs": "https://logs.example.com/bob"}
+    Alice->>Bob: Hello
+"#;
+    let output = render_sequence_diagram_with_warnings(input, &RenderOptions::default()).unwrap();
+    let svg = output.svg;
+
+    maybe_save_svg(&svg, "light", "participant_links");
+
+    assert!(output.warnings.is_empty());
+    // A single link wraps the participant, with its label as tooltip
+    assert!(svg.contains(
+        "data-index=\"0\">\n      <a href=\"https://dashboard.example.com/alice\">\n        <title>Dashboard</title>\n        <rect "
+    ));
+    assert_eq!(svg.matches("<a href=").count(), 1);
+    // Several links are listed in a tooltip, in the order they are written
+    assert!(svg.contains(
+        "data-index=\"1\">\n      <title>Wiki: https://wiki.example.com/bob\nLogs: https://logs.example.com/bob</title>"
+    ));
+}
+
+#[test]
+fn test_unsafe_participant_links_are_dropped() {
+    let input = r#"
+sequenceDiagram
+    link Alice: Home @ javascript:alert(1)
+    link Carol: Home @ https://example.com
+    Alice->>Bob: Hello
+"#;
+    let output = render_sequence_diagram_with_warnings(input, &RenderOptions::default()).unwrap();
+
+    assert!(!output.svg.contains("javascript"));
+    assert!(!output.svg.contains("<a "));
+    assert_eq!(
+        output.warnings,
+        vec![
+            "unsafe link URL 'javascript:alert(1)' for 'Alice' ignored",
+            "unknown participant 'Carol' in link statement",
+        ]
+    );
+}
+
+#[test]
+fn test_links_can_be_disabled() {
+    let input = r#"
+sequenceDiagram
+    link Alice: Home @ https://example.com/alice
+    Alice->>Bob: See [docs](https://example.com/docs)
+"#;
+    let linked = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+    assert_eq!(linked.matches("<a href=").count(), 2);
+
+    let output =
+        render_sequence_diagram_with_warnings(input, &RenderOptions::default().without_links())
+            .unwrap();
+    assert!(!output.svg.contains("<a "));
+    assert!(!output.svg.contains("example.com"));
+    assert!(output.svg.contains(">See docs</text>"));
+    assert!(output.warnings.is_empty());
+}
+
+// ============================================
+// Embedded Source Tests
+// ============================================
+
+#[test]
+fn test_embedded_source_round_trip() {
+    let input = r#"%%{init: {'theme': 'forest', 'themeVariables': {'noteBkgColor': '#fafafa'}, 'sequence': {'mirrorActors': false}}}%%
+sequenceDiagram
+    classDef hot fill:#f00
+    class Alice hot
+    Alice->>Bob: a < b && "c"
+"#;
+    let options = RenderOptions::default().with_embedded_source();
+    let svg = render_sequence_diagram(input, &options).unwrap();
+
+    maybe_save_svg(&svg, "forest", "embedded_source");
+
+    assert!(svg.contains(&format!(
+        "<metadata>\n    <msv:diagram xmlns:msv=\"https://github.com/Hebububu/msv\" version=\"{}\">",
+        env!("CARGO_PKG_VERSION")
+    )));
+    // The effective options include the init directive's palette and sequence settings
+    assert!(svg.contains("&quot;theme&quot;:&quot;custom&quot;"));
+    assert!(svg.contains("&quot;note-bg&quot;:&quot;#fafafa&quot;"));
+    assert!(svg.contains("&quot;mirror_actors&quot;:false"));
+    assert_eq!(extract_source(&svg).as_deref(), Some(input));
+
+    // Re-rendering the extracted source gives the same SVG
+    let again = render_sequence_diagram(&extract_source(&svg).unwrap(), &options).unwrap();
+    assert_eq!(again, svg);
+}
+
+#[test]
+fn test_source_not_embedded_by_default() {
+    let svg = render_sequence_diagram("sequenceDiagram\n    A->>B: Hi", &RenderOptions::default())
+        .unwrap();
+    assert!(!svg.contains("<metadata>"));
+    assert_eq!(extract_source(&svg), None);
+}
+
+// ============================================
+// Deterministic Output Tests
+// ============================================
+
+const FIXTURE_DIR: &str = "tests/fixtures";
+
+/// Numbers in the geometric attributes of an SVG
+fn numeric_attribute_values(svg: &str) -> Vec<String> {
+    let attribute = regex::Regex::new(r#" ([A-Za-z-]+)="([^"]*)""#).unwrap();
+    let number = regex::Regex::new(r"-?\d+(\.\d+)?").unwrap();
+    attribute
+        .captures_iter(svg)
+        .filter(|c| NUMERIC_ATTRIBUTES.contains(&&c[1]))
+        .flat_map(|c| {
+            number
+                .find_iter(&c[2])
+                .map(|m| m.as_str().to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn decimals(number: &str) -> usize {
+    number
+        .split_once('.')
+        .map_or(0, |(_, fraction)| fraction.len())
+}
+
+const DETERMINISM_INPUT: &str = r#"
+sequenceDiagram
+    Alice->>Bob: Hello Bob, how are you?
+    Bob-->>Alice: Fine
+    Bob->>Bob: Think
+    Alice-xBob: Bye
+"#;
+
+#[test]
+fn test_output_is_byte_stable() {
+    let options = RenderOptions::default()
+        .with_width(333)
+        .with_accessible_summary();
+    let first = render_sequence_diagram(DETERMINISM_INPUT, &options).unwrap();
+
+    for _ in 0..3 {
+        assert_eq!(
+            render_sequence_diagram(DETERMINISM_INPUT, &options).unwrap(),
+            first
+        );
+    }
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let options = options.clone();
+            std::thread::spawn(move || render_sequence_diagram(DETERMINISM_INPUT, &options))
+        })
+        .collect();
+    for thread in threads {
+        assert_eq!(thread.join().unwrap().unwrap(), first);
+    }
+}
+
+#[test]
+fn test_numbers_are_rounded() {
+    let options = RenderOptions::default().with_width(333);
+    let svg = render_sequence_diagram(DETERMINISM_INPUT, &options).unwrap();
+
+    maybe_save_svg(&svg, "light", "rounded_numbers");
+
+    let numbers = numeric_attribute_values(&svg);
+    assert!(!numbers.is_empty());
+    for number in &numbers {
+        assert!(decimals(number) <= 2, "{} has too many decimals", number);
+        assert!(
+            !(number.contains('.') && number.ends_with('0')),
+            "{} has trailing zeros",
+            number
+        );
+        assert_ne!(number, "-0");
+    }
+    // The content scale keeps full precision
+    assert!(svg.contains(") scale(0."));
+}
+
+#[test]
+fn test_precision_option() {
+    let whole = render_sequence_diagram(
+        DETERMINISM_INPUT,
+        &RenderOptions::default().with_precision(0),
+    )
+    .unwrap();
+    assert!(numeric_attribute_values(&whole)
+        .iter()
+        .all(|n| decimals(n) == 0));
+
+    let fine = render_sequence_diagram(
+        DETERMINISM_INPUT,
+        &RenderOptions::default().with_precision(4),
+    )
+    .unwrap();
+    let numbers = numeric_attribute_values(&fine);
+    assert!(numbers.iter().all(|n| decimals(n) <= 4));
+    assert!(numbers.iter().any(|n| decimals(n) == 4));
+
+    assert!(matches!(
+        render_sequence_diagram(
+            DETERMINISM_INPUT,
+            &RenderOptions::default().with_precision(7)
+        ),
+        Err(RenderError::InvalidOptions(_))
+    ));
+}
+
+/// Compares against a committed rendering; run with UPDATE_FIXTURES=1 after
+/// an intended change to the output
+#[test]
+fn test_output_matches_fixture() {
+    let options = RenderOptions::default().with_width(333);
+    let svg = render_sequence_diagram(DETERMINISM_INPUT, &options).unwrap();
+
+    let path = Path::new(FIXTURE_DIR).join("deterministic.svg");
+    if env::var("UPDATE_FIXTURES").is_ok() {
+        fs::create_dir_all(FIXTURE_DIR).unwrap();
+        fs::write(&path, &svg).unwrap();
+    }
+    let expected = fs::read_to_string(&path).unwrap();
+    assert_eq!(svg, expected);
+}
+
+// ============================================
+// Minified Output Tests
+// ============================================
+
+/// Renders an SVG with the bundled DejaVu Sans standing in for every font
+fn rasterize(svg: &str) -> resvg::tiny_skia::Pixmap {
+    let mut options = resvg::usvg::Options::default();
+    let fontdb = options.fontdb_mut();
+    fontdb.load_font_data(fs::read("assets/fonts/DejaVuSans.ttf").unwrap());
+    fontdb.set_sans_serif_family("DejaVu Sans");
+    options.font_family = "DejaVu Sans".to_string();
+
+    let tree = resvg::usvg::Tree::from_str(svg, &options).unwrap();
+    let size = tree.size().to_int_size();
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(size.width(), size.height()).unwrap();
+    resvg::render(
+        &tree,
+        resvg::tiny_skia::Transform::identity(),
+        &mut pixmap.as_mut(),
+    );
+    pixmap
+}
+
+/// Asserts that two SVGs rasterize to the same image
+///
+/// Antialiasing may differ where merged lines cross, so a few pixels may
+/// differ slightly.
+fn assert_renders_same(expected: &str, actual: &str) {
+    let (expected, actual) = (rasterize(expected), rasterize(actual));
+    let background = expected.pixels()[0];
+    assert!(expected.pixels().iter().any(|p| *p != background));
+    assert_eq!(
+        (expected.width(), expected.height()),
+        (actual.width(), actual.height())
+    );
+
+    let mut differing = 0;
+    for (a, b) in expected.pixels().iter().zip(actual.pixels()) {
+        let channels = [
+            (a.red(), b.red()),
+            (a.green(), b.green()),
+            (a.blue(), b.blue()),
+            (a.alpha(), b.alpha()),
+        ];
+        let delta = channels.iter().map(|(a, b)| a.abs_diff(*b)).max().unwrap();
+        assert!(delta <= 64, "pixel differs by {}", delta);
+        if delta > 0 {
+            differing += 1;
+        }
+    }
+    assert!(
+        differing * 1000 <= expected.pixels().len(),
+        "{} pixels differ",
+        differing
+    );
+}
+
+const MINIFY_INPUT: &str = r#"
+sequenceDiagram
+    participant Alice
+    participant Bob
+    Alice->>Bob: Hello Bob, how are you?
+    Bob-->>Alice: Fine, thanks
+    Alice-)Bob: Async
+    Alice-xBob: Cross
+    Bob->>Bob: Think
+    Bob--xAlice: Dotted cross
+    Alice<<->>Bob: Both ways
+"#;
+
+#[test]
+fn test_minified_output_is_smaller() {
+    let options = RenderOptions::default();
+    let pretty = render_sequence_diagram(MINIFY_INPUT, &options).unwrap();
+    let minified = render_sequence_diagram(MINIFY_INPUT, &options.clone().minified()).unwrap();
+
+    maybe_save_svg(&minified, "light", "minified");
+
+    assert!(minified.len() * 5 < pretty.len() * 4);
+    assert!(!minified.contains('\n'));
+    assert!(minified.contains("<style>.msv-"));
+    // Crossheads are drawn with one path
+    assert!(minified.contains("<path d=\"M2 2L14 14M2 14L14 2\""));
+}
+
+#[test]
+fn test_minified_output_renders_the_same() {
+    let cases = [
+        ("light", RenderOptions::default()),
+        (
+            "inline_arrowheads",
+            RenderOptions::default().with_inline_arrowheads(),
+        ),
+        (
+            "style_block",
+            RenderOptions::with_theme(Theme::Dark).with_style_block(),
+        ),
+        (
+            "scaled",
+            RenderOptions::default().with_width(333).with_precision(1),
+        ),
+    ];
+    for (name, options) in cases {
+        let pretty = render_sequence_diagram(MINIFY_INPUT, &options).unwrap();
+        let minified = render_sequence_diagram(MINIFY_INPUT, &options.minified()).unwrap();
+        maybe_save_svg(&minified, "minified", name);
+        assert_renders_same(&pretty, &minified);
+    }
+}
+
+#[test]
+fn test_minified_output_keeps_class_styles() {
+    let input = r#"
+sequenceDiagram
+    classDef hot fill:#ff0000,stroke-width:4px
+    class Alice hot
+    Alice->>Bob: Hello
+    Bob->>Alice: Hi
+"#;
+    let options = RenderOptions::default();
+    let pretty = render_sequence_diagram(input, &options).unwrap();
+    let minified = render_sequence_diagram(input, &options.clone().minified()).unwrap();
+    assert_renders_same(&pretty, &minified);
+
+    // The embedded source survives minification
+    let embedded = options.with_embedded_source().minified();
+    let svg = render_sequence_diagram(input, &embedded).unwrap();
+    assert_eq!(extract_source(&svg).as_deref(), Some(input));
+}
+
+// ============================================
+// Pretty Output Tests
+// ============================================
+
+/// Lines of `old` missing from `new`, counting repeated lines
+fn removed_lines<'a>(old: &'a str, new: &str) -> Vec<&'a str> {
+    let mut remaining: Vec<&str> = new.lines().collect();
+    old.lines()
+        .filter(|line| match remaining.iter().position(|l| l == line) {
+            Some(index) => {
+                remaining.remove(index);
+                false
+            }
+            None => true,
+        })
+        .collect()
+}
+
+#[test]
+fn test_pretty_output_is_canonical() {
+    let options = RenderOptions::default()
+        .with_style_block()
+        .pretty_printed()
+        .with_post_processor(|svg: &mut Element| {
+            svg.push(Node::Raw(create_self_loop(
+                10.0,
+                10.0,
+                20.0,
+                Paint::Color("#000000"),
+                1.0,
+                LineStyle::Solid,
+                "extra",
+            )));
+        });
+    let svg = render_sequence_diagram(MINIFY_INPUT, &options).unwrap();
+
+    maybe_save_svg(&svg, "light", "pretty");
+
+    assert!(svg.ends_with("</svg>\n"));
+    for line in svg.lines() {
+        let indentation = line.len() - line.trim_start().len();
+        assert_eq!(indentation % 2, 0, "odd indentation: {:?}", line);
+    }
+    // String API markup is indented like the elements around it
+    assert!(svg.contains("\n  <path d=\"M 10 10 Q 30 10 30 25"));
+    assert!(svg.contains("\n  <polygon points=\""));
+    // Stylesheet rules are indented below <style>
+    assert!(svg.contains(" rect.background { fill: #ffffff; }\n"));
+    assert!(svg.contains("<style>\n    .msv-diagram-"));
+    // One attribute order everywhere
+    assert!(svg.contains(
+        "<rect class=\"actor actor-top\" x=\"20\" y=\"20\" width=\"80\" height=\"40\" rx=\"4\" stroke-width=\"1\"/>"
+    ));
+    assert!(svg.contains(
+        "<g id=\"message-0\" class=\"message\" data-arrow=\"solid-closed\" data-from=\"Alice\" data-index=\"0\" data-to=\"Bob\">"
+    ));
+}
+
+#[test]
+fn test_pretty_output_gives_minimal_diffs() {
+    let before =
+        "sequenceDiagram\n    Alice->>Bob: Hello\n    Bob->>Carol: Ping\n    Carol-->>Bob: Pong\n";
+    let options = RenderOptions::default().pretty_printed();
+    let old = render_sequence_diagram(before, &options).unwrap();
+
+    // Changing one label changes one line
+    let relabelled = render_sequence_diagram(&before.replace("Ping", "Pang"), &options).unwrap();
+    let removed = removed_lines(&old, &relabelled);
+    assert_eq!(removed.len(), 1, "{:?}", removed);
+    assert!(removed[0].ends_with(">Ping</text>"));
+    assert_eq!(relabelled.lines().count(), old.lines().count());
+
+    // A new arrowhead adds its marker without moving anything else
+    let crossed =
+        render_sequence_diagram(&before.replace("Bob->>Carol", "Bob-xCarol"), &options).unwrap();
+    let removed = removed_lines(&old, &crossed);
+    assert_eq!(removed.len(), 2, "{:?}", removed);
+    assert!(removed
+        .iter()
+        .all(|line| line.contains("data-arrow=\"solid-closed\"") || line.contains("marker-end")));
+}
+
+#[test]
+fn test_minify_and_pretty_conflict() {
+    let options = RenderOptions::default().minified().pretty_printed();
+    assert!(matches!(
+        render_sequence_diagram("sequenceDiagram\n    A->>B: Hi", &options),
+        Err(RenderError::InvalidOptions(_))
+    ));
+}
+
+// ============================================
+// PNG Output Tests
+// ============================================
+
+#[cfg(feature = "png")]
+mod png_output {
+    use super::*;
+    use mermaid_svg_render::png::{
+        render_sequence_diagram_png, render_sequence_diagram_png_with_warnings, svg_to_png,
+        PngOptions,
+    };
+    use mermaid_svg_render::theme::Color;
+    use resvg::tiny_skia::Pixmap;
+
+    /// Returns the data of the first chunk of type `name`
+    fn chunk<'a>(png: &'a [u8], name: &[u8; 4]) -> Option<&'a [u8]> {
+        let mut offset = 8;
+        while offset + 8 <= png.len() {
+            let len = u32::from_be_bytes(png[offset..offset + 4].try_into().unwrap()) as usize;
+            if &png[offset + 4..offset + 8] == name {
+                return Some(&png[offset + 8..offset + 8 + len]);
+            }
+            offset += len + 12;
+        }
+        None
+    }
+
+    #[test]
+    fn test_png_size_and_density() {
+        let input = "sequenceDiagram\n    Alice->>Bob: Hello";
+        let options = RenderOptions::default();
+        let svg = render_sequence_diagram(input, &options).unwrap();
+        let png =
+            render_sequence_diagram_png(input, &options, &PngOptions::default().with_dpi(192.0))
+                .unwrap();
+
+        let size = regex::Regex::new(r#"width="(\d+)" height="(\d+)""#)
+            .unwrap()
+            .captures(&svg)
+            .map(|c| (c[1].parse::<u32>().unwrap(), c[2].parse::<u32>().unwrap()))
+            .unwrap();
+        let pixmap = Pixmap::decode_png(&png).unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (size.0 * 2, size.1 * 2));
+
+        // 192 dpi is 7559 pixels per meter
+        let density = chunk(&png, b"pHYs").unwrap();
+        assert_eq!(&density[..4], &7559u32.to_be_bytes());
+        assert_eq!(density[8], 1);
+    }
+
+    #[test]
+    fn test_png_renders_text_and_background() {
+        let input = "sequenceDiagram\n    Alice->>Bob: Hello";
+        let options = RenderOptions::with_theme(Theme::Auto).transparent();
+
+        let transparent =
+            render_sequence_diagram_png(input, &options, &PngOptions::default()).unwrap();
+        let pixmap = Pixmap::decode_png(&transparent).unwrap();
+        assert_eq!(pixmap.pixel(0, 0).unwrap().alpha(), 0);
+        // The auto theme falls back to its light palette: dark text and lines
+        assert!(pixmap
+            .pixels()
+            .iter()
+            .any(|p| p.alpha() == 255 && p.red() < 0x40));
+
+        let filled = render_sequence_diagram_png(
+            input,
+            &options,
+            &PngOptions::default().with_background(Color::rgb(255, 255, 0)),
+        )
+        .unwrap();
+        let corner = Pixmap::decode_png(&filled).unwrap().pixel(0, 0).unwrap();
+        assert_eq!((corner.red(), corner.green(), corner.blue()), (255, 255, 0));
+
+        // Also when the diagram selects the auto theme itself
+        let configured = format!(
+            "%%{{init: {{'theme': 'auto', 'colour': 'blue'}}}}%%\n{}",
+            input
+        );
+        let output = render_sequence_diagram_png_with_warnings(
+            &configured,
+            &RenderOptions::default(),
+            &PngOptions::default(),
+        )
+        .unwrap();
+        let light = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+        assert_eq!(
+            output.png,
+            svg_to_png(&light, &PngOptions::default()).unwrap()
+        );
+        assert_eq!(
+            output.warnings,
+            vec!["unsupported config key 'colour' ignored"]
+        );
+
+        assert!(matches!(
+            svg_to_png("not svg", &PngOptions::default()),
+            Err(RenderError::PngError(_))
+        ));
+    }
+}
+
 // ============================================
 // Unicode and International Character Tests
 // ============================================
@@ -739,6 +2731,43 @@ sequenceDiagram
     assert!(svg.contains("你好"));
 }
 
+#[test]
+fn test_cjk_participant_box_width() {
+    let input = r#"
+sequenceDiagram
+    participant A as 데이터베이스서버
+    participant B as 클라이언트
+    A->>B: 응답
+"#;
+    let svg = render_sequence_diagram(input, &RenderOptions::default()).unwrap();
+
+    maybe_save_svg(&svg, "light", "cjk_participants");
+
+    // 8 full-width characters at 14px plus 20px padding
+    let width_pattern = regex::Regex::new(r#"<rect x="[^"]+" y="[^"]+" width="(\d+)""#).unwrap();
+    let width: u32 = width_pattern.captures(&svg).unwrap()[1].parse().unwrap();
+    assert_eq!(width, 8 * 14 + 20);
+}
+
+#[test]
+fn test_cjk_message_expands_gap() {
+    let latin = r#"
+sequenceDiagram
+    Alice->>Bob: aaaaaaaaaaaaaaaaaaaaaaaaaaaaaa
+"#;
+    let cjk = r#"
+sequenceDiagram
+    Alice->>Bob: 가가가가가가가가가가가가가가가가가가가가가가가가가가가가가가
+"#;
+    let latin_svg = render_sequence_diagram(latin, &RenderOptions::default()).unwrap();
+    let cjk_svg = render_sequence_diagram(cjk, &RenderOptions::default()).unwrap();
+
+    let latin_width: u32 = attr_value(&latin_svg, "width").unwrap().parse().unwrap();
+    let cjk_width: u32 = attr_value(&cjk_svg, "width").unwrap().parse().unwrap();
+    // Same character count, but Hangul syllables are twice as wide
+    assert!(cjk_width > latin_width);
+}
+
 #[test]
 fn test_emoji_in_messages() {
     let input = r#"